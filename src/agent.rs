@@ -0,0 +1,735 @@
+use crate::app::NotesApp;
+use crate::crypto::{self, create_private_dir, write_private, Key};
+use crate::paths::DataPaths;
+use crate::utils::{from_hex, hash_bytes, to_hex};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+/// Marks an encrypted store and holds its passphrase salt
+const STORE_FILE: &str = "store.json";
+const SEALED_DIR: &str = "sealed";
+const SOCKET_FILE: &str = "agent.sock";
+/// Errors the agent could not report to a client, appended next to the sealed store
+const LOG_FILE: &str = "agent.log";
+/// Socket of the agent holding the keys of unlocked encrypted notes, in the unlocked directory
+const KEYS_SOCKET: &str = "keys.sock";
+
+/// How long an encrypted note stays unlocked before the key agent locks it again, unless
+/// `NOTES_KEY_TIMEOUT` sets another number of minutes
+const NOTE_KEY_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+const NOTE_KEY_TIMEOUT_ENV: &str = "NOTES_KEY_TIMEOUT";
+/// Name of the sealed blob listing every file in the store
+const MANIFEST_BLOB: &str = "manifest";
+/// Known plaintext sealed into `store.json` to check passphrases
+const CHECK_TOKEN: &[u8] = b"notes store";
+const STORE_FORMAT: u32 = 1;
+
+/// How often the agent writes workspace changes back to the sealed store
+const SYNC_INTERVAL: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long a client may take to send its request before the agent drops the connection
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Workspace entries that are never sealed: per-note plaintext and the daemon's pid
+const UNSEALED: &[&str] = &["unlocked", "daemon.pid"];
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoreMeta {
+    format: u32,
+    salt: String,
+    check: String,
+}
+
+impl StoreMeta {
+    fn load(home: &Path) -> Result<Self> {
+        let path = home.join(STORE_FILE);
+        let content =
+            fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let meta: Self = serde_json::from_slice(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        if meta.format > STORE_FORMAT {
+            bail!("Unsupported store format {}", meta.format);
+        }
+        Ok(meta)
+    }
+
+    /// Derives the store key, failing on a wrong passphrase.
+    fn key(&self, passphrase: &str) -> Result<Key> {
+        let key = Key::derive(passphrase, &self.salt)?;
+        key.decrypt(&from_hex(&self.check)?)?;
+        Ok(key)
+    }
+}
+
+/// Encrypted copy of the store: one blob per file, named by a keyed hash of its path, and
+/// a manifest mapping paths to plaintext hashes.
+struct SealedStore {
+    dir: PathBuf,
+    key: Key,
+    manifest: BTreeMap<String, String>,
+}
+
+impl SealedStore {
+    fn open(home: &Path, key: Key) -> Result<Self> {
+        let dir = home.join(SEALED_DIR);
+        let manifest_path = dir.join(MANIFEST_BLOB);
+        let manifest = if manifest_path.exists() {
+            let sealed = fs::read(&manifest_path)
+                .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+            serde_json::from_slice(&key.decrypt(&sealed)?).context("Failed to parse manifest")?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { dir, key, manifest })
+    }
+
+    fn blob_path(&self, rel: &str) -> PathBuf {
+        self.dir.join(self.key.mac(rel.as_bytes()))
+    }
+
+    /// Decrypts every file into `workspace`.
+    fn restore(&self, workspace: &Path) -> Result<()> {
+        create_private_dir(workspace)?;
+        for rel in self.manifest.keys() {
+            let blob = self.blob_path(rel);
+            let sealed =
+                fs::read(&blob).with_context(|| format!("Failed to read {}", blob.display()))?;
+            let content = self
+                .key
+                .decrypt(&sealed)
+                .with_context(|| format!("Failed to decrypt {}", rel))?;
+            write_private(&workspace.join(rel), &content)?;
+        }
+        Ok(())
+    }
+
+    /// Seals files of `root` that changed since the last sync and drops blobs of removed
+    /// files. Returns the number of files written or removed.
+    fn sync(&mut self, root: &Path) -> Result<usize> {
+        let mut files = Vec::new();
+        collect_files(root, root, &mut files)?;
+
+        let mut changed = 0;
+        let mut seen = BTreeMap::new();
+        for rel in files {
+            let path = root.join(&rel);
+            let Ok(content) = fs::read(&path) else {
+                continue;
+            };
+            let hash = hash_bytes(&content);
+            if self.manifest.get(&rel) != Some(&hash) {
+                self.write_blob(&self.blob_path(&rel), &content)?;
+                changed += 1;
+            }
+            seen.insert(rel, hash);
+        }
+        for rel in self.manifest.keys().filter(|rel| !seen.contains_key(*rel)) {
+            let blob = self.blob_path(rel);
+            if blob.exists() {
+                fs::remove_file(&blob)
+                    .with_context(|| format!("Failed to remove {}", blob.display()))?;
+            }
+            changed += 1;
+        }
+
+        if changed > 0 || !self.dir.join(MANIFEST_BLOB).exists() {
+            self.manifest = seen;
+            let manifest = serde_json::to_vec(&self.manifest)?;
+            self.write_blob(&self.dir.join(MANIFEST_BLOB), &manifest)?;
+        }
+        Ok(changed)
+    }
+
+    /// Seals `content` into `path` through a temporary file, so a crash never leaves a
+    /// half-written blob.
+    fn write_blob(&self, path: &Path, content: &[u8]) -> Result<()> {
+        let temp = path.with_extension("tmp");
+        write_private(&temp, &self.key.encrypt(content)?)?;
+        fs::rename(&temp, path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Whether `home` holds an encrypted store.
+pub(crate) fn is_encrypted_store(home: &Path) -> bool {
+    home.join(STORE_FILE).exists()
+}
+
+/// Plaintext root of an encrypted store, as served by the running agent.
+pub(crate) fn workspace(home: &Path) -> Result<PathBuf> {
+    match request(home, "status")? {
+        Some(reply) => parse_status(&reply).map(|(_, workspace)| workspace),
+        None => bail!("The notes store is encrypted and locked. Run `notes unlock` first."),
+    }
+}
+
+/// When the running agent locks the store, or `None` when it is locked.
+pub(crate) fn unlocked_until(home: &Path) -> Result<Option<DateTime<Utc>>> {
+    request(home, "status")?
+        .map(|reply| parse_status(&reply).map(|(until, _)| until))
+        .transpose()
+}
+
+/// Seals all of `home` into an encrypted store and removes the plaintext files. Returns the
+/// number of files sealed.
+pub fn encrypt_store(home: &Path, passphrase: &str) -> Result<usize> {
+    if is_encrypted_store(home) {
+        bail!("{} is already encrypted", home.display());
+    }
+    let unlocked = home.join("unlocked");
+    if fs::read_dir(&unlocked).is_ok_and(|mut entries| entries.next().is_some()) {
+        bail!("Some encrypted notes are unlocked. Run `notes lock` first.");
+    }
+
+    // The daemon would keep writing plaintext next to the sealed copy.
+    stop_daemon(home);
+
+    let salt = crypto::new_salt();
+    let key = Key::derive(passphrase, &salt)?;
+    let meta = StoreMeta {
+        format: STORE_FORMAT,
+        salt,
+        check: to_hex(&key.encrypt(CHECK_TOKEN)?),
+    };
+
+    let mut files = Vec::new();
+    collect_files(home, home, &mut files)?;
+    let mut store = SealedStore::open(home, key)?;
+    let count = store.sync(home)?;
+
+    // Only written once everything is sealed, so a failure leaves the plaintext store usable.
+    fs::write(home.join(STORE_FILE), serde_json::to_string_pretty(&meta)?)
+        .with_context(|| format!("Failed to write {}", home.join(STORE_FILE).display()))?;
+    for rel in files {
+        fs::remove_file(home.join(&rel))
+            .with_context(|| format!("Failed to remove {}", rel))?;
+    }
+    remove_empty_dirs(home)?;
+    Ok(count)
+}
+
+/// Checks the passphrase and starts an agent that decrypts the store into a private
+/// workspace and keeps the key in memory for `timeout`. With `on_disk`, the workspace may
+/// fall back to `NOTES_HOME` when there is no runtime directory. Returns the workspace.
+pub fn unlock(home: &Path, passphrase: &str, timeout: Duration, on_disk: bool) -> Result<PathBuf> {
+    let meta = StoreMeta::load(home)?;
+    let key = meta.key(passphrase)?;
+
+    let exe = env::current_exe().context("Failed to resolve current executable")?;
+    let mut args = vec!["agent".to_string(), "--timeout".to_string(), timeout.as_secs().to_string()];
+    if on_disk {
+        args.push("--on-disk".to_string());
+    }
+    let mut child = std::process::Command::new(exe)
+        .args(args)
+        .env("NOTES_HOME", home)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to start notes agent")?;
+
+    let mut stdin = child.stdin.take().ok_or_else(|| anyhow!("Agent has no stdin"))?;
+    writeln!(stdin, "{}", key.to_hex())?;
+    drop(stdin);
+
+    // The agent answers with one line once it listens, or with the reason it could not.
+    let mut reply = String::new();
+    let stdout = child.stdout.take().ok_or_else(|| anyhow!("Agent has no stdout"))?;
+    BufReader::new(stdout).read_line(&mut reply)?;
+    match reply.trim().strip_prefix("ready ") {
+        Some(workspace) => Ok(PathBuf::from(workspace)),
+        None => {
+            let _ = child.wait();
+            bail!("Notes agent failed: {}", reply.trim())
+        }
+    }
+}
+
+/// Asks the agent to seal the store, wipe the workspace and exit. Returns false when no
+/// agent was running.
+pub fn lock(home: &Path) -> Result<bool> {
+    Ok(request(home, "lock")?.is_some())
+}
+
+/// Asks the agent to seal workspace changes now. Does nothing for a plaintext store.
+pub(crate) fn sync(home: &Path) -> Result<()> {
+    if !is_encrypted_store(home) {
+        return Ok(());
+    }
+    match request(home, "sync")? {
+        Some(reply) if reply.starts_with("error") => bail!("Notes agent: {}", reply),
+        _ => Ok(()),
+    }
+}
+
+/// Runs the agent: reads the key from stdin, decrypts the store and serves `status`, `sync`
+/// and `lock` on a Unix socket in `home` until locked or `timeout` passes.
+pub fn run_agent(home: &Path, timeout: Duration, on_disk: bool) -> Result<()> {
+    let mut hex = String::new();
+    std::io::stdin().read_line(&mut hex)?;
+    let key = Key::from_hex(hex.trim())?;
+
+    let served = serve(home, key, timeout, on_disk);
+    if let Err(err) = &served {
+        // Before `ready` this reaches `notes unlock`; afterwards nobody reads stdout.
+        println!("{:#}", err);
+        log_error(home, err);
+    }
+    served
+}
+
+fn serve(home: &Path, key: Key, timeout: Duration, on_disk: bool) -> Result<()> {
+    let workspace = workspace_dir(home, on_disk)?;
+    let mut store = SealedStore::open(home, key)?;
+    // Bound before the workspace is touched, so an agent never restores over a running one's.
+    let socket = home.join(SOCKET_FILE);
+    let listener = bind_private(&socket)?;
+    if let Err(err) = restore_workspace(&mut store, &workspace) {
+        let _ = fs::remove_file(&socket);
+        return Err(err);
+    }
+
+    let served = listen(home, listener, &mut store, &workspace, timeout);
+
+    // The only way out once the workspace holds plaintext: whether the agent was locked,
+    // timed out or failed, seal the workspace, wipe it and remove the socket.
+    let _ = fs::remove_file(&socket);
+    stop_daemon(&workspace);
+    let sealed = store.sync(&workspace);
+    let wiped = fs::remove_dir_all(&workspace)
+        .with_context(|| format!("Failed to remove {}", workspace.display()));
+    let locked_by = served.as_ref().ok().and_then(Option::as_ref);
+    // Answered only now, so `notes lock` returns once the plaintext is gone.
+    if let Some(mut stream) = locked_by {
+        let _ = match (&sealed, &wiped) {
+            (Err(err), _) | (_, Err(err)) => writeln!(stream, "error {:#}", err),
+            _ => writeln!(stream, "ok"),
+        };
+    }
+    served?;
+    sealed?;
+    wiped
+}
+
+/// Serves requests on `socket` until a `lock` request, which is returned unanswered, or
+/// until `timeout` passes. A failing connection or sync is logged and does not end it.
+fn listen(
+    home: &Path,
+    listener: UnixListener,
+    store: &mut SealedStore,
+    workspace: &Path,
+    timeout: Duration,
+) -> Result<Option<UnixStream>> {
+    let until = Utc::now() + chrono::Duration::from_std(timeout)?;
+    let deadline = Instant::now() + timeout;
+    println!("ready {}", workspace.display());
+    std::io::stdout().flush()?;
+
+    let mut last_sync = Instant::now();
+    while Instant::now() < deadline {
+        match listener.accept() {
+            Ok((stream, _)) => match handle(stream, store, workspace, until) {
+                Ok(Some(locked_by)) => return Ok(Some(locked_by)),
+                Ok(None) => {}
+                Err(err) => log_error(home, &err.context("Failed to answer a request")),
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(err) => return Err(err.into()),
+        }
+        if last_sync.elapsed() >= SYNC_INTERVAL {
+            if let Err(err) = store.sync(workspace) {
+                log_error(home, &err);
+            }
+            last_sync = Instant::now();
+        }
+    }
+    Ok(None)
+}
+
+/// Answers one request. Returns the connection of a `lock` request, which is answered once
+/// the store is sealed.
+fn handle(
+    stream: UnixStream,
+    store: &mut SealedStore,
+    workspace: &Path,
+    until: DateTime<Utc>,
+) -> Result<Option<UnixStream>> {
+    let line = read_request(&stream)?;
+    let mut stream = stream;
+
+    match line.as_str() {
+        "status" => {
+            writeln!(stream, "unlocked {} {}", until.to_rfc3339(), workspace.display())?;
+            Ok(None)
+        }
+        "sync" => {
+            match store.sync(workspace) {
+                Ok(_) => writeln!(stream, "ok")?,
+                Err(err) => writeln!(stream, "error {:#}", err)?,
+            }
+            Ok(None)
+        }
+        "lock" => Ok(Some(stream)),
+        other => {
+            writeln!(stream, "error unknown request {}", other)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Key of an unlocked encrypted note, as held by the key agent; `None` when it is locked.
+pub(crate) fn note_key(paths: &DataPaths, slug: &str) -> Result<Option<Key>> {
+    let socket = paths.unlocked.join(KEYS_SOCKET);
+    match request_at(&socket, &format!("key {}", slug))? {
+        Some(reply) => match reply.strip_prefix("key ") {
+            Some(hex) => Key::from_hex(hex).map(Some),
+            None => Ok(None),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Hands the key of a note that was just unlocked to the key agent, starting the agent when
+/// none is running. The agent locks the note again after `note_key_timeout`.
+pub(crate) fn hold_note_key(paths: &DataPaths, slug: &str, key: &Key) -> Result<()> {
+    let socket = paths.unlocked.join(KEYS_SOCKET);
+    let hold = format!("hold {} {}", slug, key.to_hex());
+    if request_at(&socket, &hold)?.as_deref() == Some("ok") {
+        return Ok(());
+    }
+
+    create_private_dir(&paths.unlocked)?;
+    let exe = env::current_exe().context("Failed to resolve current executable")?;
+    let timeout = note_key_timeout()?.as_secs().to_string();
+    let mut child = std::process::Command::new(exe)
+        .args(["agent", "--notes", "--timeout", &timeout])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to start notes key agent")?;
+
+    let mut stdin = child.stdin.take().ok_or_else(|| anyhow!("Agent has no stdin"))?;
+    writeln!(stdin, "{} {}", slug, key.to_hex())?;
+    drop(stdin);
+
+    let mut reply = String::new();
+    let stdout = child.stdout.take().ok_or_else(|| anyhow!("Agent has no stdout"))?;
+    BufReader::new(stdout).read_line(&mut reply)?;
+    if reply.trim() != "ready" {
+        let _ = child.wait();
+        bail!("Notes key agent failed: {}", reply.trim());
+    }
+    Ok(())
+}
+
+/// How long a newly started key agent keeps notes unlocked: `NOTES_KEY_TIMEOUT` minutes, or
+/// `NOTE_KEY_TIMEOUT`.
+fn note_key_timeout() -> Result<Duration> {
+    let Ok(minutes) = env::var(NOTE_KEY_TIMEOUT_ENV) else {
+        return Ok(NOTE_KEY_TIMEOUT);
+    };
+    match minutes.trim().parse::<u64>() {
+        Ok(minutes) if minutes > 0 => Ok(Duration::from_secs(minutes * 60)),
+        _ => bail!(
+            "{} must be a positive number of minutes, got {:?}",
+            NOTE_KEY_TIMEOUT_ENV,
+            minutes
+        ),
+    }
+}
+
+/// Drops the key of a note that was locked or deleted; nothing to do when no agent holds it.
+pub(crate) fn forget_note_key(paths: &DataPaths, slug: &str) -> Result<()> {
+    request_at(&paths.unlocked.join(KEYS_SOCKET), &format!("forget {}", slug))?;
+    Ok(())
+}
+
+/// Runs the key agent: reads the first note's id and key from stdin and serves `key`, `hold`
+/// and `forget` until it holds no keys. Each note is locked again `timeout` after it was
+/// unlocked, and every note still held is locked when the agent fails.
+pub fn run_key_agent(timeout: Duration) -> Result<()> {
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    let (slug, hex) = line
+        .trim()
+        .split_once(' ')
+        .ok_or_else(|| anyhow!("Expected a note id and key"))?;
+    let mut keys = BTreeMap::new();
+    keys.insert(slug.to_string(), (Key::from_hex(hex)?, Instant::now() + timeout));
+
+    let paths = DataPaths::new()?;
+    let socket = paths.unlocked.join(KEYS_SOCKET);
+    let listener = match bind_private(&socket) {
+        Ok(listener) => listener,
+        Err(err) => {
+            println!("{:#}", err);
+            return Err(err);
+        }
+    };
+    println!("ready");
+    std::io::stdout().flush()?;
+
+    let served = serve_keys(&paths, listener, &mut keys, timeout);
+    // Removed before the listener closes, so no client waits on a socket nobody answers.
+    let _ = fs::remove_file(&socket);
+    for (slug, (key, _)) in keys {
+        if let Err(err) = lock_note(&slug, key) {
+            log_error(&paths.root, &err);
+        }
+    }
+    if let Err(err) = &served {
+        log_error(&paths.root, err);
+    }
+    served
+}
+
+fn serve_keys(
+    paths: &DataPaths,
+    listener: UnixListener,
+    keys: &mut BTreeMap<String, (Key, Instant)>,
+    timeout: Duration,
+) -> Result<()> {
+    while !keys.is_empty() {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if let Err(err) = handle_keys(stream, keys, timeout) {
+                    log_error(&paths.root, &err.context("Failed to answer a request"));
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        let now = Instant::now();
+        let expired: Vec<String> = keys
+            .iter()
+            .filter(|(_, (_, until))| *until <= now)
+            .map(|(slug, _)| slug.clone())
+            .collect();
+        for slug in expired {
+            if let Some((key, _)) = keys.remove(&slug)
+                && let Err(err) = lock_note(&slug, key)
+            {
+                log_error(&paths.root, &err);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decrypts the store into `workspace`, first sealing whatever an agent that did not exit
+/// cleanly left there.
+fn restore_workspace(store: &mut SealedStore, workspace: &Path) -> Result<()> {
+    if workspace.exists() {
+        // It may hold unsealed edits.
+        store.sync(workspace)?;
+        fs::remove_dir_all(workspace)
+            .with_context(|| format!("Failed to remove {}", workspace.display()))?;
+    }
+    if let Err(err) = store.restore(workspace) {
+        let _ = fs::remove_dir_all(workspace);
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Answers one request to the key agent.
+fn handle_keys(
+    stream: UnixStream,
+    keys: &mut BTreeMap<String, (Key, Instant)>,
+    timeout: Duration,
+) -> Result<()> {
+    let line = read_request(&stream)?;
+    let mut stream = stream;
+
+    let mut parts = line.splitn(3, ' ');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("key"), Some(slug), None) => match keys.get(slug) {
+            Some((key, _)) => writeln!(stream, "key {}", key.to_hex())?,
+            None => writeln!(stream, "none")?,
+        },
+        (Some("hold"), Some(slug), Some(hex)) => {
+            keys.insert(slug.to_string(), (Key::from_hex(hex)?, Instant::now() + timeout));
+            writeln!(stream, "ok")?;
+        }
+        (Some("forget"), Some(slug), None) => {
+            keys.remove(slug);
+            writeln!(stream, "ok")?;
+        }
+        (request, _, _) => writeln!(stream, "error unknown request {}", request.unwrap_or(""))?,
+    }
+    Ok(())
+}
+
+/// Snapshots and removes the working copy of a note whose key the agent is dropping.
+fn lock_note(slug: &str, key: Key) -> Result<()> {
+    let mut app = NotesApp::load()?;
+    app.seal_unlocked(slug, key)
+        .with_context(|| format!("Failed to lock {}", slug))?;
+    app.save()
+}
+
+/// Listens on `socket` for the current user only. A socket file nobody answers on is left
+/// over from an agent that did not exit cleanly and is replaced; a live one is refused.
+fn bind_private(socket: &Path) -> Result<UnixListener> {
+    use std::io::ErrorKind;
+    use std::os::unix::fs::PermissionsExt;
+
+    match UnixStream::connect(socket) {
+        Ok(_) => bail!("Another agent is already listening on {}", socket.display()),
+        Err(err) if err.kind() == ErrorKind::NotFound => {}
+        Err(err) if err.kind() == ErrorKind::ConnectionRefused => {
+            fs::remove_file(socket)
+                .with_context(|| format!("Failed to remove stale {}", socket.display()))?;
+        }
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to check {}", socket.display()));
+        }
+    }
+    let listener = UnixListener::bind(socket)
+        .with_context(|| format!("Failed to listen on {}", socket.display()))?;
+    fs::set_permissions(socket, fs::Permissions::from_mode(0o600))?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+/// Reads a client's one-line request, giving up after `REQUEST_TIMEOUT`.
+fn read_request(stream: &UnixStream) -> Result<String> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .context("Failed to read request")?;
+    Ok(line.trim().to_string())
+}
+
+/// Sends one request to the store agent; `None` when no agent is listening.
+fn request(home: &Path, command: &str) -> Result<Option<String>> {
+    request_at(&home.join(SOCKET_FILE), command)
+}
+
+/// Sends one request to the agent listening on `socket`; `None` when none is.
+fn request_at(socket: &Path, command: &str) -> Result<Option<String>> {
+    let Ok(mut stream) = UnixStream::connect(socket) else {
+        return Ok(None);
+    };
+    writeln!(stream, "{}", command)?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+    Ok(Some(reply.trim().to_string()))
+}
+
+fn parse_status(reply: &str) -> Result<(DateTime<Utc>, PathBuf)> {
+    let mut parts = reply.splitn(3, ' ');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("unlocked"), Some(until), Some(workspace)) => Ok((
+            DateTime::parse_from_rfc3339(until)?.with_timezone(&Utc),
+            PathBuf::from(workspace),
+        )),
+        _ => bail!("Unexpected reply from notes agent: {}", reply),
+    }
+}
+
+/// Where the agent decrypts the store: `NOTES_UNLOCKED_DIR/store`, else a per-store directory
+/// in the runtime directory (usually a tmpfs). `NOTES_HOME/unlocked/store` would put the
+/// plaintext on persistent disk, so it is only used with `on_disk`.
+fn workspace_dir(home: &Path, on_disk: bool) -> Result<PathBuf> {
+    if let Ok(dir) = env::var("NOTES_UNLOCKED_DIR") {
+        return Ok(PathBuf::from(dir).join("store"));
+    }
+    let home = fs::canonicalize(home)?;
+    match dirs::runtime_dir() {
+        Some(runtime) => Ok(runtime
+            .join("notes")
+            .join(&hash_bytes(home.to_string_lossy().as_bytes())[..16])),
+        None if on_disk => disk_workspace(&home),
+        None => bail!(
+            "No runtime directory to decrypt the store into. Set NOTES_UNLOCKED_DIR to a tmpfs, \
+             or pass --on-disk to decrypt it under {}",
+            home.display()
+        ),
+    }
+}
+
+/// The workspace `--on-disk` falls back to, inside the store itself.
+pub(crate) fn disk_workspace(home: &Path) -> Result<PathBuf> {
+    Ok(fs::canonicalize(home)?.join("unlocked").join("store"))
+}
+
+/// Appends `err` to the agent log in `home`; the agent has no terminal to report to.
+fn log_error(home: &Path, err: &anyhow::Error) {
+    let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(home.join(LOG_FILE))
+    else {
+        return;
+    };
+    let _ = writeln!(file, "[{}] {:#}", Utc::now().to_rfc3339(), err);
+}
+
+/// Stops a daemon running against `root`, such as a workspace that is about to disappear.
+fn stop_daemon(root: &Path) {
+    let Ok(pid) = fs::read_to_string(root.join("daemon.pid")) else {
+        return;
+    };
+    if let Ok(pid) = pid.trim().parse::<i32>()
+        && pid > 0
+    {
+        unsafe { libc::kill(pid, libc::SIGTERM) };
+    }
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<()> {
+    let entries =
+        fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let rel = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .into_owned();
+        if dir == root
+            && (UNSEALED.contains(&rel.as_str())
+                || [STORE_FILE, SEALED_DIR, SOCKET_FILE, LOG_FILE].contains(&rel.as_str()))
+        {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if file_type.is_file() {
+            files.push(rel);
+        }
+    }
+    files.sort();
+    Ok(())
+}
+
+fn remove_empty_dirs(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() && path.file_name().is_some_and(|name| name != SEALED_DIR) {
+            remove_empty_dirs(&path)?;
+            let _ = fs::remove_dir(&path);
+        }
+    }
+    Ok(())
+}
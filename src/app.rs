@@ -1,17 +1,21 @@
+use crate::agent;
+use crate::crypto::{self, write_private, Key};
+use crate::oplog::{self, read_optional, relative_path, Change, FileContent};
 use crate::paths::DataPaths;
-use crate::utils::{hash_bytes, slugify};
+use crate::templates::expand_template;
+use crate::utils::{attachment_refs, hash_bytes, slugify, wikilink_re, wikilink_targets};
 use anyhow::{anyhow, bail, Context, Result};
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Default, serde::Serialize, serde::Deserialize)]
 pub struct Index {
     notes: HashMap<String, NoteMeta>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 struct NoteMeta {
     title: String,
     slug: String,
@@ -20,6 +24,16 @@ struct NoteMeta {
     current_version: u32,
     versions: Vec<VersionMeta>,
     working_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encryption: Option<Encryption>,
+}
+
+/// Marks a note whose version files are sealed with a key derived from a passphrase.
+/// Version and working copy hashes are HMACs of the plaintext keyed with the note key, so
+/// the index cannot be used to confirm guesses about the content.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct Encryption {
+    salt: String,
 }
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
@@ -28,11 +42,126 @@ struct VersionMeta {
     path: String,
     hash: String,
     created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    /// Attachment file names referenced by this version
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attachments: Vec<String>,
+    /// `[[...]]` targets in this version, as written
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    links: Vec<String>,
+}
+
+/// Metadata of a deleted note, stored next to its version files under `trash/`.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TrashedNote {
+    deleted_at: DateTime<Utc>,
+    note: NoteMeta,
+}
+
+const TRASH_META_FILE: &str = "note.json";
+
+/// Attachments with these extensions are linked as images
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "svg", "webp"];
+
+/// A note's state before a mutating operation, kept for the operation journal.
+struct NoteCapture {
+    slug: String,
+    meta: Option<NoteMeta>,
+    working: Option<FileContent>,
+}
+
+/// A note as it was at a point in time, read only from its history.
+struct HistoricalNote {
+    title: String,
+    slug: String,
+    version: u32,
+    created_at: DateTime<Utc>,
+    path: PathBuf,
+    deleted: bool,
+    encrypted: bool,
 }
 
 pub struct NotesApp {
     paths: DataPaths,
     index: Index,
+    /// Keys of encrypted notes unlocked by this process; others come from the key agent
+    keys: HashMap<String, Key>,
+}
+
+/// Read-only view of a note and its version history.
+#[derive(Clone)]
+pub struct NoteHistory {
+    pub title: String,
+    pub slug: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub current_version: u32,
+    pub versions: Vec<VersionInfo>,
+    /// Passphrase salt when the note is encrypted; its version files are then ciphertext
+    pub encryption: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct VersionInfo {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub message: Option<String>,
+    /// Hash of the version's plaintext
+    pub hash: String,
+    pub path: PathBuf,
+    /// Attachment file names the version links to
+    pub attachments: Vec<String>,
+}
+
+/// A note and its history coming from outside the store (archive, folder or git import).
+pub struct ImportedNote {
+    pub title: String,
+    /// Preferred id; the slugified title is used when absent
+    pub slug: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Versions in order, oldest first
+    pub versions: Vec<ImportedVersion>,
+    /// Working copy content, when it differs from the last version
+    pub working: Option<Vec<u8>>,
+    /// Passphrase salt when the versions are already encrypted
+    pub encryption: Option<String>,
+}
+
+pub struct ImportedVersion {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub message: Option<String>,
+    pub content: Vec<u8>,
+    /// Keyed hash of the plaintext, required when `content` is encrypted
+    pub hash: Option<String>,
+}
+
+impl ImportedVersion {
+    /// The hash to record: the supplied keyed hash for encrypted content, else its SHA-256.
+    fn recorded_hash(&self) -> String {
+        self.hash
+            .clone()
+            .unwrap_or_else(|| hash_bytes(&self.content))
+    }
+}
+
+/// A note's working copy, as read for export.
+pub struct WorkingCopy {
+    pub title: String,
+    pub slug: String,
+    pub content: String,
+}
+
+/// Result of writing a working copy from the UI.
+pub enum WriteOutcome {
+    Written,
+    /// The file was edited externally after it was loaded; the external content was saved
+    /// as `external_version` and the local content as `local_version`.
+    Conflict {
+        external_version: u32,
+        local_version: u32,
+    },
 }
 
 #[derive(Clone)]
@@ -44,6 +173,12 @@ pub struct NoteSummary {
     pub current_version: u32,
 }
 
+/// A `[[...]]` link and the note it resolves to, if any.
+pub struct NoteLink {
+    pub target: String,
+    pub slug: Option<String>,
+}
+
 impl NotesApp {
     pub fn load() -> Result<Self> {
         let paths = DataPaths::new()?;
@@ -58,34 +193,45 @@ impl NotesApp {
             Index::default()
         };
 
-        Ok(Self { paths, index })
+        Ok(Self {
+            paths,
+            index,
+            keys: HashMap::new(),
+        })
     }
 
     pub fn save(&self) -> Result<()> {
         let serialized = serde_json::to_string_pretty(&self.index)?;
         fs::write(&self.paths.index, serialized)
-            .with_context(|| format!("Failed to write {}", self.paths.index.display()))
+            .with_context(|| format!("Failed to write {}", self.paths.index.display()))?;
+        agent::sync(&self.paths.home)
     }
 
     pub fn paths(&self) -> &DataPaths {
         &self.paths
     }
 
-    pub fn create_note(&mut self, title: Option<String>) -> Result<PathBuf> {
-        let (_, path) = self.create_note_with_slug(title)?;
+    pub fn create_note(&mut self, title: Option<String>, template: Option<&str>) -> Result<PathBuf> {
+        let (_, path) = self.create_note_with_slug(title, template)?;
         Ok(path)
     }
 
-    pub fn create_note_with_slug(&mut self, title: Option<String>) -> Result<(String, PathBuf)> {
+    /// Creates a note whose first version is empty, or `template` expanded for the note.
+    pub fn create_note_with_slug(
+        &mut self,
+        title: Option<String>,
+        template: Option<&str>,
+    ) -> Result<(String, PathBuf)> {
         let now = Utc::now();
         let title = title.unwrap_or_else(|| format!("note-{}", now.format("%Y%m%d-%H%M%S")));
-        let mut slug = slugify(&title);
-
-        let mut counter = 1;
-        while self.index.notes.contains_key(&slug) {
-            counter += 1;
-            slug = format!("{}-{}", slugify(&title), counter);
-        }
+        let content = match template {
+            Some(name) => {
+                let today = now.with_timezone(&chrono::Local).date_naive();
+                expand_template(&self.paths, name, &title, today)?
+            }
+            None => String::new(),
+        };
+        let slug = self.unique_slug(&slugify(&title));
 
         let note_dir = self.paths.versions.join(&slug);
         fs::create_dir_all(&note_dir)
@@ -99,7 +245,7 @@ impl NotesApp {
                 .parent()
                 .ok_or_else(|| anyhow!("Invalid version path"))?,
         )?;
-        fs::write(&version_path, b"")?;
+        fs::write(&version_path, &content)?;
 
         let working_path = self.paths.working_file(&slug);
         fs::create_dir_all(
@@ -107,14 +253,17 @@ impl NotesApp {
                 .parent()
                 .ok_or_else(|| anyhow!("Invalid working path"))?,
         )?;
-        fs::write(&working_path, b"")?;
+        fs::write(&working_path, &content)?;
 
-        let hash = hash_bytes(b"");
+        let hash = hash_bytes(content.as_bytes());
         let version = VersionMeta {
             version: version_number,
             path: version_path_rel,
             hash: hash.clone(),
             created_at: now,
+            message: template.map(|name| format!("From template {}", name)),
+            attachments: attachment_refs(content.as_bytes()),
+            links: wikilink_targets(content.as_bytes()),
         };
 
         let meta = NoteMeta {
@@ -125,20 +274,112 @@ impl NotesApp {
             current_version: version_number,
             versions: vec![version],
             working_hash: Some(hash),
+            encryption: None,
         };
 
         self.index.notes.insert(slug.clone(), meta);
         Ok((slug, working_path))
     }
 
+    /// Returns `base`, or `base-2`, `base-3`, ... if it is already taken.
+    fn unique_slug(&self, base: &str) -> String {
+        let mut slug = base.to_string();
+        let mut counter = 1;
+        while self.index.notes.contains_key(&slug) {
+            counter += 1;
+            slug = format!("{}-{}", base, counter);
+        }
+        slug
+    }
+
+    /// Adds a note with existing history, keeping its timestamps. The preferred slug (or the
+    /// title) is slugified, since it names files, and gets a numeric suffix on collision, like
+    /// `create_note_with_slug`.
+    pub fn import_note(&mut self, note: ImportedNote) -> Result<String> {
+        if note.versions.is_empty() {
+            bail!("Cannot import {} without versions", note.title);
+        }
+
+        let base = slugify(note.slug.as_deref().unwrap_or(&note.title));
+        let slug = self.unique_slug(&base);
+
+        let mut versions = Vec::new();
+        for version in &note.versions {
+            let version_rel = format!("versions/{}/{:07}.md", slug, version.version);
+            let version_path = self.paths.root.join(&version_rel);
+            fs::create_dir_all(
+                version_path
+                    .parent()
+                    .ok_or_else(|| anyhow!("Invalid version path"))?,
+            )?;
+            fs::write(&version_path, &version.content)
+                .with_context(|| format!("Failed to write {}", version_path.display()))?;
+            versions.push(VersionMeta {
+                version: version.version,
+                path: version_rel,
+                hash: version.recorded_hash(),
+                created_at: version.created_at,
+                message: version.message.clone(),
+                attachments: match note.encryption {
+                    Some(_) => Vec::new(),
+                    None => attachment_refs(&version.content),
+                },
+                links: match note.encryption {
+                    Some(_) => Vec::new(),
+                    None => wikilink_targets(&version.content),
+                },
+            });
+        }
+
+        let last = note.versions.last().expect("checked above");
+        // Encrypted notes arrive locked: no working copy until they are opened.
+        let working_hash = if note.encryption.is_some() {
+            last.recorded_hash()
+        } else {
+            let working = note.working.as_deref().unwrap_or(&last.content);
+            let working_path = self.paths.working_file(&slug);
+            fs::write(&working_path, working)
+                .with_context(|| format!("Failed to write {}", working_path.display()))?;
+            hash_bytes(working)
+        };
+
+        let meta = NoteMeta {
+            title: note.title,
+            slug: slug.clone(),
+            created_at: note.created_at,
+            updated_at: last.created_at,
+            current_version: last.version,
+            versions,
+            working_hash: Some(working_hash),
+            encryption: note.encryption.map(|salt| Encryption { salt }),
+        };
+        self.index.notes.insert(slug.clone(), meta);
+
+        Ok(slug)
+    }
+
+    /// Whether a note with this title already holds exactly the given versions.
+    pub fn has_identical_note(&self, title: &str, versions: &[ImportedVersion]) -> bool {
+        self.index.notes.values().any(|note| {
+            note.title == title
+                && note.versions.len() == versions.len()
+                && note
+                    .versions
+                    .iter()
+                    .zip(versions)
+                    .all(|(version, imported)| version.hash == imported.recorded_hash())
+        })
+    }
+
     pub fn open_note(&mut self, identifier: &str) -> Result<PathBuf> {
         let slug = self
             .resolve_slug(identifier)
             .ok_or_else(|| anyhow!("Note not found: {}", identifier))?;
 
         self.snapshot_if_changed(&slug)?;
+        self.ensure_working_copy_exists(&slug)?;
 
-        Ok(self.paths.working_file(&slug))
+        Ok(self.working_path(&slug))
     }
 
     pub fn list_notes(&self) -> Result<()> {
@@ -148,17 +389,25 @@ impl NotesApp {
         }
 
         let mut notes: Vec<&NoteMeta> = self.index.notes.values().collect();
-        notes.sort_by(|a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()));
+        notes.sort_by_key(|note| note.title.to_lowercase());
 
         for note in notes {
-            let path = self.paths.working_file(&note.slug);
+            let path = self.working_path(&note.slug);
+            let encryption = if note.encryption.is_none() {
+                ""
+            } else if self.is_locked(&note.slug) {
+                " [encrypted, locked]"
+            } else {
+                " [encrypted]"
+            };
             println!(
-                "- {} (id: {}) versions: {} current: {} path: {}",
+                "- {} (id: {}) versions: {} current: {} path: {}{}",
                 note.title,
                 note.slug,
                 note.versions.len(),
                 note.current_version,
-                path.display()
+                path.display(),
+                encryption
             );
         }
 
@@ -167,7 +416,7 @@ impl NotesApp {
 
     pub fn note_summaries(&self) -> Vec<NoteSummary> {
         let mut notes: Vec<&NoteMeta> = self.index.notes.values().collect();
-        notes.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        notes.sort_by_key(|note| std::cmp::Reverse(note.updated_at));
         notes
             .into_iter()
             .map(|note| NoteSummary {
@@ -180,7 +429,35 @@ impl NotesApp {
             .collect()
     }
 
-    pub fn list_versions(&mut self, identifier: &str) -> Result<()> {
+    pub fn note_histories(&self) -> Vec<NoteHistory> {
+        let mut notes: Vec<&NoteMeta> = self.index.notes.values().collect();
+        notes.sort_by_key(|note| note.title.to_lowercase());
+        notes
+            .into_iter()
+            .map(|note| NoteHistory {
+                title: note.title.clone(),
+                slug: note.slug.clone(),
+                created_at: note.created_at,
+                updated_at: note.updated_at,
+                current_version: note.current_version,
+                versions: note
+                    .versions
+                    .iter()
+                    .map(|version| VersionInfo {
+                        version: version.version,
+                        created_at: version.created_at,
+                        message: version.message.clone(),
+                        hash: version.hash.clone(),
+                        path: self.paths.root.join(&version.path),
+                        attachments: version.attachments.clone(),
+                    })
+                    .collect(),
+                encryption: note.encryption.as_ref().map(|e| e.salt.clone()),
+            })
+            .collect()
+    }
+
+    pub fn list_versions(&self, identifier: &str) -> Result<()> {
         let slug = self
             .resolve_slug(identifier)
             .ok_or_else(|| anyhow!("Note not found: {}", identifier))?;
@@ -192,11 +469,17 @@ impl NotesApp {
 
         println!("Versions for {}:", note.title);
         for version in &note.versions {
+            let message = version
+                .message
+                .as_deref()
+                .map(|message| format!(" - {}", message))
+                .unwrap_or_default();
             println!(
-                "  v{} @ {} ({})",
+                "  v{} @ {} ({}){}",
                 version.version,
                 version.created_at.to_rfc3339(),
-                version.path
+                version.path,
+                message
             );
         }
 
@@ -217,9 +500,10 @@ impl NotesApp {
             .resolve_slug(identifier)
             .ok_or_else(|| anyhow!("Note not found: {}", identifier))?;
 
+        let capture = self.capture_note(&slug)?;
         self.snapshot_if_changed(&slug)?;
 
-        let (target, current_version) = {
+        let (target, current_version, content) = {
             let note = self
                 .index
                 .notes
@@ -241,23 +525,15 @@ impl NotesApp {
                 .find(|v| v.version == desired)
                 .ok_or_else(|| anyhow!("Version {} not found", desired))?
                 .clone();
+            let content = self.read_version(note, &self.paths.root.join(&target.path))?;
 
-            (target, note.current_version)
+            (target, note.current_version, content)
         };
 
-        let content = fs::read(self.paths.root.join(&target.path))
-            .with_context(|| format!("Failed to read {}", target.path))?;
-
-        let hash = hash_bytes(&content);
+        let hash = self.content_hash(&slug, &content)?;
         let new_version_number = current_version + 1;
         let new_version_rel = format!("versions/{}/{:07}.md", slug, new_version_number);
-        let new_version_path = self.paths.root.join(&new_version_rel);
-        fs::create_dir_all(
-            new_version_path
-                .parent()
-                .ok_or_else(|| anyhow!("Invalid version path"))?,
-        )?;
-        fs::write(&new_version_path, &content)?;
+        self.write_version(&slug, &new_version_rel, &content)?;
 
         let now = Utc::now();
         let new_meta = VersionMeta {
@@ -265,6 +541,9 @@ impl NotesApp {
             path: new_version_rel,
             hash: hash.clone(),
             created_at: now,
+            message: None,
+            attachments: self.attachment_names(&slug, &content),
+            links: self.link_targets(&slug, &content),
         };
 
         if let Some(note) = self.index.notes.get_mut(&slug) {
@@ -274,46 +553,363 @@ impl NotesApp {
             note.working_hash = Some(hash);
         }
 
-        let working_path = self.paths.working_file(&slug);
+        let working_path = self.working_path(&slug);
         fs::write(&working_path, &content)?;
 
+        if !self.is_encrypted(&slug) {
+            let changes = self.changes_since(capture)?;
+            oplog::record(
+                &self.paths,
+                "rollback",
+                format!("Roll back {} to v{}", slug, target.version),
+                changes,
+            )?;
+        }
+
         Ok(working_path)
     }
 
     pub fn delete_note_by_title(&mut self, title: &str) -> Result<String> {
         let slug = self.resolve_unique_title_slug(title)?;
+        let capture = self.capture_note(&slug)?;
 
+        let working_path = self.working_path(&slug);
         let note = self
             .index
             .notes
             .remove(&slug)
             .ok_or_else(|| anyhow!("Note not found: {}", title))?;
 
-        let working_path = self.paths.working_file(&slug);
         if working_path.exists() {
             fs::remove_file(&working_path)
                 .with_context(|| format!("Failed to remove {}", working_path.display()))?;
         }
 
+        let encrypted = note.encryption.is_some();
+        if encrypted {
+            self.keys.remove(&slug);
+            agent::forget_note_key(&self.paths, &slug)?;
+        }
         let versions_dir = self.paths.versions.join(&slug);
+        let trash_dir = self.move_to_trash(note)?;
+        if encrypted {
+            return Ok(slug);
+        }
+
+        let mut changes = self.changes_since(capture)?;
+        changes.push(Change::Move {
+            from: relative_path(&self.paths, &versions_dir),
+            to: relative_path(&self.paths, &trash_dir),
+        });
+        let trash_meta = trash_dir.join(TRASH_META_FILE);
+        changes.push(Change::File {
+            path: relative_path(&self.paths, &trash_meta),
+            before: None,
+            after: read_optional(&trash_meta),
+        });
+        oplog::record(&self.paths, "delete", format!("Delete {}", slug), changes)?;
+
+        Ok(slug)
+    }
+
+    /// Stores `file` under `attachments/` by content hash, appends a link to it to the note's
+    /// working copy and snapshots the note. Returns the inserted link.
+    pub fn attach_file(&mut self, identifier: &str, file: &Path) -> Result<String> {
+        let slug = self
+            .resolve_slug(identifier)
+            .ok_or_else(|| anyhow!("Note not found: {}", identifier))?;
+        if self.is_encrypted(&slug) {
+            bail!("{} is encrypted; attachments are stored unencrypted", identifier);
+        }
+
+        let content =
+            fs::read(file).with_context(|| format!("Failed to read {}", file.display()))?;
+        let extension = file
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .filter(|ext| ext.chars().all(|c| c.is_ascii_alphanumeric()));
+        let name = match &extension {
+            Some(ext) => format!("{}.{}", hash_bytes(&content), ext),
+            None => hash_bytes(&content),
+        };
+        let target = self.paths.attachment_file(&name);
+        if !target.exists() {
+            fs::create_dir_all(&self.paths.attachments)?;
+            fs::write(&target, &content)
+                .with_context(|| format!("Failed to write {}", target.display()))?;
+        }
+
+        let label = file
+            .file_name()
+            .map(|name| name.to_string_lossy().replace(['[', ']'], ""))
+            .unwrap_or_else(|| name.clone());
+        let image = extension
+            .as_deref()
+            .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext));
+        let link = format!(
+            "{}[{}](../attachments/{})",
+            if image { "!" } else { "" },
+            label,
+            name
+        );
+
+        let mut working = self.read_working_content(&slug)?;
+        if !working.is_empty() && !working.ends_with('\n') {
+            working.push('\n');
+        }
+        working.push_str(&link);
+        working.push('\n');
+        let working_path = self.working_path(&slug);
+        fs::write(&working_path, working)
+            .with_context(|| format!("Failed to write {}", working_path.display()))?;
+        self.snapshot_with_message(&slug, Some(&format!("Attach {}", label)))?;
+
+        Ok(link)
+    }
+
+    /// Removes attachments that no version of any note, including deleted notes still in the
+    /// trash, and no unsnapshotted working copy refers to. Returns the number of files and
+    /// bytes removed.
+    pub fn gc_attachments(&self) -> Result<(usize, u64)> {
+        let mut referenced: HashSet<String> = HashSet::new();
+        let trashed = self.trashed_notes()?;
+        let notes = self
+            .index
+            .notes
+            .values()
+            .chain(trashed.iter().map(|(_, trashed)| &trashed.note));
+        for note in notes {
+            for version in &note.versions {
+                referenced.extend(version.attachments.iter().cloned());
+            }
+        }
+        for slug in self.index.notes.keys() {
+            if let Ok(content) = fs::read(self.working_path(slug)) {
+                referenced.extend(attachment_refs(&content));
+            }
+        }
+
+        let Ok(entries) = fs::read_dir(&self.paths.attachments) else {
+            return Ok((0, 0));
+        };
+        let mut removed = 0;
+        let mut bytes = 0;
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if referenced.contains(&name) {
+                continue;
+            }
+            bytes += entry.metadata()?.len();
+            fs::remove_file(entry.path())
+                .with_context(|| format!("Failed to remove {}", entry.path().display()))?;
+            removed += 1;
+        }
+        Ok((removed, bytes))
+    }
+
+    /// Moves a removed note's history under `trash/` so time-travel queries can still see it.
+    fn move_to_trash(&self, note: NoteMeta) -> Result<PathBuf> {
+        let deleted_at = Utc::now();
+        let base = format!("{}-{}", note.slug, deleted_at.format("%Y%m%d%H%M%S"));
+        let mut trash_dir = self.paths.trash.join(&base);
+        let mut counter = 1;
+        while trash_dir.exists() {
+            counter += 1;
+            trash_dir = self.paths.trash.join(format!("{}-{}", base, counter));
+        }
+
+        let versions_dir = self.paths.versions.join(&note.slug);
         if versions_dir.exists() {
-            fs::remove_dir_all(&versions_dir)
-                .with_context(|| format!("Failed to remove {}", versions_dir.display()))?;
+            fs::create_dir_all(&self.paths.trash)?;
+            fs::rename(&versions_dir, &trash_dir)
+                .with_context(|| format!("Failed to move {}", versions_dir.display()))?;
+        } else {
+            fs::create_dir_all(&trash_dir)?;
+        }
+
+        let trashed = TrashedNote { deleted_at, note };
+        let meta_path = trash_dir.join(TRASH_META_FILE);
+        fs::write(&meta_path, serde_json::to_string_pretty(&trashed)?)
+            .with_context(|| format!("Failed to write {}", meta_path.display()))?;
+
+        Ok(trash_dir)
+    }
+
+    fn trashed_notes(&self) -> Result<Vec<(PathBuf, TrashedNote)>> {
+        if !self.paths.trash.exists() {
+            return Ok(Vec::new());
         }
 
-        Ok(note.slug)
+        let mut trashed = Vec::new();
+        for entry in fs::read_dir(&self.paths.trash)
+            .with_context(|| format!("Failed to read {}", self.paths.trash.display()))?
+        {
+            let dir = entry?.path();
+            let meta_path = dir.join(TRASH_META_FILE);
+            let Ok(content) = fs::read_to_string(&meta_path) else {
+                continue;
+            };
+            let note = serde_json::from_str::<TrashedNote>(&content)
+                .with_context(|| format!("Failed to parse {}", meta_path.display()))?;
+            trashed.push((dir, note));
+        }
+
+        Ok(trashed)
+    }
+
+    /// When the note `slug` was last moved to the trash, while the trash still holds it.
+    pub fn deleted_at(&self, slug: &str) -> Result<Option<DateTime<Utc>>> {
+        Ok(self
+            .trashed_notes()?
+            .into_iter()
+            .filter(|(_, trashed)| trashed.note.slug == slug)
+            .map(|(_, trashed)| trashed.deleted_at)
+            .max())
+    }
+
+    /// Resolves, for every note that existed at `at`, the version that was current then.
+    /// Deleted notes are included while their history is still in the trash.
+    fn notes_at(&self, at: DateTime<Utc>) -> Result<Vec<HistoricalNote>> {
+        let mut notes = Vec::new();
+
+        for note in self.index.notes.values() {
+            if let Some(version) = version_at(note, at) {
+                notes.push(HistoricalNote {
+                    title: note.title.clone(),
+                    slug: note.slug.clone(),
+                    version: version.version,
+                    created_at: version.created_at,
+                    path: self.paths.root.join(&version.path),
+                    deleted: false,
+                    encrypted: note.encryption.is_some(),
+                });
+            }
+        }
+
+        for (dir, trashed) in self.trashed_notes()? {
+            if trashed.deleted_at <= at {
+                continue;
+            }
+            let Some(version) = version_at(&trashed.note, at) else {
+                continue;
+            };
+            let Some(file_name) = PathBuf::from(&version.path).file_name().map(PathBuf::from)
+            else {
+                continue;
+            };
+            notes.push(HistoricalNote {
+                title: trashed.note.title.clone(),
+                slug: trashed.note.slug.clone(),
+                version: version.version,
+                created_at: version.created_at,
+                path: dir.join(file_name),
+                deleted: true,
+                encrypted: trashed.note.encryption.is_some(),
+            });
+        }
+
+        notes.sort_by_key(|note| note.title.to_lowercase());
+        Ok(notes)
     }
 
-    pub fn search(&mut self, query: &str) -> Result<()> {
+    pub fn list_notes_at(&self, at: DateTime<Utc>) -> Result<()> {
+        let notes = self.notes_at(at)?;
+        if notes.is_empty() {
+            println!("No notes at {}.", at.to_rfc3339());
+            return Ok(());
+        }
+
+        for note in notes {
+            println!(
+                "- {} (id: {}) version: {} @ {}{}",
+                note.title,
+                note.slug,
+                note.version,
+                note.created_at.to_rfc3339(),
+                if note.deleted { " [deleted]" } else { "" }
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn show_note_at(&self, identifier: &str, at: DateTime<Utc>) -> Result<()> {
+        let id_lower = identifier.to_lowercase();
+        let notes = self.notes_at(at)?;
+        let note = notes
+            .iter()
+            .find(|note| note.slug == identifier)
+            .or_else(|| {
+                notes
+                    .iter()
+                    .find(|note| note.title.to_lowercase() == id_lower || note.slug == id_lower)
+            })
+            .ok_or_else(|| anyhow!("Note not found at {}: {}", at.to_rfc3339(), identifier))?;
+        if note.encrypted {
+            bail!("{} is encrypted; its history cannot be shown", note.title);
+        }
+
+        let content = fs::read_to_string(&note.path)
+            .with_context(|| format!("Failed to read {}", note.path.display()))?;
+        print!("{}", content);
+        Ok(())
+    }
+
+    pub fn search_at(&self, query: &str, at: DateTime<Utc>) -> Result<()> {
+        let needle = query.to_lowercase();
+        let mut matches_found = false;
+
+        for note in self.notes_at(at)?.into_iter().filter(|note| !note.encrypted) {
+            let content = fs::read_to_string(&note.path).unwrap_or_else(|_| String::new());
+            if content.to_lowercase().contains(&needle) {
+                matches_found = true;
+                println!("- {} (id: {}) version: {}", note.title, note.slug, note.version);
+            }
+        }
+
+        if !matches_found {
+            println!("No matches found.");
+        }
+
+        Ok(())
+    }
+
+    /// Writes the content of every note as of `at` to `<dir>/<slug>.md`.
+    pub fn export_at(&self, dir: &Path, at: DateTime<Utc>) -> Result<usize> {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let notes: Vec<_> = self
+            .notes_at(at)?
+            .into_iter()
+            .filter(|note| !note.encrypted)
+            .collect();
+        for note in &notes {
+            let target = dir.join(format!("{}.md", note.slug));
+            fs::copy(&note.path, &target)
+                .with_context(|| format!("Failed to write {}", target.display()))?;
+        }
+
+        Ok(notes.len())
+    }
+
+    pub fn search(&self, query: &str) -> Result<()> {
         let needle = query.to_lowercase();
         let mut matches_found = false;
 
         let mut notes: Vec<&NoteMeta> = self.index.notes.values().collect();
-        notes.sort_by(|a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()));
+        notes.sort_by_key(|note| note.title.to_lowercase());
 
+        let mut locked = 0;
         for note in notes {
-            let content = fs::read_to_string(self.current_version_path(note))
-                .unwrap_or_else(|_| String::new());
+            if self.is_locked(&note.slug) {
+                locked += 1;
+                continue;
+            }
+            let content = self
+                .read_version(note, &self.current_version_path(note))
+                .map(|content| String::from_utf8_lossy(&content).into_owned())
+                .unwrap_or_default();
             if content.to_lowercase().contains(&needle) {
                 matches_found = true;
                 println!("- {} (id: {})", note.title, note.slug);
@@ -323,49 +919,73 @@ impl NotesApp {
         if !matches_found {
             println!("No matches found.");
         }
+        if locked > 0 {
+            println!("Skipped {} locked encrypted note(s).", locked);
+        }
 
         Ok(())
     }
 
     pub fn snapshot_all_changes(&mut self) -> Result<Vec<String>> {
-        let slugs: Vec<String> = self.index.notes.keys().cloned().collect();
+        self.snapshot_all_with_message(None)
+    }
+
+    pub fn snapshot_all_with_message(&mut self, message: Option<&str>) -> Result<Vec<String>> {
+        let mut slugs: Vec<String> = self.index.notes.keys().cloned().collect();
+        slugs.sort();
         let mut updated = Vec::new();
         for slug in slugs {
-            if self.snapshot_if_changed(&slug)? {
+            if self.snapshot_with_message(&slug, message)? {
                 updated.push(slug);
             }
         }
         Ok(updated)
     }
 
+    /// Snapshots a single note by title or id, returning the new version number if the
+    /// working copy had changed.
+    pub fn snapshot_note(&mut self, identifier: &str, message: Option<&str>) -> Result<Option<u32>> {
+        let slug = self
+            .resolve_slug(identifier)
+            .ok_or_else(|| anyhow!("Note not found: {}", identifier))?;
+
+        if self.snapshot_with_message(&slug, message)? {
+            Ok(Some(self.current_version_number(&slug)?))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn snapshot_if_changed(&mut self, slug: &str) -> Result<bool> {
+        self.snapshot_with_message(slug, None)
+    }
+
+    fn snapshot_with_message(&mut self, slug: &str, message: Option<&str>) -> Result<bool> {
+        // A locked note has no working copy, so there is nothing to snapshot.
+        if self.is_locked(slug) {
+            return Ok(false);
+        }
         self.ensure_working_copy_exists(slug)?;
+        let working_path = self.working_path(slug);
+        let content = fs::read(&working_path)
+            .with_context(|| format!("Failed to read {}", working_path.display()))?;
+        let hash = self.content_hash(slug, &content)?;
+
         let note = self
             .index
             .notes
             .get_mut(slug)
             .ok_or_else(|| anyhow!("Note not found: {}", slug))?;
-        let working_path = self.paths.working_file(slug);
-        let content = fs::read(&working_path)
-            .with_context(|| format!("Failed to read {}", working_path.display()))?;
-        let hash = hash_bytes(&content);
-
-        if let Some(last) = note.versions.last() {
-            if last.hash == hash {
-                note.working_hash = Some(hash);
-                return Ok(false);
-            }
+        if let Some(last) = note.versions.last()
+            && last.hash == hash
+        {
+            note.working_hash = Some(hash);
+            return Ok(false);
         }
 
         let new_version_number = note.current_version + 1;
         let version_rel = format!("versions/{}/{:07}.md", slug, new_version_number);
-        let version_path = self.paths.root.join(&version_rel);
-        fs::create_dir_all(
-            version_path
-                .parent()
-                .ok_or_else(|| anyhow!("Invalid version path"))?,
-        )?;
-        fs::write(&version_path, &content)?;
+        self.write_version(slug, &version_rel, &content)?;
 
         let now = Utc::now();
         let meta = VersionMeta {
@@ -373,8 +993,16 @@ impl NotesApp {
             path: version_rel,
             hash: hash.clone(),
             created_at: now,
+            message: message.map(str::to_string),
+            attachments: self.attachment_names(slug, &content),
+            links: self.link_targets(slug, &content),
         };
 
+        let note = self
+            .index
+            .notes
+            .get_mut(slug)
+            .ok_or_else(|| anyhow!("Note not found: {}", slug))?;
         note.versions.push(meta);
         note.current_version = new_version_number;
         note.updated_at = now;
@@ -385,20 +1013,158 @@ impl NotesApp {
 
     pub fn read_working_content(&mut self, slug: &str) -> Result<String> {
         self.ensure_working_copy_exists(slug)?;
-        let working_path = self.paths.working_file(slug);
-        fs::read_to_string(&working_path)
-            .with_context(|| format!("Failed to read {}", working_path.display()))
+        let working_path = self.working_path(slug);
+        let content = fs::read_to_string(&working_path)
+            .with_context(|| format!("Failed to read {}", working_path.display()))?;
+        let hash = self.content_hash(slug, content.as_bytes())?;
+        if let Some(note) = self.index.notes.get_mut(slug) {
+            note.working_hash = Some(hash);
+        }
+        Ok(content)
+    }
+
+    /// Reads a note's working copy without snapshotting it.
+    pub fn working_copy(&self, identifier: &str) -> Result<WorkingCopy> {
+        let slug = self
+            .resolve_slug(identifier)
+            .ok_or_else(|| anyhow!("Note not found: {}", identifier))?;
+        let note = &self.index.notes[&slug];
+        let working_path = self.working_path(&slug);
+        let content = match fs::read_to_string(&working_path) {
+            Ok(content) => content,
+            Err(_) => {
+                let content = self.read_version(note, &self.current_version_path(note))?;
+                String::from_utf8_lossy(&content).into_owned()
+            }
+        };
+
+        Ok(WorkingCopy {
+            title: note.title.clone(),
+            slug,
+            content,
+        })
     }
 
-    pub fn write_working_content(&mut self, slug: &str, content: &str) -> Result<()> {
+    /// Writes `content` to the working copy unless the file changed on disk since it was
+    /// last read. On divergence both sides are kept as versions and the local content wins.
+    pub fn write_working_content(&mut self, slug: &str, content: &str) -> Result<WriteOutcome> {
         self.ensure_working_copy_exists(slug)?;
-        let working_path = self.paths.working_file(slug);
+        let working_path = self.working_path(slug);
+        let on_disk = fs::read(&working_path)
+            .with_context(|| format!("Failed to read {}", working_path.display()))?;
+        let disk_hash = self.content_hash(slug, &on_disk)?;
+        let loaded_hash = self
+            .index
+            .notes
+            .get(slug)
+            .ok_or_else(|| anyhow!("Note not found: {}", slug))?
+            .working_hash
+            .clone();
+
+        let conflict = loaded_hash.is_some_and(|hash| hash != disk_hash);
+        if conflict {
+            self.snapshot_if_changed(slug)?;
+        }
+        let external_version = self.current_version_number(slug)?;
+
         fs::write(&working_path, content)
-            .with_context(|| format!("Failed to write {}", working_path.display()))
+            .with_context(|| format!("Failed to write {}", working_path.display()))?;
+
+        if !conflict {
+            let hash = self.content_hash(slug, content.as_bytes())?;
+            if let Some(note) = self.index.notes.get_mut(slug) {
+                note.working_hash = Some(hash);
+            }
+            return Ok(WriteOutcome::Written);
+        }
+
+        self.snapshot_if_changed(slug)?;
+        Ok(WriteOutcome::Conflict {
+            external_version,
+            local_version: self.current_version_number(slug)?,
+        })
+    }
+
+    pub(crate) fn note_meta_value(&self, slug: &str) -> Result<Option<serde_json::Value>> {
+        self.index
+            .notes
+            .get(slug)
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    pub(crate) fn restore_note_meta(
+        &mut self,
+        slug: &str,
+        meta: Option<&serde_json::Value>,
+    ) -> Result<()> {
+        match meta {
+            Some(value) => {
+                let note = serde_json::from_value::<NoteMeta>(value.clone())
+                    .with_context(|| format!("Invalid note metadata for {}", slug))?;
+                self.index.notes.insert(slug.to_string(), note);
+            }
+            None => {
+                self.index.notes.remove(slug);
+            }
+        }
+        Ok(())
+    }
+
+    fn capture_note(&self, slug: &str) -> Result<NoteCapture> {
+        Ok(NoteCapture {
+            slug: slug.to_string(),
+            meta: self.index.notes.get(slug).cloned(),
+            working: read_optional(&self.working_path(slug)),
+        })
+    }
+
+    /// Journal changes for a note since `capture`: its metadata, working copy and any
+    /// version files added in between.
+    fn changes_since(&self, capture: NoteCapture) -> Result<Vec<Change>> {
+        let after = self.index.notes.get(&capture.slug);
+        let mut changes = vec![
+            Change::Note {
+                slug: capture.slug.clone(),
+                before: capture.meta.as_ref().map(serde_json::to_value).transpose()?,
+                after: after.map(serde_json::to_value).transpose()?,
+            },
+            Change::File {
+                path: relative_path(&self.paths, &self.working_path(&capture.slug)),
+                before: capture.working,
+                after: read_optional(&self.working_path(&capture.slug)),
+            },
+        ];
+
+        let before_versions: Vec<&str> = capture
+            .meta
+            .iter()
+            .flat_map(|note| note.versions.iter().map(|v| v.path.as_str()))
+            .collect();
+        for version in after.iter().flat_map(|note| note.versions.iter()) {
+            if !before_versions.contains(&version.path.as_str()) {
+                changes.push(Change::File {
+                    path: version.path.clone(),
+                    before: None,
+                    after: read_optional(&self.paths.root.join(&version.path)),
+                });
+            }
+        }
+
+        Ok(changes)
+    }
+
+    fn current_version_number(&self, slug: &str) -> Result<u32> {
+        self.index
+            .notes
+            .get(slug)
+            .map(|note| note.current_version)
+            .ok_or_else(|| anyhow!("Note not found: {}", slug))
     }
 
     fn ensure_working_copy_exists(&self, slug: &str) -> Result<()> {
-        let working_path = self.paths.working_file(slug);
+        let working_path = self.working_path(slug);
         if working_path.exists() {
             return Ok(());
         }
@@ -409,8 +1175,10 @@ impl NotesApp {
             .get(slug)
             .ok_or_else(|| anyhow!("Note not found: {}", slug))?;
         let source = self.current_version_path(note);
-        let content =
-            fs::read(&source).with_context(|| format!("Failed to read {}", source.display()))?;
+        let content = self.read_version(note, &source)?;
+        if note.encryption.is_some() {
+            return write_private(&working_path, &content);
+        }
         fs::create_dir_all(
             working_path
                 .parent()
@@ -421,6 +1189,436 @@ impl NotesApp {
         Ok(())
     }
 
+    /// Where a note's working copy lives. Encrypted notes keep a decrypted copy under the
+    /// unlocked directory, and only while they are unlocked.
+    pub fn working_path(&self, slug: &str) -> PathBuf {
+        if self.is_encrypted(slug) {
+            self.paths.unlocked_file(slug)
+        } else {
+            self.paths.working_file(slug)
+        }
+    }
+
+    fn is_encrypted(&self, slug: &str) -> bool {
+        self.index
+            .notes
+            .get(slug)
+            .is_some_and(|note| note.encryption.is_some())
+    }
+
+    /// Whether a note is encrypted and its key is not held, here or by the key agent.
+    pub fn is_locked(&self, slug: &str) -> bool {
+        self.is_encrypted(slug) && !matches!(self.note_key(slug), Ok(Some(_)))
+    }
+
+    /// Hash recorded for a note's content: SHA-256, or for an encrypted note an HMAC keyed
+    /// with its key.
+    fn content_hash(&self, slug: &str, content: &[u8]) -> Result<String> {
+        if !self.is_encrypted(slug) {
+            return Ok(hash_bytes(content));
+        }
+        let key = self
+            .note_key(slug)?
+            .ok_or_else(|| anyhow!("{} is encrypted and locked", slug))?;
+        Ok(key.mac(content))
+    }
+
+    /// Key of an unlocked encrypted note. Never stored on disk: it stays in this process or
+    /// with the key agent until the note is locked.
+    fn note_key(&self, slug: &str) -> Result<Option<Key>> {
+        match self.keys.get(slug) {
+            Some(key) => Ok(Some(key.clone())),
+            None => agent::note_key(&self.paths, slug),
+        }
+    }
+
+    /// Whether opening `identifier` needs a passphrase first.
+    pub fn needs_unlock(&self, identifier: &str) -> Result<bool> {
+        let slug = self
+            .resolve_slug(identifier)
+            .ok_or_else(|| anyhow!("Note not found: {}", identifier))?;
+        Ok(self.is_locked(&slug))
+    }
+
+    /// Reads a version file, decrypting it with the session key for encrypted notes.
+    fn read_version(&self, note: &NoteMeta, path: &Path) -> Result<Vec<u8>> {
+        let content = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        if note.encryption.is_none() {
+            return Ok(content);
+        }
+        let key = self.note_key(&note.slug)?.ok_or_else(|| {
+            anyhow!(
+                "{} is encrypted and locked. Run `notes open` to unlock it.",
+                note.title
+            )
+        })?;
+        key.decrypt(&content)
+    }
+
+    /// Writes a version file, sealing it with the session key for encrypted notes.
+    fn write_version(&self, slug: &str, version_rel: &str, content: &[u8]) -> Result<()> {
+        let version_path = self.paths.root.join(version_rel);
+        fs::create_dir_all(
+            version_path
+                .parent()
+                .ok_or_else(|| anyhow!("Invalid version path"))?,
+        )?;
+        let sealed = if self.is_encrypted(slug) {
+            let key = self
+                .note_key(slug)?
+                .ok_or_else(|| anyhow!("{} is encrypted and locked", slug))?;
+            key.encrypt(content)?
+        } else {
+            content.to_vec()
+        };
+        fs::write(&version_path, sealed)
+            .with_context(|| format!("Failed to write {}", version_path.display()))
+    }
+
+    /// Encrypts every version of a note with a key derived from `passphrase` and removes
+    /// its plaintext working copy. The note is left locked. Journal entries for the note
+    /// are dropped, since they hold its plaintext.
+    pub fn encrypt_note(&mut self, identifier: &str, passphrase: &str) -> Result<String> {
+        let slug = self
+            .resolve_slug(identifier)
+            .ok_or_else(|| anyhow!("Note not found: {}", identifier))?;
+        if self.is_encrypted(&slug) {
+            bail!("{} is already encrypted", identifier);
+        }
+        self.snapshot_if_changed(&slug)?;
+
+        let salt = crypto::new_salt();
+        let key = Key::derive(passphrase, &salt)?;
+
+        // Earlier deleted copies of the note in the trash hold its plaintext too.
+        let mut trashed: Vec<(PathBuf, TrashedNote)> = self
+            .trashed_notes()?
+            .into_iter()
+            .filter(|(_, trashed)| trashed.note.slug == slug && trashed.note.encryption.is_none())
+            .collect();
+        let mut files = Vec::new();
+        for version in &self.index.notes[&slug].versions {
+            files.push(self.paths.root.join(&version.path));
+        }
+        for (dir, trashed) in &trashed {
+            for version in &trashed.note.versions {
+                if let Some(name) = Path::new(&version.path).file_name()
+                    && dir.join(name).exists()
+                {
+                    files.push(dir.join(name));
+                }
+            }
+        }
+
+        // Everything is sealed into temporary files before any plaintext is replaced, and
+        // each file is then swapped in with a rename, so no file is ever half-written.
+        let mut staged = Vec::new();
+        let mut hashes = HashMap::new();
+        for path in &files {
+            let temp = path.with_extension("md.sealing");
+            let sealed = fs::read(path)
+                .with_context(|| format!("Failed to read {}", path.display()))
+                .and_then(|content| {
+                    hashes.insert(path.clone(), key.mac(&content));
+                    key.encrypt(&content)
+                })
+                .and_then(|sealed| {
+                    fs::write(&temp, sealed)
+                        .with_context(|| format!("Failed to write {}", temp.display()))
+                });
+            if let Err(err) = sealed {
+                for temp in staged.iter().chain([&temp]) {
+                    let _ = fs::remove_file(temp);
+                }
+                return Err(err);
+            }
+            staged.push(temp);
+        }
+        for (temp, path) in staged.iter().zip(&files) {
+            fs::rename(temp, path).with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+
+        // Plain SHA-256 of the plaintext would let anyone with the index check guesses.
+        let seal_meta = |note: &mut NoteMeta, path_of: &dyn Fn(&VersionMeta) -> PathBuf| {
+            note.encryption = Some(Encryption { salt: salt.clone() });
+            for version in &mut note.versions {
+                if let Some(hash) = hashes.get(&path_of(version)) {
+                    version.hash = hash.clone();
+                }
+            }
+            note.working_hash = note.versions.last().map(|version| version.hash.clone());
+        };
+        for (dir, trashed) in &mut trashed {
+            seal_meta(&mut trashed.note, &|version| {
+                dir.join(Path::new(&version.path).file_name().unwrap_or_default())
+            });
+            let meta_path = dir.join(TRASH_META_FILE);
+            fs::write(&meta_path, serde_json::to_string_pretty(trashed)?)
+                .with_context(|| format!("Failed to write {}", meta_path.display()))?;
+        }
+        if let Some(note) = self.index.notes.get_mut(&slug) {
+            seal_meta(note, &|version| self.paths.root.join(&version.path));
+        }
+
+        let working_path = self.paths.working_file(&slug);
+        if working_path.exists() {
+            fs::remove_file(&working_path)
+                .with_context(|| format!("Failed to remove {}", working_path.display()))?;
+        }
+        oplog::forget_note(&self.paths, &slug)?;
+
+        Ok(slug)
+    }
+
+    /// Checks `passphrase` against the current version, hands the key to the key agent, which
+    /// locks the note again after a while, and writes the decrypted working copy. Returns the
+    /// working copy path.
+    pub fn unlock_note(&mut self, identifier: &str, passphrase: &str) -> Result<PathBuf> {
+        let slug = self
+            .resolve_slug(identifier)
+            .ok_or_else(|| anyhow!("Note not found: {}", identifier))?;
+        let note = &self.index.notes[&slug];
+        let encryption = note
+            .encryption
+            .as_ref()
+            .ok_or_else(|| anyhow!("{} is not encrypted", identifier))?;
+
+        let key = Key::derive(passphrase, &encryption.salt)?;
+        let source = self.current_version_path(note);
+        let sealed =
+            fs::read(&source).with_context(|| format!("Failed to read {}", source.display()))?;
+        key.decrypt(&sealed)?;
+
+        agent::hold_note_key(&self.paths, &slug, &key)?;
+        self.keys.insert(slug.clone(), key);
+        self.ensure_working_copy_exists(&slug)?;
+        Ok(self.working_path(&slug))
+    }
+
+    /// Snapshots and locks the given note, or every unlocked note: the decrypted working
+    /// copy is removed and the key agent forgets the key. Returns the ids that were locked.
+    pub fn lock_notes(&mut self, identifier: Option<&str>) -> Result<Vec<String>> {
+        let slugs: Vec<String> = match identifier {
+            Some(identifier) => vec![
+                self.resolve_slug(identifier)
+                    .ok_or_else(|| anyhow!("Note not found: {}", identifier))?,
+            ],
+            None => {
+                let mut slugs: Vec<String> = self
+                    .index
+                    .notes
+                    .values()
+                    .filter(|note| note.encryption.is_some())
+                    .map(|note| note.slug.clone())
+                    .collect();
+                slugs.sort();
+                slugs
+            }
+        };
+
+        let mut locked = Vec::new();
+        for slug in slugs {
+            if !self.is_encrypted(&slug) {
+                bail!("{} is not encrypted", slug);
+            }
+            if self.is_locked(&slug) {
+                if self.paths.unlocked_file(&slug).exists() {
+                    bail!(
+                        "The key of {} is gone but its decrypted copy is still there. \
+                         Run `notes open {}` and lock it again.",
+                        slug,
+                        slug
+                    );
+                }
+                continue;
+            }
+            let key = self
+                .note_key(&slug)?
+                .ok_or_else(|| anyhow!("{} is encrypted and locked", slug))?;
+            self.seal_unlocked(&slug, key)?;
+            agent::forget_note_key(&self.paths, &slug)?;
+            locked.push(slug);
+        }
+        Ok(locked)
+    }
+
+    /// Snapshots an unlocked note with `key` and removes its decrypted working copy. Used
+    /// by `lock_notes` and by the key agent when a note's time is up.
+    pub(crate) fn seal_unlocked(&mut self, slug: &str, key: Key) -> Result<()> {
+        self.keys.insert(slug.to_string(), key);
+        let sealed = self.snapshot_if_changed(slug);
+        self.keys.remove(slug);
+        sealed?;
+        let working_path = self.paths.unlocked_file(slug);
+        if working_path.exists() {
+            fs::remove_file(&working_path)
+                .with_context(|| format!("Failed to remove {}", working_path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Link targets to index for a new version; none for encrypted notes, whose index entry
+    /// would otherwise reveal them.
+    fn link_targets(&self, slug: &str, content: &[u8]) -> Vec<String> {
+        if self.is_encrypted(slug) {
+            Vec::new()
+        } else {
+            wikilink_targets(content)
+        }
+    }
+
+    /// Attachments to index for a new version; none for encrypted notes, like `link_targets`.
+    fn attachment_names(&self, slug: &str, content: &[u8]) -> Vec<String> {
+        if self.is_encrypted(slug) {
+            Vec::new()
+        } else {
+            attachment_refs(content)
+        }
+    }
+
+    /// Outgoing links of a note's current version, each with the id it resolves to.
+    pub fn links_of(&self, identifier: &str) -> Result<(String, Vec<NoteLink>)> {
+        let slug = self
+            .resolve_slug(identifier)
+            .ok_or_else(|| anyhow!("Note not found: {}", identifier))?;
+        let note = &self.index.notes[&slug];
+        let links = current_version(note)
+            .map(|version| version.links.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|target| NoteLink {
+                slug: self.resolve_slug(&target),
+                target,
+            })
+            .collect();
+        Ok((note.title.clone(), links))
+    }
+
+    /// Notes whose current version links to `identifier`, as (title, id) sorted by title.
+    pub fn backlinks_of(&self, identifier: &str) -> Result<(String, Vec<(String, String)>)> {
+        let slug = self
+            .resolve_slug(identifier)
+            .ok_or_else(|| anyhow!("Note not found: {}", identifier))?;
+        let mut sources: Vec<(String, String)> = self
+            .index
+            .notes
+            .values()
+            .filter(|note| {
+                current_version(note).is_some_and(|version| {
+                    version
+                        .links
+                        .iter()
+                        .any(|target| self.resolve_slug(target).as_deref() == Some(slug.as_str()))
+                })
+            })
+            .map(|note| (note.title.clone(), note.slug.clone()))
+            .collect();
+        sources.sort_by_key(|(title, _)| title.to_lowercase());
+        Ok((self.index.notes[&slug].title.clone(), sources))
+    }
+
+    /// Links in current versions that resolve to no note, as (source title, source id, target).
+    pub fn broken_links(&self) -> Vec<(String, String, String)> {
+        let mut broken: Vec<(String, String, String)> = self
+            .index
+            .notes
+            .values()
+            .flat_map(|note| {
+                current_version(note)
+                    .map(|version| version.links.as_slice())
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|target| self.resolve_slug(target).is_none())
+                    .map(|target| (note.title.clone(), note.slug.clone(), target.clone()))
+            })
+            .collect();
+        broken.sort();
+        broken
+    }
+
+    /// Gives a note a new title, keeping its id. Links to the old title in other notes are
+    /// rewritten and snapshotted; links by id keep working unchanged. Returns the ids of the
+    /// notes whose links were rewritten. Locked encrypted notes are left alone.
+    pub fn rename_note(&mut self, identifier: &str, new_title: &str) -> Result<Vec<String>> {
+        let slug = self
+            .resolve_slug(identifier)
+            .ok_or_else(|| anyhow!("Note not found: {}", identifier))?;
+        let new_title = new_title.trim();
+        if new_title.is_empty() {
+            bail!("Title must not be empty");
+        }
+        if let Some(other) = self.resolve_slug(new_title)
+            && other != slug
+        {
+            bail!("{} already refers to note {}", new_title, other);
+        }
+        let old_title = self.index.notes[&slug].title.clone();
+
+        // Resolve against the old title before anything changes.
+        let mut rewrites = Vec::new();
+        let mut slugs: Vec<String> = self.index.notes.keys().cloned().collect();
+        slugs.sort();
+        for source in slugs {
+            if self.is_locked(&source) {
+                continue;
+            }
+            let content = self.read_working_content(&source)?;
+            let mut changed = false;
+            let rewritten = wikilink_re().replace_all(&content, |caps: &regex::Captures| {
+                let target = caps[1].trim();
+                let by_title = target != slug
+                    && self.resolve_slug(target).as_deref() == Some(slug.as_str());
+                if !by_title {
+                    return caps[0].to_string();
+                }
+                changed = true;
+                match caps.get(2) {
+                    Some(label) => format!("[[{}|{}]]", new_title, label.as_str()),
+                    None => format!("[[{}]]", new_title),
+                }
+            });
+            if changed {
+                rewrites.push((source, rewritten.into_owned()));
+            }
+        }
+
+        let mut captures = vec![self.capture_note(&slug)?];
+        for (source, _) in &rewrites {
+            if source != &slug {
+                captures.push(self.capture_note(source)?);
+            }
+        }
+
+        if let Some(note) = self.index.notes.get_mut(&slug) {
+            note.title = new_title.to_string();
+            note.updated_at = Utc::now();
+        }
+        let message = format!("Update links to {}", new_title);
+        let mut rewritten = Vec::new();
+        for (source, content) in rewrites {
+            let working_path = self.working_path(&source);
+            fs::write(&working_path, content)
+                .with_context(|| format!("Failed to write {}", working_path.display()))?;
+            self.snapshot_with_message(&source, Some(&message))?;
+            rewritten.push(source);
+        }
+
+        let mut changes = Vec::new();
+        for capture in captures {
+            if !self.is_encrypted(&capture.slug) {
+                changes.extend(self.changes_since(capture)?);
+            }
+        }
+        oplog::record(
+            &self.paths,
+            "rename",
+            format!("Rename {} to {}", old_title, new_title),
+            changes,
+        )?;
+
+        Ok(rewritten)
+    }
+
     fn resolve_slug(&self, identifier: &str) -> Option<String> {
         if self.index.notes.contains_key(identifier) {
             return Some(identifier.to_string());
@@ -463,3 +1661,63 @@ impl NotesApp {
             .join(&note.versions.last().expect("note has versions").path)
     }
 }
+
+fn current_version(note: &NoteMeta) -> Option<&VersionMeta> {
+    note.versions
+        .iter()
+        .find(|version| version.version == note.current_version)
+        .or(note.versions.last())
+}
+
+fn version_at(note: &NoteMeta, at: DateTime<Utc>) -> Option<&VersionMeta> {
+    note.versions
+        .iter()
+        .filter(|version| version.created_at <= at)
+        .max_by_key(|version| (version.created_at, version.version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_working_content_keeps_both_sides_of_an_external_edit() {
+        let home = tempfile::tempdir().expect("tempdir");
+        // The only test in this binary that reads NOTES_HOME, so nothing races with it.
+        unsafe { std::env::set_var("NOTES_HOME", home.path()) };
+        let mut app = NotesApp::load().expect("load store");
+        let (slug, path) = app
+            .create_note_with_slug(Some("Draft".to_string()), None)
+            .expect("create note");
+
+        assert_eq!(app.read_working_content(&slug).expect("read"), "");
+        fs::write(&path, "edited in another editor").expect("external edit");
+        let outcome = app
+            .write_working_content(&slug, "edited in the app")
+            .expect("write");
+
+        let WriteOutcome::Conflict {
+            external_version,
+            local_version,
+        } = outcome
+        else {
+            panic!("an external edit after reading must be reported as a conflict");
+        };
+        let history = app
+            .note_histories()
+            .into_iter()
+            .find(|note| note.slug == slug)
+            .expect("history");
+        let content_of = |number: u32| {
+            let version = history
+                .versions
+                .iter()
+                .find(|version| version.version == number)
+                .expect("version");
+            fs::read_to_string(&version.path).expect("read version")
+        };
+        assert_eq!(content_of(external_version), "edited in another editor");
+        assert_eq!(content_of(local_version), "edited in the app");
+        assert_eq!(fs::read_to_string(&path).expect("read"), "edited in the app");
+    }
+}
@@ -0,0 +1,290 @@
+use crate::app::{ImportedNote, ImportedVersion, NotesApp};
+use crate::bullet::import_journal;
+use crate::utils::hash_bytes;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Layout version written to the archive manifest
+const ARCHIVE_FORMAT: u32 = 1;
+
+const MANIFEST_PATH: &str = "manifest.json";
+
+/// zstd compression level for archives
+const COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Manifest {
+    format: u32,
+    created_at: DateTime<Utc>,
+    latest_only: bool,
+    notes: Vec<ArchivedNote>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ArchivedNote {
+    title: String,
+    slug: String,
+    created_at: DateTime<Utc>,
+    versions: Vec<ArchivedVersion>,
+    /// Archive path of the working copy, present only when it has unsnapshotted changes
+    working: Option<String>,
+    /// Passphrase salt of an encrypted note; its versions are archived as ciphertext
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encryption: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ArchivedVersion {
+    version: u32,
+    created_at: DateTime<Utc>,
+    message: Option<String>,
+    path: String,
+    /// Keyed hash of the plaintext, recorded for encrypted versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+}
+
+pub struct ImportSummary {
+    /// Original id and the id it was imported as
+    pub imported: Vec<(String, String)>,
+    /// Ids skipped because an identical note already exists
+    pub skipped: Vec<String>,
+    pub journal_entries: usize,
+}
+
+/// Writes notes (full history, or only each note's current version) and the bullet journal
+/// to a zstd-compressed tarball.
+pub fn export_archive(app: &NotesApp, target: &Path, latest_only: bool) -> Result<usize> {
+    let file = fs::File::create(target)
+        .with_context(|| format!("Failed to create {}", target.display()))?;
+    let encoder = zstd::Encoder::new(file, COMPRESSION_LEVEL)?;
+    let mut builder = tar::Builder::new(encoder);
+
+    let mut notes = Vec::new();
+    let mut attachments = BTreeSet::new();
+    for note in app.note_histories() {
+        let versions: Vec<_> = if latest_only {
+            note.versions
+                .iter()
+                .filter(|version| version.version == note.current_version)
+                .collect()
+        } else {
+            note.versions.iter().collect()
+        };
+
+        let mut archived = Vec::new();
+        let mut last_content = Vec::new();
+        for version in versions {
+            let content = fs::read(&version.path)
+                .with_context(|| format!("Failed to read {}", version.path.display()))?;
+            let path = format!("versions/{}/{:07}.md", note.slug, version.version);
+            append_file(&mut builder, &path, &content, version.created_at)?;
+            archived.push(ArchivedVersion {
+                version: version.version,
+                created_at: version.created_at,
+                message: version.message.clone(),
+                path,
+                hash: note.encryption.as_ref().map(|_| version.hash.clone()),
+            });
+            attachments.extend(version.attachments.iter().cloned());
+            last_content = content;
+        }
+
+        // The decrypted working copy of an unlocked note never leaves the machine.
+        let working_path = app.working_path(&note.slug);
+        let working = match fs::read(&working_path) {
+            Ok(content) if note.encryption.is_none() && content != last_content => {
+                let path = format!("files/{}.md", note.slug);
+                append_file(&mut builder, &path, &content, note.updated_at)?;
+                Some(path)
+            }
+            _ => None,
+        };
+
+        notes.push(ArchivedNote {
+            title: note.title,
+            slug: note.slug,
+            created_at: note.created_at,
+            versions: archived,
+            working,
+            encryption: note.encryption,
+        });
+    }
+
+    for name in attachments {
+        let path = app.paths().attachment_file(&name);
+        let Ok(content) = fs::read(&path) else {
+            continue;
+        };
+        append_file(&mut builder, &format!("attachments/{}", name), &content, Utc::now())?;
+    }
+
+    let journal_root = app.paths().journal_root();
+    let mut journal_files = Vec::new();
+    collect_files(&journal_root, &journal_root, &mut journal_files)?;
+    journal_files.sort();
+    for rel in journal_files {
+        let content = fs::read(journal_root.join(&rel))?;
+        append_file(&mut builder, &format!("journal/{}", rel), &content, Utc::now())?;
+    }
+
+    let count = notes.len();
+    let manifest = Manifest {
+        format: ARCHIVE_FORMAT,
+        created_at: Utc::now(),
+        latest_only,
+        notes,
+    };
+    append_file(
+        &mut builder,
+        MANIFEST_PATH,
+        serde_json::to_string_pretty(&manifest)?.as_bytes(),
+        manifest.created_at,
+    )?;
+
+    builder.into_inner()?.finish()?.flush()?;
+    Ok(count)
+}
+
+/// Merges an archive into the store. Notes keep their timestamps; ids that are already taken
+/// get a numeric suffix, and notes identical to an existing one are skipped.
+pub fn import_archive(app: &mut NotesApp, source: &Path) -> Result<ImportSummary> {
+    let file =
+        fs::File::open(source).with_context(|| format!("Failed to open {}", source.display()))?;
+    let mut archive = tar::Archive::new(zstd::Decoder::new(file)?);
+
+    let mut files: HashMap<String, Vec<u8>> = HashMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        files.insert(path, content);
+    }
+
+    let manifest_bytes = files
+        .get(MANIFEST_PATH)
+        .ok_or_else(|| anyhow!("{} is not a notes archive", source.display()))?;
+    let manifest: Manifest = serde_json::from_slice(manifest_bytes)
+        .with_context(|| format!("Failed to parse {}", MANIFEST_PATH))?;
+    if manifest.format > ARCHIVE_FORMAT {
+        bail!("Unsupported archive format {}", manifest.format);
+    }
+
+    let mut summary = ImportSummary {
+        imported: Vec::new(),
+        skipped: Vec::new(),
+        journal_entries: 0,
+    };
+
+    // Content-addressed, so an existing file with the same name is the same attachment.
+    for (path, content) in &files {
+        let Some(name) = path.strip_prefix("attachments/") else {
+            continue;
+        };
+        let Some(hash) = attachment_hash(name) else {
+            bail!("Invalid attachment name in archive: {}", name);
+        };
+        if hash_bytes(content) != hash {
+            bail!("Attachment {} does not match its hash", name);
+        }
+        let target = app.paths().attachment_file(name);
+        if !target.exists() {
+            fs::create_dir_all(&app.paths().attachments)?;
+            fs::write(&target, content)
+                .with_context(|| format!("Failed to write {}", target.display()))?;
+        }
+    }
+
+    for note in manifest.notes {
+        let mut versions = Vec::new();
+        for version in note.versions {
+            let content = files
+                .get(&version.path)
+                .ok_or_else(|| anyhow!("Archive is missing {}", version.path))?
+                .clone();
+            versions.push(ImportedVersion {
+                version: version.version,
+                created_at: version.created_at,
+                message: version.message,
+                content,
+                hash: version.hash,
+            });
+        }
+
+        if note.working.is_none() && app.has_identical_note(&note.title, &versions) {
+            summary.skipped.push(note.slug);
+            continue;
+        }
+
+        let original = note.slug.clone();
+        let slug = app.import_note(ImportedNote {
+            title: note.title,
+            slug: Some(note.slug),
+            created_at: note.created_at,
+            versions,
+            working: note.working.and_then(|path| files.get(&path).cloned()),
+            encryption: note.encryption,
+        })?;
+        summary.imported.push((original, slug));
+    }
+
+    let mut journal_files: Vec<(String, String)> = files
+        .iter()
+        .filter_map(|(path, content)| {
+            let rel = path.strip_prefix("journal/")?;
+            Some((rel.to_string(), String::from_utf8_lossy(content).into_owned()))
+        })
+        .collect();
+    journal_files.sort();
+    if !journal_files.is_empty() {
+        summary.journal_entries = import_journal(&journal_files)?;
+    }
+
+    Ok(summary)
+}
+
+/// The sha256 an attachment is named by, when `name` is `<64 hex digits>[.<ext>]`.
+fn attachment_hash(name: &str) -> Option<&str> {
+    let (hash, extension) = name.split_once('.').unwrap_or((name, "x"));
+    let valid = hash.len() == 64
+        && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        && !extension.is_empty()
+        && extension.chars().all(|c| c.is_ascii_alphanumeric());
+    valid.then_some(hash)
+}
+
+fn append_file<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    content: &[u8],
+    modified: DateTime<Utc>,
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(modified.timestamp().max(0) as u64);
+    header.set_entry_type(tar::EntryType::Regular);
+    builder
+        .append_data(&mut header, path, content)
+        .with_context(|| format!("Failed to add {} to archive", path))
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<()> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if let Ok(rel) = path.strip_prefix(root) {
+            files.push(rel.to_string_lossy().into_owned());
+        }
+    }
+    Ok(())
+}
@@ -1,23 +1,17 @@
-use crate::cli::BulletAction;
+use crate::cli::{BulletAction, BulletExportFormat, RecurAction};
+use crate::pdf;
+use crate::templates::expand_template;
+use crate::oplog::{self, read_optional, relative_path, Change, FileContent};
 use crate::paths::DataPaths;
 use crate::utils::launch_subl_if_installed;
-use anyhow::{bail, Context, Result};
-use chrono::{Datelike, DateTime, NaiveDate, Utc};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Datelike, DateTime, NaiveDate, SecondsFormat, Utc};
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
-/// Number of days to search back when completing tasks
-const TASK_COMPLETION_SEARCH_DAYS: i64 = 30;
-
-/// Number of days to search back when searching journal entries
-const JOURNAL_SEARCH_DAYS: i64 = 90;
-
-/// Number of days to look back for listing entry IDs (shell completion)
-const ENTRY_IDS_SEARCH_DAYS: i64 = 30;
-
 #[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 enum BulletType {
     Task,
@@ -31,16 +25,157 @@ enum TaskState {
     Complete,
     Migrated,
     Scheduled,
+    /// `[~]`, decided not to do; the text is struck through
+    Cancelled,
+    /// `[/]`, started
+    InProgress,
 }
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+impl TaskState {
+    /// Still to be done: shown in `pending` and carried by migrate and schedule.
+    fn is_open(self) -> bool {
+        matches!(self, TaskState::Incomplete | TaskState::InProgress)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            TaskState::Incomplete => "open",
+            TaskState::Complete => "complete",
+            TaskState::Migrated => "migrated",
+            TaskState::Scheduled => "scheduled",
+            TaskState::Cancelled => "cancelled",
+            TaskState::InProgress => "in progress",
+        }
+    }
+}
+
+/// Marks written before an entry's text: `*` priority, `!` inspiration, `?` to explore.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum Signifier {
+    Priority,
+    Inspiration,
+    Explore,
+}
+
+impl Signifier {
+    const ALL: [Signifier; 3] = [Signifier::Priority, Signifier::Inspiration, Signifier::Explore];
+
+    fn symbol(self) -> char {
+        match self {
+            Signifier::Priority => '*',
+            Signifier::Inspiration => '!',
+            Signifier::Explore => '?',
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 struct BulletEntry {
     id: String,
     bullet_type: BulletType,
     task_state: Option<TaskState>,
+    /// Text without the signifiers
     content: String,
-    created_at: DateTime<Utc>,
+    /// Written as `- [ ] *! text {id:… sig:*!}`, in `Signifier::ALL` order; the `sig:` field
+    /// tells them apart from text that happens to start with `*`, `!` or `?`
+    signifiers: Vec<Signifier>,
+    /// Stored as `created:` in the `{id:…}` trailer; missing on entries written before it was
+    created_at: Option<DateTime<Utc>>,
     date: NaiveDate,
+    /// Leading spaces of the line; entries indented under another entry are its children
+    indent: usize,
+    /// Id of the entry this one is nested under
+    parent: Option<String>,
+    /// On a copy made by `schedule`, the id of the original task (`from:` in the trailer)
+    scheduled_from: Option<String>,
+    /// On a scheduled task, the key of the day or month it was scheduled to (`to:`)
+    scheduled_to: Option<String>,
+    /// On an entry added by a recurrence, the recurrence's id (`recur:`)
+    recurrence: Option<String>,
+    /// Trailer fields this version does not know, kept so they survive a rewrite
+    extra_fields: Vec<(String, String)>,
+}
+
+impl BulletEntry {
+    /// A new entry; signifiers typed at the start of `content` are split off.
+    fn new(content: &str, bullet_type: BulletType, date: NaiveDate) -> Self {
+        let (signifiers, content) = split_signifiers(content);
+        Self {
+            id: generate_entry_id(),
+            bullet_type,
+            task_state: match bullet_type {
+                BulletType::Task => Some(TaskState::Incomplete),
+                _ => None,
+            },
+            content: content.to_string(),
+            signifiers,
+            created_at: Some(Utc::now()),
+            date,
+            indent: 0,
+            parent: None,
+            scheduled_from: None,
+            scheduled_to: None,
+            recurrence: None,
+            extra_fields: Vec::new(),
+        }
+    }
+
+    /// An open copy of this task for another day, keeping its text, signifiers and whether
+    /// it was in progress.
+    fn carry_over(&self, date: NaiveDate) -> Self {
+        let mut copy = Self::new("", BulletType::Task, date);
+        copy.content = self.content.clone();
+        copy.signifiers = self.signifiers.clone();
+        if self.task_state == Some(TaskState::InProgress) {
+            copy.task_state = Some(TaskState::InProgress);
+        }
+        copy
+    }
+
+    fn is_open_task(&self) -> bool {
+        self.bullet_type == BulletType::Task && self.task_state.is_some_and(TaskState::is_open)
+    }
+
+    fn has_signifier(&self, signifier: Signifier) -> bool {
+        self.signifiers.contains(&signifier)
+    }
+
+    fn add_signifiers(&mut self, signifiers: &[Signifier]) {
+        self.signifiers = Signifier::ALL
+            .into_iter()
+            .filter(|signifier| self.signifiers.contains(signifier) || signifiers.contains(signifier))
+            .collect();
+    }
+
+    /// The text as written in the file, signifiers first.
+    fn display_content(&self) -> String {
+        if self.signifiers.is_empty() {
+            return self.content.clone();
+        }
+        let symbols: String = self.signifiers.iter().map(|signifier| signifier.symbol()).collect();
+        format!("{} {}", symbols, self.content)
+    }
+}
+
+/// One line of a period file.
+// Most lines are entries, so boxing the entry would not make files smaller in memory
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+enum JournalLine {
+    Entry {
+        entry: BulletEntry,
+        /// The line as read and the entry it parsed to, so unchanged entries are written as-is
+        original: Option<Box<(String, BulletEntry)>>,
+    },
+    /// Headings, free text and anything else that is not an entry, kept verbatim
+    Text(String),
+}
+
+/// A parsed daily, weekly or monthly file.
+#[derive(Clone, Debug)]
+struct JournalFile {
+    lines: Vec<JournalLine>,
+    trailing_newline: bool,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -58,94 +193,481 @@ struct JournalEntryRef {
     content_preview: String,
 }
 
+/// Where every entry lives, by id. Reconciled with the period files on load and updated on
+/// every write, so lookups by id never need to scan the journal.
 #[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
 struct JournalIndex {
     entries: HashMap<String, JournalEntryRef>,
+    /// Size and modification time of each period file, by key, as of its last indexing
+    #[serde(default)]
+    files: HashMap<String, FileStamp>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct FileStamp {
+    len: u64,
+    modified_ns: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified_ns = metadata
+            .modified()
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_nanos() as u64;
+        Some(Self {
+            len: metadata.len(),
+            modified_ns,
+        })
+    }
+}
+
+/// What `bullet migrate --interactive` does with one task.
+enum MigrateChoice {
+    Migrate,
+    Schedule(NaiveDate, JournalPeriod),
+    Cancel,
+    Skip,
+}
+
+#[derive(Default)]
+struct MigrateSummary {
+    /// Ids of the copies in the target log
+    migrated: Vec<String>,
+    scheduled: usize,
+    cancelled: usize,
+}
+
+/// How often a recurring entry repeats, which also decides the logs it goes into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+enum RecurrenceRule {
+    /// Every daily log
+    Day,
+    /// Monday to Friday
+    Weekday,
+    Weekend,
+    DayOfWeek(chrono::Weekday),
+    /// One day of the month; shorter months use their last day
+    DayOfMonth(u32),
+    LastOfMonth,
+    /// Every weekly log
+    Week,
+    /// Every monthly log
+    Month,
+}
+
+impl RecurrenceRule {
+    fn parse(input: &str) -> Result<Self> {
+        let rule = input.trim().to_lowercase();
+        let parsed = match rule.as_str() {
+            "day" | "daily" => Some(Self::Day),
+            "weekday" | "weekdays" => Some(Self::Weekday),
+            "weekend" | "weekends" => Some(Self::Weekend),
+            "last-of-month" => Some(Self::LastOfMonth),
+            "week" | "weekly" => Some(Self::Week),
+            "month" | "monthly" => Some(Self::Month),
+            _ => rule
+                .strip_suffix("-of-month")
+                .and_then(parse_ordinal)
+                .map(Self::DayOfMonth)
+                .or_else(|| rule.parse::<chrono::Weekday>().ok().map(Self::DayOfWeek)),
+        };
+        parsed.ok_or_else(|| {
+            anyhow!(
+                "Unknown recurrence: {}. Use day, weekday, weekend, a day of the week such as \
+                 monday, week, month, 1st-of-month or last-of-month",
+                input
+            )
+        })
+    }
+
+    fn period(self) -> JournalPeriod {
+        match self {
+            Self::Week => JournalPeriod::Weekly,
+            Self::Month => JournalPeriod::Monthly,
+            _ => JournalPeriod::Daily,
+        }
+    }
+
+    /// Whether the log starting on `date` gets an entry.
+    fn matches(self, date: NaiveDate) -> bool {
+        let last_day = last_day_of_month(date);
+        match self {
+            Self::Day | Self::Week | Self::Month => true,
+            Self::Weekday => date.weekday().number_from_monday() <= 5,
+            Self::Weekend => date.weekday().number_from_monday() > 5,
+            Self::DayOfWeek(day) => date.weekday() == day,
+            Self::DayOfMonth(day) => date.day() == day.min(last_day),
+            Self::LastOfMonth => date.day() == last_day,
+        }
+    }
+}
+
+impl TryFrom<String> for RecurrenceRule {
+    type Error = anyhow::Error;
+
+    fn try_from(rule: String) -> Result<Self> {
+        Self::parse(&rule)
+    }
+}
+
+impl From<RecurrenceRule> for String {
+    fn from(rule: RecurrenceRule) -> Self {
+        rule.to_string()
+    }
+}
+
+impl std::fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Day => write!(f, "day"),
+            Self::Weekday => write!(f, "weekday"),
+            Self::Weekend => write!(f, "weekend"),
+            Self::DayOfWeek(day) => {
+                let name = match day {
+                    chrono::Weekday::Mon => "monday",
+                    chrono::Weekday::Tue => "tuesday",
+                    chrono::Weekday::Wed => "wednesday",
+                    chrono::Weekday::Thu => "thursday",
+                    chrono::Weekday::Fri => "friday",
+                    chrono::Weekday::Sat => "saturday",
+                    chrono::Weekday::Sun => "sunday",
+                };
+                write!(f, "{}", name)
+            }
+            Self::DayOfMonth(day) => {
+                let suffix = match (day % 10, day % 100) {
+                    (_, 11..=13) => "th",
+                    (1, _) => "st",
+                    (2, _) => "nd",
+                    (3, _) => "rd",
+                    _ => "th",
+                };
+                write!(f, "{}{}-of-month", day, suffix)
+            }
+            Self::LastOfMonth => write!(f, "last-of-month"),
+            Self::Week => write!(f, "week"),
+            Self::Month => write!(f, "month"),
+        }
+    }
+}
+
+/// An entry added to every log its rule matches, kept in `journal/recurrences.json`.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct Recurrence {
+    id: String,
+    bullet_type: BulletType,
+    /// Text as it is added, signifiers first
+    content: String,
+    every: RecurrenceRule,
+    /// First day an entry can fall on
+    start: NaiveDate,
+    #[serde(default)]
+    paused: bool,
+    /// Keys of the current and upcoming logs that already got their entry
+    #[serde(default)]
+    added_to: Vec<String>,
 }
 
 struct BulletJournal {
     paths: DataPaths,
     index: JournalIndex,
+    /// Raw index file as loaded, for the operation journal
+    index_before: Option<String>,
+    recurrences: Vec<Recurrence>,
+    /// Period files written since load, with their previous content
+    touched: Vec<(PathBuf, Option<FileContent>)>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_bullet_command(
     action: Option<BulletAction>,
     text: Vec<String>,
-    task: bool,
+    _task: bool,
     event: bool,
     note: bool,
+    priority: bool,
+    inspiration: bool,
+    explore: bool,
     date: Option<String>,
     weekly: bool,
     monthly: bool,
+    every: Option<String>,
 ) -> Result<()> {
     let paths = DataPaths::new()?;
     let mut journal = BulletJournal::load(paths)?;
 
     if let Some(action) = action {
+        let mut operation = None;
         match action {
+            BulletAction::Add {
+                text,
+                task: _,
+                event,
+                note,
+                priority,
+                inspiration,
+                explore,
+                date,
+                weekly,
+                monthly,
+                every,
+            } => {
+                return add_command(
+                    &mut journal,
+                    &text,
+                    event,
+                    note,
+                    priority,
+                    inspiration,
+                    explore,
+                    date,
+                    weekly,
+                    monthly,
+                    every,
+                );
+            }
             BulletAction::List {
                 date: list_date,
                 week,
                 month,
+                priority,
             } => {
-                let today = Utc::now().date_naive();
-                let entries = if week {
-                    let week_num = today.iso_week().week();
-                    let year = today.iso_week().year();
-                    journal.list_weekly(year, week_num)?
-                } else if month {
-                    journal.list_monthly(today.year(), today.month())?
-                } else {
-                    let target_date =
-                        list_date.map(|d| parse_date(&d)).transpose()?.unwrap_or(today);
-                    journal.list_daily(target_date)?
+                let (target_date, period) = target_log(list_date, week, month)?;
+                journal.add_recurring_entries(target_date, period)?;
+                let entries = match period {
+                    JournalPeriod::Daily => journal.list_daily(target_date)?,
+                    JournalPeriod::Weekly => {
+                        let week = target_date.iso_week();
+                        journal.list_weekly(week.year(), week.week())?
+                    }
+                    JournalPeriod::Monthly => {
+                        journal.list_monthly(target_date.year(), target_date.month())?
+                    }
                 };
+                let entries: Vec<BulletEntry> = entries
+                    .into_iter()
+                    .filter(|entry| !priority || entry.has_signifier(Signifier::Priority))
+                    .collect();
                 print_entries(&entries);
             }
             BulletAction::Pending { days } => {
+                let today = Utc::now().date_naive();
+                for period in [JournalPeriod::Daily, JournalPeriod::Weekly, JournalPeriod::Monthly] {
+                    journal.add_recurring_entries(today, period)?;
+                }
                 let entries = journal.list_pending(days)?;
                 print_entries(&entries);
             }
-            BulletAction::Complete { entry } => {
-                journal.complete_task(&entry)?;
-                println!("Marked complete: {}", entry);
+            BulletAction::Complete { entry, subtasks } => {
+                let completed_subtasks = journal.complete_task(&entry, subtasks)?;
+                if completed_subtasks > 0 {
+                    println!(
+                        "Marked complete: {} (and {} sub-task(s))",
+                        entry, completed_subtasks
+                    );
+                } else {
+                    println!("Marked complete: {}", entry);
+                }
+                operation = Some(("bullet complete", format!("Complete {}", entry)));
+            }
+            BulletAction::Cancel { entry } => {
+                journal.change_task_state(
+                    &entry,
+                    TaskState::Cancelled,
+                    &[TaskState::Incomplete, TaskState::InProgress],
+                )?;
+                println!("Cancelled: {}", entry);
+                operation = Some(("bullet cancel", format!("Cancel {}", entry)));
+            }
+            BulletAction::Start { entry } => {
+                journal.change_task_state(&entry, TaskState::InProgress, &[TaskState::Incomplete])?;
+                println!("Started: {}", entry);
+                operation = Some(("bullet start", format!("Start {}", entry)));
+            }
+            BulletAction::Reopen { entry } => {
+                journal.change_task_state(
+                    &entry,
+                    TaskState::Incomplete,
+                    &[TaskState::Complete, TaskState::Cancelled, TaskState::InProgress],
+                )?;
+                println!("Reopened: {}", entry);
+                operation = Some(("bullet reopen", format!("Reopen {}", entry)));
+            }
+            BulletAction::Schedule { entry, to, month } => {
+                let (date, period) = match (to, month) {
+                    (_, Some(month)) => {
+                        let (date, _) = parse_when(&month)?;
+                        (period_start(date, JournalPeriod::Monthly), JournalPeriod::Monthly)
+                    }
+                    (Some(to), None) => parse_when(&to)?,
+                    (None, None) => bail!("Pass --to <date> or --month <YYYY-MM>"),
+                };
+                ensure_not_past(date, period)?;
+                let (id, key) = journal.schedule_task(&entry, date, period)?;
+                println!("Scheduled {} for {} [{}]", entry, key, &id[..4.min(id.len())]);
+                operation = Some(("bullet schedule", format!("Schedule {} for {}", entry, key)));
+            }
+            BulletAction::Edit { entry, text } => {
+                journal.edit_entry(&entry, &text.join(" "))?;
+                println!("Edited: {}", entry);
+                operation = Some(("bullet edit", format!("Edit {}", entry)));
+            }
+            BulletAction::Rm { entry } => {
+                let removed_children = journal.remove_entry(&entry)?;
+                if removed_children > 0 {
+                    println!("Deleted: {} (and {} sub-entries)", entry, removed_children);
+                } else {
+                    println!("Deleted: {}", entry);
+                }
+                operation = Some(("bullet rm", format!("Delete {}", entry)));
+            }
+            BulletAction::Move {
+                entry,
+                date: move_date,
+                weekly: move_weekly,
+                monthly: move_monthly,
+            } => {
+                let (target_date, period) = target_log(move_date, move_weekly, move_monthly)?;
+                let key = journal.move_entry(&entry, target_date, period)?;
+                println!("Moved {} to {}", entry, key);
+                operation = Some(("bullet move", format!("Move {} to {}", entry, key)));
+            }
+            BulletAction::Retype {
+                entry,
+                task: _,
+                event: retype_event,
+                note: retype_note,
+            } => {
+                let bullet_type = if retype_event {
+                    BulletType::Event
+                } else if retype_note {
+                    BulletType::Note
+                } else {
+                    BulletType::Task
+                };
+                journal.retype_entry(&entry, bullet_type)?;
+                println!("Retyped {} as {}", entry, type_name(bullet_type));
+                operation = Some(("bullet retype", format!("Retype {}", entry)));
+            }
+            BulletAction::Future => {
+                let entries = journal.list_future()?;
+                print_entries(&entries);
             }
-            BulletAction::Migrate { all, from } => {
+            BulletAction::Migrate {
+                all,
+                from,
+                week,
+                month,
+                interactive,
+            } => {
                 let today = Utc::now().date_naive();
-                let from_date = from
-                    .map(|d| parse_date(&d))
-                    .transpose()?
-                    .unwrap_or(today - chrono::Duration::days(1));
-                let migrated = journal.migrate_tasks(from_date, all)?;
-                if migrated.is_empty() {
+                let from = from.map(|d| parse_when(&d)).transpose()?;
+                // A week or month given to --from migrates that whole week or month
+                let period = if month {
+                    JournalPeriod::Monthly
+                } else if week {
+                    JournalPeriod::Weekly
+                } else {
+                    from.map_or(JournalPeriod::Daily, |(_, period)| period)
+                };
+                let (default_from, target) = match period {
+                    JournalPeriod::Monthly => {
+                        let this_month = period_start(today, JournalPeriod::Monthly);
+                        (this_month - chrono::Duration::days(1), "this month's log")
+                    }
+                    JournalPeriod::Weekly => (today - chrono::Duration::days(7), "this week's log"),
+                    JournalPeriod::Daily => (today - chrono::Duration::days(1), "today"),
+                };
+                let from_date = from.map_or(default_from, |(date, _)| date);
+                let source = if all {
+                    "earlier days".to_string()
+                } else {
+                    journal.period_file(from_date, period).1
+                };
+
+                let sources = journal.migrate_sources(from_date, period, all)?;
+                let summary = journal.migrate_tasks(&sources, today, period, interactive)?;
+                if summary.migrated.is_empty() && summary.scheduled == 0 && summary.cancelled == 0 {
                     println!("No tasks to migrate.");
                 } else {
-                    println!("Migrated {} task(s) to today.", migrated.len());
+                    println!("Migrated {} task(s) to {}.", summary.migrated.len(), target);
+                    if summary.scheduled > 0 {
+                        println!("Scheduled {} task(s).", summary.scheduled);
+                    }
+                    if summary.cancelled > 0 {
+                        println!("Cancelled {} task(s).", summary.cancelled);
+                    }
+                    operation = Some((
+                        "bullet migrate",
+                        format!("Migrate {} task(s) from {}", summary.migrated.len(), source),
+                    ));
                 }
             }
             BulletAction::Open {
                 date: open_date,
                 weekly: open_weekly,
                 monthly: open_monthly,
+                template,
             } => {
-                let today = Utc::now().date_naive();
-                let target_date =
-                    open_date.map(|d| parse_date(&d)).transpose()?.unwrap_or(today);
-                let period = if open_weekly {
-                    JournalPeriod::Weekly
-                } else if open_monthly {
-                    JournalPeriod::Monthly
-                } else {
-                    JournalPeriod::Daily
-                };
-                let path = journal.open_file(target_date, period)?;
+                let (target_date, period) = target_log(open_date, open_weekly, open_monthly)?;
+                let path = journal.open_file(target_date, period, template.as_deref())?;
+                journal.add_recurring_entries(target_date, period)?;
                 launch_subl_if_installed(&path);
                 println!("{}", path.display());
             }
+            BulletAction::Recur { action } => match action.unwrap_or(RecurAction::List) {
+                RecurAction::List => journal.list_recurrences(),
+                RecurAction::Pause { id } => {
+                    let recurrence = journal.set_recurrence_paused(&id, true)?;
+                    println!("Paused: {}", recurrence);
+                }
+                RecurAction::Resume { id } => {
+                    let recurrence = journal.set_recurrence_paused(&id, false)?;
+                    println!("Resumed: {}", recurrence);
+                }
+                RecurAction::Rm { id } => {
+                    let recurrence = journal.remove_recurrence(&id)?;
+                    println!("Deleted recurrence: {}", recurrence);
+                }
+            },
             BulletAction::Search { query } => {
                 let entries = journal.search(&query)?;
                 print_entries(&entries);
             }
+            BulletAction::Export {
+                format:
+                    BulletExportFormat::Pdf {
+                        week,
+                        month: _,
+                        date: export_date,
+                        output,
+                    },
+            } => {
+                let today = Utc::now().date_naive();
+                let target_date = export_date
+                    .map(|d| parse_when(&d))
+                    .transpose()?
+                    .map_or(today, |(date, _)| date);
+                let period = if week {
+                    JournalPeriod::Weekly
+                } else {
+                    JournalPeriod::Monthly
+                };
+                let (title, key, markdown) = journal.review_markdown(target_date, period)?;
+                let target = output.unwrap_or_else(|| format!("journal-{}.pdf", key).into());
+                if let Some(warning) = pdf::unsupported_warning(&markdown) {
+                    eprintln!("{}", warning);
+                }
+                fs::write(&target, pdf::render_markdown(&title, &markdown))
+                    .with_context(|| format!("Failed to write {}", target.display()))?;
+                println!("Exported {} to {}", title, target.display());
+                return Ok(());
+            }
             BulletAction::Interactive => {
                 journal.run_interactive()?;
                 return Ok(());
@@ -156,51 +678,97 @@ pub fn handle_bullet_command(
             }
         }
         journal.save()?;
+        if let Some((action, description)) = operation {
+            journal.record_operation(action, description)?;
+        }
         return Ok(());
     }
 
     if !text.is_empty() {
-        let content = text.join(" ");
-        let today = Utc::now().date_naive();
-        let target_date = date.map(|d| parse_date(&d)).transpose()?.unwrap_or(today);
-
-        let bullet_type = if event {
-            BulletType::Event
-        } else if note {
-            BulletType::Note
-        } else if task {
-            BulletType::Task
-        } else {
-            BulletType::Task
-        };
-
-        let period = if weekly {
-            JournalPeriod::Weekly
-        } else if monthly {
-            JournalPeriod::Monthly
-        } else {
-            JournalPeriod::Daily
-        };
-
-        let id = journal.add_entry(&content, bullet_type, target_date, period)?;
-        journal.save()?;
-
-        let type_name = match bullet_type {
-            BulletType::Task => "task",
-            BulletType::Event => "event",
-            BulletType::Note => "note",
-        };
-        println!("Added {}: {} [{}]", type_name, content, &id[..4.min(id.len())]);
-        return Ok(());
+        return add_command(
+            &mut journal,
+            &text,
+            event,
+            note,
+            priority,
+            inspiration,
+            explore,
+            date,
+            weekly,
+            monthly,
+            every,
+        );
     }
 
     let today = Utc::now().date_naive();
+    journal.add_recurring_entries(today, JournalPeriod::Daily)?;
+    journal.save()?;
     let entries = journal.list_daily(today)?;
     print_entries(&entries);
 
     Ok(())
 }
 
+/// `bullet add` and its `bullet <text>` shorthand.
+#[allow(clippy::too_many_arguments)]
+fn add_command(
+    journal: &mut BulletJournal,
+    text: &[String],
+    event: bool,
+    note: bool,
+    priority: bool,
+    inspiration: bool,
+    explore: bool,
+    date: Option<String>,
+    weekly: bool,
+    monthly: bool,
+    every: Option<String>,
+) -> Result<()> {
+    let content = text.join(" ");
+    let (target_date, period) = target_log(date, weekly, monthly)?;
+
+    let bullet_type = if event {
+        BulletType::Event
+    } else if note {
+        BulletType::Note
+    } else {
+        BulletType::Task
+    };
+
+    let signifiers: Vec<Signifier> = [
+        (priority, Signifier::Priority),
+        (inspiration, Signifier::Inspiration),
+        (explore, Signifier::Explore),
+    ]
+    .into_iter()
+    .filter_map(|(set, signifier)| set.then_some(signifier))
+    .collect();
+
+    if let Some(every) = every {
+        let rule = RecurrenceRule::parse(&every)?;
+        let id = journal.add_recurrence(&content, bullet_type, &signifiers, rule, target_date)?;
+        journal.save()?;
+        println!(
+            "Added recurring {}: {}, every {} [{}]",
+            type_name(bullet_type),
+            content,
+            rule,
+            &id[..4.min(id.len())]
+        );
+        return Ok(());
+    }
+
+    let id = journal.add_entry(&content, bullet_type, &signifiers, target_date, period)?;
+    journal.save()?;
+    println!(
+        "Added {}: {} [{}]",
+        type_name(bullet_type),
+        content,
+        &id[..4.min(id.len())]
+    );
+    Ok(())
+}
+
 pub fn run_interactive() -> Result<()> {
     let paths = DataPaths::new()?;
     let mut journal = BulletJournal::load(paths)?;
@@ -212,94 +780,388 @@ impl BulletJournal {
     fn load(paths: DataPaths) -> Result<Self> {
         paths.ensure_journal_dirs()?;
 
-        let index = if paths.journal_index().exists() {
-            let content = fs::read_to_string(paths.journal_index())
-                .with_context(|| format!("Failed to read {}", paths.journal_index().display()))?;
-            serde_json::from_str::<JournalIndex>(&content).unwrap_or_default()
+        let index_before = if paths.journal_index().exists() {
+            Some(
+                fs::read_to_string(paths.journal_index()).with_context(|| {
+                    format!("Failed to read {}", paths.journal_index().display())
+                })?,
+            )
         } else {
-            JournalIndex::default()
+            None
+        };
+        let index = index_before
+            .as_deref()
+            .map(|content| serde_json::from_str::<JournalIndex>(content).unwrap_or_default())
+            .unwrap_or_default();
+
+        let recurrences = if paths.journal_recurrences().exists() {
+            let content = fs::read_to_string(paths.journal_recurrences()).with_context(|| {
+                format!("Failed to read {}", paths.journal_recurrences().display())
+            })?;
+            serde_json::from_str(&content).with_context(|| {
+                format!("Failed to parse {}", paths.journal_recurrences().display())
+            })?
+        } else {
+            Vec::new()
         };
 
-        Ok(Self { paths, index })
+        let mut journal = Self {
+            paths,
+            index,
+            index_before,
+            recurrences,
+            touched: Vec::new(),
+        };
+        journal.sync_index()?;
+        Ok(journal)
     }
 
-    fn save(&self) -> Result<()> {
-        let serialized = serde_json::to_string_pretty(&self.index)?;
-        fs::write(self.paths.journal_index(), serialized)
-            .with_context(|| format!("Failed to write {}", self.paths.journal_index().display()))
+    /// Re-indexes period files that were added, changed (for example in an editor) or removed
+    /// since they were last indexed.
+    fn sync_index(&mut self) -> Result<()> {
+        let mut present = HashSet::new();
+        for (period, dir) in [
+            (JournalPeriod::Daily, self.paths.journal_daily_dir()),
+            (JournalPeriod::Weekly, self.paths.journal_weekly_dir()),
+            (JournalPeriod::Monthly, self.paths.journal_monthly_dir()),
+        ] {
+            let Ok(files) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for file in files {
+                let path = file?.path();
+                let Some(key) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    continue;
+                };
+                if path.extension().is_none_or(|ext| ext != "md")
+                    || period_key_date(period, key).is_none()
+                {
+                    continue;
+                }
+                present.insert(key.to_string());
+                if self.index.files.get(key) != FileStamp::of(&path).as_ref() {
+                    self.reindex_file(&path)?;
+                }
+            }
+        }
+
+        self.index.files.retain(|key, _| present.contains(key));
+        self.index
+            .entries
+            .retain(|_, entry| present.contains(&entry.date_key));
+        Ok(())
     }
 
-    fn add_entry(
+    /// Replaces the index entries of one period file with what the file contains now.
+    fn reindex_file(&mut self, path: &Path) -> Result<()> {
+        let Some(key) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            return Ok(());
+        };
+        let key = key.to_string();
+        self.index.entries.retain(|_, entry| entry.date_key != key);
+        self.index.files.remove(&key);
+
+        let Some((_, date)) = key_file(&self.paths, &key) else {
+            return Ok(());
+        };
+        let Ok(content) = fs::read_to_string(path) else {
+            return Ok(());
+        };
+        for entry in parse_journal_file(&content, date)?.entries() {
+            self.index.entries.insert(
+                entry.id.clone(),
+                JournalEntryRef {
+                    date_key: key.clone(),
+                    bullet_type: entry.bullet_type,
+                    task_state: entry.task_state,
+                    content_preview: preview(&entry.content, 50),
+                },
+            );
+        }
+        if let Some(stamp) = FileStamp::of(path) {
+            self.index.files.insert(key, stamp);
+        }
+        Ok(())
+    }
+
+    /// Writes a period file, remembering its previous content the first time it is touched,
+    /// and re-indexes it.
+    fn write_file(&mut self, path: &Path, content: &str) -> Result<()> {
+        self.remember(path);
+        fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+        self.reindex_file(path)
+    }
+
+    /// Keeps the content of `path` from before its first write since load.
+    fn remember(&mut self, path: &Path) {
+        if !self.touched.iter().any(|(touched, _)| touched == path) {
+            self.touched.push((path.to_path_buf(), read_optional(path)));
+        }
+    }
+
+    fn save_recurrences(&mut self) -> Result<()> {
+        let path = self.paths.journal_recurrences();
+        self.remember(&path);
+        let serialized = serde_json::to_string_pretty(&self.recurrences)?;
+        fs::write(&path, serialized)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Adds a recurring entry from `start` on, and its entry in the current log when that
+    /// matches. Returns the recurrence id.
+    fn add_recurrence(
         &mut self,
         content: &str,
         bullet_type: BulletType,
-        date: NaiveDate,
-        period: JournalPeriod,
+        signifiers: &[Signifier],
+        every: RecurrenceRule,
+        start: NaiveDate,
     ) -> Result<String> {
+        let mut entry = BulletEntry::new(content, bullet_type, start);
+        entry.add_signifiers(signifiers);
         let id = generate_entry_id();
-        let now = Utc::now();
+        self.recurrences.push(Recurrence {
+            id: id.clone(),
+            bullet_type,
+            content: entry.display_content(),
+            every,
+            start,
+            paused: false,
+            added_to: Vec::new(),
+        });
+        self.save_recurrences()?;
+        self.add_recurring_entries(Utc::now().date_naive(), every.period())?;
+        Ok(id)
+    }
 
-        let task_state = match bullet_type {
-            BulletType::Task => Some(TaskState::Incomplete),
-            _ => None,
-        };
+    /// Adds the entries of active recurrences that match the `period` log containing `date` and
+    /// have not been added to it. Logs before the current day, week or month are left alone.
+    /// Returns the number of entries added.
+    fn add_recurring_entries(&mut self, date: NaiveDate, period: JournalPeriod) -> Result<usize> {
+        let today = Utc::now().date_naive();
+        let log_start = period_start(date, period);
+        if log_start < period_start(today, period) {
+            return Ok(0);
+        }
+        let (file_path, key) = self.period_file(date, period);
+        let due: Vec<usize> = (0..self.recurrences.len())
+            .filter(|index| {
+                let recurrence = &self.recurrences[*index];
+                !recurrence.paused
+                    && recurrence.every.period() == period
+                    && period_start(recurrence.start, period) <= log_start
+                    && recurrence.every.matches(log_start)
+                    && !recurrence.added_to.contains(&key)
+            })
+            .collect();
+        if due.is_empty() {
+            return Ok(0);
+        }
 
-        let preview = if content.len() > 50 {
-            format!("{}...", &content[..47])
-        } else {
-            content.to_string()
-        };
+        let mut file = read_period_file(&file_path, date, period, &key)?;
+        let mut added = 0;
+        for index in due {
+            let recurrence = &mut self.recurrences[index];
+            // Already there when the list of added logs was lost, for example by an import
+            if !file
+                .entries()
+                .any(|entry| entry.recurrence.as_deref() == Some(recurrence.id.as_str()))
+            {
+                let mut entry = BulletEntry::new(&recurrence.content, recurrence.bullet_type, date);
+                entry.recurrence = Some(recurrence.id.clone());
+                file.push(entry);
+                added += 1;
+            }
+            recurrence.added_to.push(key.clone());
+        }
 
-        let entry = BulletEntry {
-            id: id.clone(),
-            bullet_type,
-            task_state,
-            content: content.to_string(),
-            created_at: now,
-            date,
-        };
+        // Past logs never get entries, so their keys need not be kept
+        for recurrence in &mut self.recurrences {
+            let current = period_start(today, recurrence.every.period());
+            recurrence
+                .added_to
+                .retain(|key| key_file(&self.paths, key).is_some_and(|(_, date)| date >= current));
+        }
 
-        let (file_path, date_key) = match period {
-            JournalPeriod::Daily => {
-                let path = self.paths.daily_file(date);
-                let key = date.format("%Y-%m-%d").to_string();
-                (path, key)
+        if added > 0 {
+            self.write_file(&file_path, &format_journal_file(&file))?;
+        }
+        self.save_recurrences()?;
+        Ok(added)
+    }
+
+    /// Index of the recurrence whose id starts with `partial_id`.
+    fn find_recurrence(&self, partial_id: &str) -> Result<usize> {
+        let matches: Vec<usize> = (0..self.recurrences.len())
+            .filter(|index| self.recurrences[*index].id.starts_with(partial_id))
+            .collect();
+        match matches.as_slice() {
+            [] => bail!("Recurrence not found: {}", partial_id),
+            [index] => Ok(*index),
+            _ => {
+                if let Some(index) = matches
+                    .iter()
+                    .find(|index| self.recurrences[**index].id == partial_id)
+                {
+                    return Ok(*index);
+                }
+                let candidates: Vec<String> = matches
+                    .iter()
+                    .map(|index| {
+                        let recurrence = &self.recurrences[*index];
+                        format!("  {}  {}", recurrence.id, recurrence.content)
+                    })
+                    .collect();
+                bail!(
+                    "Ambiguous recurrence id: {} matches\n{}",
+                    partial_id,
+                    candidates.join("\n")
+                )
             }
-            JournalPeriod::Weekly => {
-                let week = date.iso_week().week();
-                let year = date.iso_week().year();
-                let path = self.paths.weekly_file(year, week);
-                let key = format!("{}-W{:02}", year, week);
-                (path, key)
-            }
-            JournalPeriod::Monthly => {
-                let path = self.paths.monthly_file(date.year(), date.month());
-                let key = format!("{}-{:02}", date.year(), date.month());
-                (path, key)
+        }
+    }
+
+    fn list_recurrences(&self) {
+        if self.recurrences.is_empty() {
+            println!("No recurring entries.");
+            return;
+        }
+        for recurrence in &self.recurrences {
+            let marker = match recurrence.bullet_type {
+                BulletType::Task => ' ',
+                BulletType::Event => 'o',
+                BulletType::Note => '-',
+            };
+            println!(
+                "{}  [{}] {}  every {} from {}{}",
+                &recurrence.id[..8.min(recurrence.id.len())],
+                marker,
+                recurrence.content,
+                recurrence.every,
+                recurrence.start,
+                if recurrence.paused { " (paused)" } else { "" }
+            );
+        }
+    }
+
+    /// Pauses or resumes a recurrence. Returns its text.
+    fn set_recurrence_paused(&mut self, partial_id: &str, paused: bool) -> Result<String> {
+        let index = self.find_recurrence(partial_id)?;
+        let recurrence = &mut self.recurrences[index];
+        if recurrence.paused == paused {
+            bail!(
+                "Recurrence {} is already {}",
+                partial_id,
+                if paused { "paused" } else { "active" }
+            );
+        }
+        recurrence.paused = paused;
+        let content = recurrence.content.clone();
+        self.save_recurrences()?;
+        Ok(content)
+    }
+
+    /// Deletes a recurrence; entries it already added stay. Returns its text.
+    fn remove_recurrence(&mut self, partial_id: &str) -> Result<String> {
+        let index = self.find_recurrence(partial_id)?;
+        let recurrence = self.recurrences.remove(index);
+        self.save_recurrences()?;
+        Ok(recurrence.content)
+    }
+
+    /// The period file holding the entry whose id starts with `partial_id`, parsed, with the
+    /// entry's full id.
+    fn find_entry(&self, partial_id: &str) -> Result<Option<(PathBuf, JournalFile, String)>> {
+        let mut matches: Vec<(&String, &JournalEntryRef)> = self
+            .index
+            .entries
+            .iter()
+            .filter(|(id, _)| id.starts_with(partial_id))
+            .collect();
+        if matches.len() > 1 {
+            if let Some(exact) = matches.iter().position(|(id, _)| id.as_str() == partial_id) {
+                matches = vec![matches[exact]];
+            } else {
+                matches.sort_by(|a, b| (&a.1.date_key, a.0).cmp(&(&b.1.date_key, b.0)));
+                let candidates: Vec<String> = matches
+                    .iter()
+                    .map(|(id, entry)| {
+                        format!("  {}  {}  {}", id, entry.date_key, entry.content_preview)
+                    })
+                    .collect();
+                bail!(
+                    "Ambiguous entry id: {} matches\n{}",
+                    partial_id,
+                    candidates.join("\n")
+                );
             }
+        }
+        let Some((id, entry_ref)) = matches.first() else {
+            return Ok(None);
         };
-
-        let mut entries = if file_path.exists() {
-            let file_content = fs::read_to_string(&file_path)?;
-            parse_journal_file(&file_content, date)?
-        } else {
-            Vec::new()
+        let Some((path, date)) = key_file(&self.paths, &entry_ref.date_key) else {
+            return Ok(None);
         };
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let file = parse_journal_file(&content, date)?;
+        if !file.entries().any(|entry| entry.id == **id) {
+            return Ok(None);
+        }
+        Ok(Some((path, file, id.to_string())))
+    }
 
-        entries.push(entry);
+    /// Records the files written since load, plus the index, as one undoable operation.
+    /// Must be called after `save`.
+    fn record_operation(&self, action: &str, description: String) -> Result<()> {
+        let mut changes: Vec<Change> = self
+            .touched
+            .iter()
+            .map(|(path, before)| Change::File {
+                path: relative_path(&self.paths, path),
+                before: before.clone(),
+                after: read_optional(path),
+            })
+            .collect();
+        changes.push(Change::File {
+            path: relative_path(&self.paths, &self.paths.journal_index()),
+            before: self.index_before.clone().map(FileContent::from),
+            after: read_optional(&self.paths.journal_index()),
+        });
+        oplog::record(&self.paths, action, description, changes)
+    }
 
-        let file_content = format_journal_file(&entries, period, &date_key);
-        fs::write(&file_path, &file_content)?;
+    /// Saves pending changes and starts a new operation from the current state, so that
+    /// `record_operation` only covers what is written afterwards.
+    fn start_operation(&mut self) -> Result<()> {
+        self.save()?;
+        self.index_before = Some(serde_json::to_string_pretty(&self.index)?);
+        self.touched.clear();
+        Ok(())
+    }
 
-        self.index.entries.insert(
-            id.clone(),
-            JournalEntryRef {
-                date_key,
-                bullet_type,
-                task_state,
-                content_preview: preview,
-            },
-        );
+    fn save(&self) -> Result<()> {
+        let serialized = serde_json::to_string_pretty(&self.index)?;
+        fs::write(self.paths.journal_index(), serialized)
+            .with_context(|| format!("Failed to write {}", self.paths.journal_index().display()))
+    }
+
+    fn add_entry(
+        &mut self,
+        content: &str,
+        bullet_type: BulletType,
+        signifiers: &[Signifier],
+        date: NaiveDate,
+        period: JournalPeriod,
+    ) -> Result<String> {
+        let mut entry = BulletEntry::new(content, bullet_type, date);
+        entry.add_signifiers(signifiers);
+        let id = entry.id.clone();
+
+        let (file_path, date_key) = self.period_file(date, period);
+        let mut file = read_period_file(&file_path, date, period, &date_key)?;
+        file.push(entry);
+        self.write_file(&file_path, &format_journal_file(&file))?;
 
         Ok(id)
     }
@@ -310,7 +1172,7 @@ impl BulletJournal {
             return Ok(Vec::new());
         }
         let content = fs::read_to_string(&file_path)?;
-        parse_journal_file(&content, date)
+        Ok(parse_journal_file(&content, date)?.into_entries())
     }
 
     fn list_weekly(&self, year: i32, week: u32) -> Result<Vec<BulletEntry>> {
@@ -321,7 +1183,7 @@ impl BulletJournal {
         let content = fs::read_to_string(&file_path)?;
         let date = NaiveDate::from_isoywd_opt(year, week, chrono::Weekday::Mon)
             .unwrap_or_else(|| Utc::now().date_naive());
-        parse_journal_file(&content, date)
+        Ok(parse_journal_file(&content, date)?.into_entries())
     }
 
     fn list_monthly(&self, year: i32, month: u32) -> Result<Vec<BulletEntry>> {
@@ -332,151 +1194,466 @@ impl BulletJournal {
         let content = fs::read_to_string(&file_path)?;
         let date = NaiveDate::from_ymd_opt(year, month, 1)
             .unwrap_or_else(|| Utc::now().date_naive());
-        parse_journal_file(&content, date)
+        Ok(parse_journal_file(&content, date)?.into_entries())
     }
 
-    fn list_pending(&self, days_back: u32) -> Result<Vec<BulletEntry>> {
+    /// Open tasks from every log whose day, week or month has started, newest log first, as
+    /// found through the index. With `days_back`, only logs for periods that overlap the last
+    /// `days_back` days. Priority tasks come first.
+    fn list_pending(&self, days_back: Option<u32>) -> Result<Vec<BulletEntry>> {
         let today = Utc::now().date_naive();
+        let since = days_back.map(|days| today - chrono::Duration::days(days.max(1) as i64 - 1));
         let mut pending = Vec::new();
 
-        for i in 0..days_back {
-            let date = today - chrono::Duration::days(i as i64);
-            let entries = self.list_daily(date)?;
-            for entry in entries {
-                if entry.bullet_type == BulletType::Task
-                    && entry.task_state == Some(TaskState::Incomplete)
-                {
+        let open_keys: HashSet<&str> = self
+            .index
+            .entries
+            .values()
+            .filter(|entry| entry.task_state.is_some_and(TaskState::is_open))
+            .map(|entry| entry.date_key.as_str())
+            .collect();
+        let keys = self.indexed_keys().into_iter().filter(|key| {
+            let Some((_, start)) = key_file(&self.paths, key) else {
+                return false;
+            };
+            open_keys.contains(key.as_str())
+                && start <= today
+                && since.is_none_or(|since| start >= period_start(since, key_period(key)))
+        });
+
+        for key in keys {
+            for entry in self.read_key(&key)? {
+                if entry.is_open_task() {
                     pending.push(entry);
                 }
             }
         }
 
+        pending.sort_by_key(|entry| !entry.has_signifier(Signifier::Priority));
         Ok(pending)
     }
 
-    fn complete_task(&mut self, partial_id: &str) -> Result<()> {
-        let today = Utc::now().date_naive();
+    /// Marks an open or started task complete, and with `subtasks` its open sub-tasks too.
+    /// Returns the number of sub-tasks completed.
+    fn complete_task(&mut self, partial_id: &str, subtasks: bool) -> Result<usize> {
+        let Some((file_path, mut file, id)) = self.find_task(partial_id)? else {
+            bail!("Entry not found: {}", partial_id)
+        };
+        let current = file
+            .entries()
+            .find(|entry| entry.id == id)
+            .and_then(|entry| entry.task_state)
+            .unwrap_or(TaskState::Incomplete);
+        if !current.is_open() {
+            let hint = if current == TaskState::Cancelled {
+                " (use `bullet reopen` first)"
+            } else {
+                ""
+            };
+            bail!(
+                "Task {} is {}; only open or in progress tasks can be made complete{}",
+                partial_id,
+                current.name(),
+                hint
+            );
+        }
+        let descendants = if subtasks {
+            file.descendants(&id)
+        } else {
+            Vec::new()
+        };
 
-        for i in 0..TASK_COMPLETION_SEARCH_DAYS {
-            let date = today - chrono::Duration::days(i);
-            let file_path = self.paths.daily_file(date);
-            if !file_path.exists() {
+        let mut completed_subtasks = 0;
+        for entry in file.entries_mut() {
+            let is_open_subtask = descendants.contains(&entry.id) && entry.is_open_task();
+            if entry.id != id && !is_open_subtask {
                 continue;
             }
+            entry.task_state = Some(TaskState::Complete);
+            if is_open_subtask {
+                completed_subtasks += 1;
+            }
+        }
 
-            let content = fs::read_to_string(&file_path)?;
-            let mut entries = parse_journal_file(&content, date)?;
-            let mut found = false;
+        self.write_file(&file_path, &format_journal_file(&file))?;
+        Ok(completed_subtasks)
+    }
 
-            for entry in &mut entries {
-                if entry.id.starts_with(partial_id) && entry.bullet_type == BulletType::Task {
-                    entry.task_state = Some(TaskState::Complete);
-                    found = true;
+    /// Moves a task to `state` if it is currently in one of `from`.
+    fn change_task_state(
+        &mut self,
+        partial_id: &str,
+        state: TaskState,
+        from: &[TaskState],
+    ) -> Result<()> {
+        let Some((file_path, mut file, id)) = self.find_task(partial_id)? else {
+            bail!("Entry not found: {}", partial_id)
+        };
+        let Some(task) = file.entries_mut().find(|entry| entry.id == id) else {
+            bail!("Entry not found: {}", partial_id)
+        };
+        let current = task.task_state.unwrap_or(TaskState::Incomplete);
+        if !from.contains(&current) {
+            bail!(
+                "Task {} is {}; only {} tasks can be made {}",
+                partial_id,
+                current.name(),
+                from.iter().map(|state| state.name()).collect::<Vec<_>>().join(" or "),
+                state.name()
+            );
+        }
+        task.task_state = Some(state);
+        self.write_file(&file_path, &format_journal_file(&file))
+    }
 
-                    if let Some(ref_entry) = self.index.entries.get_mut(&entry.id) {
-                        ref_entry.task_state = Some(TaskState::Complete);
+    /// Like `find_entry`, for tasks only.
+    fn find_task(&self, partial_id: &str) -> Result<Option<(PathBuf, JournalFile, String)>> {
+        let found = self.find_entry(partial_id)?;
+        if let Some((_, file, id)) = &found
+            && file
+                .entries()
+                .any(|entry| entry.id == *id && entry.bullet_type != BulletType::Task)
+        {
+            bail!("Entry {} is not a task", partial_id);
+        }
+        Ok(found)
+    }
+
+    /// Marks an open task `[<]` and adds a copy to the daily or monthly log of `date`, linked
+    /// back to it. Returns the copy's id and the target key.
+    fn schedule_task(
+        &mut self,
+        partial_id: &str,
+        date: NaiveDate,
+        period: JournalPeriod,
+    ) -> Result<(String, String)> {
+        let Some((file_path, file, id)) = self.find_task(partial_id)? else {
+            bail!("Entry not found: {}", partial_id)
+        };
+        self.schedule_entry(&file_path, file, &id, date, period)
+    }
+
+    /// Schedules task `id` of `file`, which was read from `file_path`, and writes both files.
+    fn schedule_entry(
+        &mut self,
+        file_path: &Path,
+        mut file: JournalFile,
+        id: &str,
+        date: NaiveDate,
+        period: JournalPeriod,
+    ) -> Result<(String, String)> {
+        let (target_path, target_key) = self.period_file(date, period);
+        if target_path == file_path {
+            bail!("Task {} is already in {}", id, target_key);
+        }
+
+        let Some(task) = file.entries_mut().find(|entry| entry.id == id) else {
+            bail!("Entry not found: {}", id)
+        };
+        if !task.is_open_task() {
+            bail!("Only open tasks can be scheduled: {}", id);
+        }
+        task.task_state = Some(TaskState::Scheduled);
+        task.scheduled_to = Some(target_key.clone());
+        let mut copy = task.carry_over(date);
+        copy.scheduled_from = Some(id.to_string());
+
+        let copy_id = copy.id.clone();
+
+        let mut target = read_period_file(&target_path, date, period, &target_key)?;
+        target.push(copy);
+        self.write_file(file_path, &format_journal_file(&file))?;
+        self.write_file(&target_path, &format_journal_file(&target))?;
+
+        Ok((copy_id, target_key))
+    }
+
+    /// Replaces an entry's text. Signifiers typed at the start of `text` replace the entry's;
+    /// without any, the entry keeps its own.
+    fn edit_entry(&mut self, partial_id: &str, text: &str) -> Result<String> {
+        let Some((file_path, mut file, id)) = self.find_entry(partial_id)? else {
+            bail!("Entry not found: {}", partial_id)
+        };
+        let (signifiers, content) = split_signifiers(text.trim());
+        if content.is_empty() {
+            bail!("Entry text cannot be empty");
+        }
+        let Some(entry) = file.entries_mut().find(|entry| entry.id == id) else {
+            bail!("Entry not found: {}", partial_id)
+        };
+        entry.content = content.to_string();
+        if !signifiers.is_empty() {
+            entry.signifiers = signifiers;
+        }
+        self.write_file(&file_path, &format_journal_file(&file))?;
+        Ok(id)
+    }
+
+    /// Deletes an entry along with everything nested under it. Returns the number of
+    /// sub-entries deleted.
+    fn remove_entry(&mut self, partial_id: &str) -> Result<usize> {
+        let Some((file_path, mut file, id)) = self.find_entry(partial_id)? else {
+            bail!("Entry not found: {}", partial_id)
+        };
+        let removed = file.take_block(&id);
+        self.write_file(&file_path, &format_journal_file(&file))?;
+        Ok(count_entries(&removed) - 1)
+    }
+
+    /// Moves an entry, with everything nested under it, to the `period` log containing `date`.
+    /// The entry keeps its id. Returns the target key.
+    fn move_entry(
+        &mut self,
+        partial_id: &str,
+        date: NaiveDate,
+        period: JournalPeriod,
+    ) -> Result<String> {
+        let Some((file_path, mut file, id)) = self.find_entry(partial_id)? else {
+            bail!("Entry not found: {}", partial_id)
+        };
+        let (target_path, target_key) = self.period_file(date, period);
+        if target_path == file_path {
+            bail!("Entry {} is already in {}", partial_id, target_key);
+        }
+
+        let mut block = file.take_block(&id);
+        for line in &mut block {
+            if let JournalLine::Entry { entry, .. } = line {
+                entry.date = date;
+            }
+        }
+        let mut target = read_period_file(&target_path, date, period, &target_key)?;
+        target.push_block(block);
+        self.write_file(&file_path, &format_journal_file(&file))?;
+        self.write_file(&target_path, &format_journal_file(&target))?;
+        Ok(target_key)
+    }
+
+    /// Changes an entry's type. A task becomes open; other types lose their task state. A
+    /// top-level entry moves to its new type's section.
+    fn retype_entry(&mut self, partial_id: &str, bullet_type: BulletType) -> Result<()> {
+        let Some((file_path, mut file, id)) = self.find_entry(partial_id)? else {
+            bail!("Entry not found: {}", partial_id)
+        };
+        let Some(entry) = file.entries_mut().find(|entry| entry.id == id) else {
+            bail!("Entry not found: {}", partial_id)
+        };
+        if entry.bullet_type == bullet_type {
+            bail!("Entry {} is already a {}", partial_id, type_name(bullet_type));
+        }
+        entry.bullet_type = bullet_type;
+        entry.task_state = match bullet_type {
+            BulletType::Task => Some(TaskState::Incomplete),
+            _ => None,
+        };
+        if entry.parent.is_none() {
+            let block = file.take_block(&id);
+            file.push_block(block);
+        }
+        self.write_file(&file_path, &format_journal_file(&file))
+    }
+
+    /// Entries in daily, weekly and monthly logs after the current day, week and month,
+    /// earliest first.
+    fn list_future(&self) -> Result<Vec<BulletEntry>> {
+        let today = Utc::now().date_naive();
+        let this_week = NaiveDate::from_isoywd_opt(
+            today.iso_week().year(),
+            today.iso_week().week(),
+            chrono::Weekday::Mon,
+        )
+        .unwrap_or(today);
+        let this_month = today.with_day(1).unwrap_or(today);
+
+        let mut future = Vec::new();
+        for (period, dir, after) in [
+            (JournalPeriod::Daily, self.paths.journal_daily_dir(), today),
+            (JournalPeriod::Weekly, self.paths.journal_weekly_dir(), this_week),
+            (JournalPeriod::Monthly, self.paths.journal_monthly_dir(), this_month),
+        ] {
+            let Ok(files) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for file in files {
+                let path = file?.path();
+                let Some(date) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|key| period_key_date(period, key))
+                else {
+                    continue;
+                };
+                if date <= after {
+                    continue;
+                }
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                future.extend(parse_journal_file(&content, date)?.into_entries());
+            }
+        }
+
+        future.sort_by_key(|entry| entry.date);
+        Ok(future)
+    }
+
+    /// Moves open tasks from the `sources` logs into the `period` log containing `date`.
+    /// A migrated task brings its open sub-tasks, which stay nested under the copy. With
+    /// `interactive`, each task is offered to migrate, schedule, cancel or skip.
+    fn migrate_tasks(
+        &mut self,
+        sources: &[(PathBuf, NaiveDate)],
+        date: NaiveDate,
+        period: JournalPeriod,
+        interactive: bool,
+    ) -> Result<MigrateSummary> {
+        let (to_file, to_key) = self.period_file(date, period);
+        let mut to = read_period_file(&to_file, date, period, &to_key)?;
+        let mut summary = MigrateSummary::default();
+        // (source file, its date, task id, target date, target period)
+        let mut to_schedule = Vec::new();
+        // Sources are only rewritten once the target holds the carried tasks, so a failed
+        // write never leaves tasks marked `[>]` with no copy to migrate to.
+        let mut updated_sources = Vec::new();
+
+        for (from_file, from_date) in sources {
+            if !from_file.exists() || *from_file == to_file {
+                continue;
+            }
+            let content = fs::read_to_string(from_file)
+                .with_context(|| format!("Failed to read {}", from_file.display()))?;
+            let mut from = parse_journal_file(&content, *from_date)?;
+            let parents: HashMap<String, Option<String>> = from
+                .entries()
+                .map(|entry| (entry.id.clone(), entry.parent.clone()))
+                .collect();
+
+            // (old id, copy for the target log, old id of the nearest migrated ancestor)
+            let mut carried: Vec<(String, BulletEntry, Option<String>)> = Vec::new();
+            let mut changed = false;
+            for entry in from.entries_mut() {
+                if !entry.is_open_task() {
+                    continue;
+                }
+                let mut ancestor = entry.parent.clone();
+                while let Some(id) = &ancestor {
+                    if carried.iter().any(|(old_id, _, _)| old_id == id) {
+                        break;
+                    }
+                    ancestor = parents.get(id).cloned().flatten();
+                }
+                if ancestor.is_none() && interactive {
+                    match prompt_migrate_choice(entry)? {
+                        MigrateChoice::Migrate => {}
+                        MigrateChoice::Schedule(target, target_period) => {
+                            to_schedule.push((
+                                from_file.clone(),
+                                *from_date,
+                                entry.id.clone(),
+                                target,
+                                target_period,
+                            ));
+                            continue;
+                        }
+                        MigrateChoice::Cancel => {
+                            entry.task_state = Some(TaskState::Cancelled);
+                            summary.cancelled += 1;
+                            changed = true;
+                            continue;
+                        }
+                        MigrateChoice::Skip => continue,
                     }
-                    break;
                 }
+                entry.task_state = Some(TaskState::Migrated);
+                carried.push((entry.id.clone(), entry.carry_over(date), ancestor));
+                changed = true;
             }
 
-            if found {
-                let date_key = date.format("%Y-%m-%d").to_string();
-                let content = format_journal_file(&entries, JournalPeriod::Daily, &date_key);
-                fs::write(&file_path, content)?;
-                return Ok(());
+            let mut new_ids: HashMap<String, String> = HashMap::new();
+            for (old_id, entry, ancestor) in carried {
+                let new_id = entry.id.clone();
+                match ancestor.and_then(|id| new_ids.get(&id)) {
+                    Some(parent) => to.push_child(parent, entry),
+                    None => to.push(entry),
+                }
+                new_ids.insert(old_id, new_id.clone());
+                summary.migrated.push(new_id);
             }
-        }
-
-        bail!("Entry not found: {}", partial_id)
-    }
-
-    fn migrate_tasks(&mut self, from_date: NaiveDate, all: bool) -> Result<Vec<String>> {
-        let today = Utc::now().date_naive();
-        let from_file = self.paths.daily_file(from_date);
 
-        if !from_file.exists() {
-            return Ok(Vec::new());
-        }
-
-        let content = fs::read_to_string(&from_file)?;
-        let mut from_entries = parse_journal_file(&content, from_date)?;
-        let mut migrated_ids = Vec::new();
-
-        let mut tasks_to_migrate: Vec<usize> = Vec::new();
-        for (i, entry) in from_entries.iter().enumerate() {
-            if entry.bullet_type == BulletType::Task
-                && entry.task_state == Some(TaskState::Incomplete)
-                && (all || tasks_to_migrate.is_empty())
-            {
-                tasks_to_migrate.push(i);
+            if changed {
+                updated_sources.push((from_file, format_journal_file(&from)));
             }
         }
 
-        if tasks_to_migrate.is_empty() {
-            return Ok(Vec::new());
+        if !summary.migrated.is_empty() {
+            self.write_file(&to_file, &format_journal_file(&to))?;
         }
-
-        let to_file = self.paths.daily_file(today);
-        let mut to_entries = if to_file.exists() {
-            let content = fs::read_to_string(&to_file)?;
-            parse_journal_file(&content, today)?
-        } else {
-            Vec::new()
-        };
-
-        for &idx in &tasks_to_migrate {
-            let old_entry = &from_entries[idx];
-            let new_id = generate_entry_id();
-
-            let new_entry = BulletEntry {
-                id: new_id.clone(),
-                bullet_type: BulletType::Task,
-                task_state: Some(TaskState::Incomplete),
-                content: old_entry.content.clone(),
-                created_at: Utc::now(),
-                date: today,
-            };
-
-            to_entries.push(new_entry);
-            migrated_ids.push(new_id);
+        for (from_file, content) in updated_sources {
+            self.write_file(from_file, &content)?;
         }
 
-        for &idx in &tasks_to_migrate {
-            from_entries[idx].task_state = Some(TaskState::Migrated);
+        for (from_file, from_date, id, target, target_period) in to_schedule {
+            let content = fs::read_to_string(&from_file)
+                .with_context(|| format!("Failed to read {}", from_file.display()))?;
+            let file = parse_journal_file(&content, from_date)?;
+            self.schedule_entry(&from_file, file, &id, target, target_period)?;
+            summary.scheduled += 1;
         }
 
-        let from_key = from_date.format("%Y-%m-%d").to_string();
-        let to_key = today.format("%Y-%m-%d").to_string();
+        Ok(summary)
+    }
 
-        fs::write(
-            &from_file,
-            format_journal_file(&from_entries, JournalPeriod::Daily, &from_key),
-        )?;
-        fs::write(
-            &to_file,
-            format_journal_file(&to_entries, JournalPeriod::Daily, &to_key),
-        )?;
+    /// Logs that `bullet migrate` reads from: for `Daily` the day `from` (or with `all` every
+    /// daily log before today); for `Weekly` and `Monthly` the week or month containing `from`,
+    /// as its period log followed by its daily logs.
+    fn migrate_sources(
+        &self,
+        from: NaiveDate,
+        period: JournalPeriod,
+        all: bool,
+    ) -> Result<Vec<(PathBuf, NaiveDate)>> {
+        let today = Utc::now().date_naive();
+        let days: Vec<NaiveDate> = match period {
+            JournalPeriod::Daily if all => {
+                let mut days = Vec::new();
+                if let Ok(files) = fs::read_dir(self.paths.journal_daily_dir()) {
+                    for file in files {
+                        let path = file?.path();
+                        if let Some(day) = path
+                            .file_stem()
+                            .and_then(|stem| stem.to_str())
+                            .and_then(|key| period_key_date(JournalPeriod::Daily, key))
+                            && day < today
+                        {
+                            days.push(day);
+                        }
+                    }
+                }
+                days.sort();
+                days
+            }
+            JournalPeriod::Daily => vec![from],
+            JournalPeriod::Weekly => period_start(from, period).iter_days().take(7).collect(),
+            JournalPeriod::Monthly => period_start(from, period)
+                .iter_days()
+                .take_while(|day| day.month() == from.month())
+                .collect(),
+        };
 
-        Ok(migrated_ids)
+        let mut sources = Vec::new();
+        if period != JournalPeriod::Daily {
+            let (path, _) = self.period_file(from, period);
+            sources.push((path, period_start(from, period)));
+        }
+        sources.extend(days.into_iter().map(|day| (self.paths.daily_file(day), day)));
+        Ok(sources)
     }
 
     fn search(&self, query: &str) -> Result<Vec<BulletEntry>> {
         let needle = query.to_lowercase();
-        let today = Utc::now().date_naive();
         let mut results = Vec::new();
 
-        for i in 0..JOURNAL_SEARCH_DAYS {
-            let date = today - chrono::Duration::days(i);
-            let file_path = self.paths.daily_file(date);
-            if !file_path.exists() {
-                continue;
-            }
-
-            let content = fs::read_to_string(&file_path)?;
-            let entries = parse_journal_file(&content, date)?;
-
-            for entry in entries {
+        for key in self.indexed_keys() {
+            for entry in self.read_key(&key)? {
                 if entry.content.to_lowercase().contains(&needle) {
                     results.push(entry);
                 }
@@ -486,34 +1663,124 @@ impl BulletJournal {
         Ok(results)
     }
 
-    fn open_file(&self, date: NaiveDate, period: JournalPeriod) -> Result<PathBuf> {
-        let file_path = match period {
-            JournalPeriod::Daily => self.paths.daily_file(date),
+    /// Keys of the period files that hold entries, newest first.
+    fn indexed_keys(&self) -> Vec<String> {
+        let mut keys: Vec<(NaiveDate, &String)> = self
+            .index
+            .files
+            .keys()
+            .filter(|key| self.index.entries.values().any(|entry| entry.date_key == **key))
+            .filter_map(|key| key_file(&self.paths, key).map(|(_, date)| (date, key)))
+            .collect();
+        keys.sort_by(|a, b| b.cmp(a));
+        keys.into_iter().map(|(_, key)| key.clone()).collect()
+    }
+
+    /// Entries of the period file with `key`, or none when it does not exist.
+    fn read_key(&self, key: &str) -> Result<Vec<BulletEntry>> {
+        let Some((path, date)) = key_file(&self.paths, key) else {
+            return Ok(Vec::new());
+        };
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Ok(Vec::new());
+        };
+        Ok(parse_journal_file(&content, date)?.into_entries())
+    }
+
+    /// Path and key (`2026-10-18`, `2026-W42`, `2026-10`) of the period file containing `date`.
+    fn period_file(&self, date: NaiveDate, period: JournalPeriod) -> (PathBuf, String) {
+        match period {
+            JournalPeriod::Daily => (
+                self.paths.daily_file(date),
+                date.format("%Y-%m-%d").to_string(),
+            ),
             JournalPeriod::Weekly => {
-                let week = date.iso_week().week();
-                let year = date.iso_week().year();
-                self.paths.weekly_file(year, week)
+                let week = date.iso_week();
+                (
+                    self.paths.weekly_file(week.year(), week.week()),
+                    format!("{}-W{:02}", week.year(), week.week()),
+                )
             }
-            JournalPeriod::Monthly => self.paths.monthly_file(date.year(), date.month()),
-        };
+            JournalPeriod::Monthly => (
+                self.paths.monthly_file(date.year(), date.month()),
+                format!("{}-{:02}", date.year(), date.month()),
+            ),
+        }
+    }
+
+    /// Path of a period file, created empty or from `template` when missing.
+    fn open_file(
+        &self,
+        date: NaiveDate,
+        period: JournalPeriod,
+        template: Option<&str>,
+    ) -> Result<PathBuf> {
+        let (file_path, date_key) = self.period_file(date, period);
 
         if !file_path.exists() {
-            let date_key = match period {
-                JournalPeriod::Daily => date.format("%Y-%m-%d").to_string(),
-                JournalPeriod::Weekly => {
-                    let week = date.iso_week().week();
-                    let year = date.iso_week().year();
-                    format!("{}-W{:02}", year, week)
+            let content = match template {
+                Some(name) => {
+                    expand_template(&self.paths, name, &period_title(period, &date_key), date)?
                 }
-                JournalPeriod::Monthly => format!("{}-{:02}", date.year(), date.month()),
+                None => format_journal_file(&JournalFile::new(period, &date_key)),
             };
-            let content = format_journal_file(&[], period, &date_key);
             fs::write(&file_path, content)?;
         }
 
         Ok(file_path)
     }
 
+    /// The week's or month's log followed by its daily logs, without entry ids, for printing.
+    /// Returns the title, the period key and the Markdown.
+    fn review_markdown(
+        &self,
+        date: NaiveDate,
+        period: JournalPeriod,
+    ) -> Result<(String, String, String)> {
+        let (title, key, period_file, days) = match period {
+            JournalPeriod::Weekly => {
+                let week = date.iso_week();
+                let start = NaiveDate::from_isoywd_opt(week.year(), week.week(), chrono::Weekday::Mon)
+                    .unwrap_or(date);
+                let key = format!("{}-W{:02}", week.year(), week.week());
+                (
+                    format!("Weekly Review - {}", key),
+                    key,
+                    self.paths.weekly_file(week.year(), week.week()),
+                    start.iter_days().take(7).collect::<Vec<_>>(),
+                )
+            }
+            JournalPeriod::Monthly | JournalPeriod::Daily => {
+                let start = date.with_day(1).unwrap_or(date);
+                let key = format!("{}-{:02}", date.year(), date.month());
+                (
+                    format!("Monthly Review - {}", key),
+                    key,
+                    self.paths.monthly_file(date.year(), date.month()),
+                    start
+                        .iter_days()
+                        .take_while(|day| day.month() == date.month())
+                        .collect(),
+                )
+            }
+        };
+
+        let mut files = vec![period_file];
+        files.extend(days.into_iter().map(|day| self.paths.daily_file(day)));
+
+        let mut markdown = String::new();
+        for path in files.iter().filter(|path| path.exists()) {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            markdown.push_str(&strip_entry_ids(&content));
+            markdown.push('\n');
+        }
+        if markdown.is_empty() {
+            markdown = format!("# {}\n\nNo entries.\n", title);
+        }
+        Ok((title, key, markdown))
+    }
+
     fn run_interactive(&mut self) -> Result<()> {
         use std::io::{BufRead, Write};
 
@@ -548,6 +1815,7 @@ impl BulletJournal {
                         let id = self.add_entry(
                             content,
                             BulletType::Task,
+                            &[],
                             today,
                             JournalPeriod::Daily,
                         )?;
@@ -560,6 +1828,7 @@ impl BulletJournal {
                         let id = self.add_entry(
                             content,
                             BulletType::Event,
+                            &[],
                             today,
                             JournalPeriod::Daily,
                         )?;
@@ -572,6 +1841,7 @@ impl BulletJournal {
                         let id = self.add_entry(
                             content,
                             BulletType::Note,
+                            &[],
                             today,
                             JournalPeriod::Daily,
                         )?;
@@ -583,14 +1853,22 @@ impl BulletJournal {
                     print_entries(&entries);
                 }
                 Some('p') => {
-                    let entries = self.list_pending(7)?;
+                    let entries = self.list_pending(None)?;
                     print_entries(&entries);
                 }
                 Some('x') => {
                     let id = input[1..].trim();
                     if !id.is_empty() {
-                        match self.complete_task(id) {
-                            Ok(()) => println!("Marked complete: {}", id),
+                        self.start_operation()?;
+                        match self.complete_task(id, false) {
+                            Ok(_) => {
+                                self.save()?;
+                                self.record_operation(
+                                    "bullet complete",
+                                    format!("Complete {}", id),
+                                )?;
+                                println!("Marked complete: {}", id);
+                            }
                             Err(err) => println!("Error: {}", err),
                         }
                     }
@@ -598,7 +1876,7 @@ impl BulletJournal {
                 Some('q') | Some('Q') => break,
                 _ => {
                     let id =
-                        self.add_entry(input, BulletType::Task, today, JournalPeriod::Daily)?;
+                        self.add_entry(input, BulletType::Task, &[], today, JournalPeriod::Daily)?;
                     println!("Added task: {} [{}]", input, &id[..4.min(id.len())]);
                 }
             }
@@ -609,18 +1887,20 @@ impl BulletJournal {
     }
 
     fn list_ids(&self) -> Result<()> {
-        let today = Utc::now().date_naive();
-
-        for i in 0..ENTRY_IDS_SEARCH_DAYS {
-            let date = today - chrono::Duration::days(i);
-            let entries = self.list_daily(date)?;
-            for entry in entries {
-                let content = if entry.content.len() > 40 {
-                    format!("{}...", &entry.content[..37])
-                } else {
-                    entry.content.clone()
-                };
-                println!("{}\t({})", &entry.id[..8.min(entry.id.len())], content);
+        for key in self.indexed_keys() {
+            let mut ids: Vec<(&String, &JournalEntryRef)> = self
+                .index
+                .entries
+                .iter()
+                .filter(|(_, entry)| entry.date_key == key)
+                .collect();
+            ids.sort_by_key(|(id, _)| *id);
+            for (id, entry) in ids {
+                println!(
+                    "{}\t({})",
+                    &id[..8.min(id.len())],
+                    preview(&entry.content_preview, 40)
+                );
             }
         }
 
@@ -628,6 +1908,135 @@ impl BulletJournal {
     }
 }
 
+/// Merges journal files from another store, given as paths relative to the journal root.
+/// Period files gain the entries they are missing (matched by id) and unknown recurrences are
+/// added; the incoming index is not needed since the index follows the files. Returns the
+/// number of entries added.
+pub(crate) fn import_journal(files: &[(String, String)]) -> Result<usize> {
+    let paths = DataPaths::new()?;
+    let mut journal = BulletJournal::load(paths)?;
+    let mut added = 0;
+
+    for (rel, content) in files {
+        let rel_path = Path::new(rel);
+        if rel == "index.json" {
+            continue;
+        }
+        if rel == "recurrences.json" {
+            let incoming: Vec<Recurrence> = serde_json::from_str(content).unwrap_or_default();
+            let before = journal.recurrences.len();
+            for recurrence in incoming {
+                if !journal.recurrences.iter().any(|known| known.id == recurrence.id) {
+                    journal.recurrences.push(recurrence);
+                }
+            }
+            if journal.recurrences.len() > before {
+                journal.save_recurrences()?;
+            }
+            continue;
+        }
+
+        let (Some(dir), Some(key)) = (
+            rel_path.parent().and_then(|p| p.to_str()),
+            rel_path.file_stem().and_then(|s| s.to_str()),
+        ) else {
+            continue;
+        };
+        let period = match dir {
+            "daily" => JournalPeriod::Daily,
+            "weekly" => JournalPeriod::Weekly,
+            "monthly" => JournalPeriod::Monthly,
+            _ => continue,
+        };
+        let Some(date) = period_key_date(period, key) else {
+            continue;
+        };
+
+        let target = journal.paths.journal_root().join(rel_path);
+        let incoming = parse_journal_file(content, date)?;
+        let Ok(existing) = fs::read_to_string(&target) else {
+            added += incoming.entries().count();
+            journal.write_file(&target, content)?;
+            continue;
+        };
+
+        let mut file = parse_journal_file(&existing, date)?;
+        let before = file.entries().count();
+        for entry in incoming.into_entries() {
+            if !file.entries().any(|known| known.id == entry.id) {
+                file.push(entry);
+            }
+        }
+        let after = file.entries().count();
+        if after > before {
+            added += after - before;
+            journal.write_file(&target, &format_journal_file(&file))?;
+        }
+    }
+
+    journal.save()?;
+    Ok(added)
+}
+
+/// Path and date of the period file with `key` (`2026-10-18`, `2026-W42` or `2026-10`).
+fn key_file(paths: &DataPaths, key: &str) -> Option<(PathBuf, NaiveDate)> {
+    let period = key_period(key);
+    let date = period_key_date(period, key)?;
+    let path = match period {
+        JournalPeriod::Daily => paths.daily_file(date),
+        JournalPeriod::Weekly => paths.weekly_file(date.iso_week().year(), date.iso_week().week()),
+        JournalPeriod::Monthly => paths.monthly_file(date.year(), date.month()),
+    };
+    Some((path, date))
+}
+
+/// Period of the log with `key`: `2026-W42` is weekly, `2026-10` monthly, anything else daily.
+fn key_period(key: &str) -> JournalPeriod {
+    if key.contains("-W") {
+        JournalPeriod::Weekly
+    } else if key.len() == 7 {
+        JournalPeriod::Monthly
+    } else {
+        JournalPeriod::Daily
+    }
+}
+
+/// `text` cut to at most `max` characters, ending in `...` when cut.
+fn preview(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max - 3).collect();
+    format!("{}...", kept)
+}
+
+/// Reads a period file, or starts a new one with just its heading when it does not exist.
+fn read_period_file(
+    path: &Path,
+    date: NaiveDate,
+    period: JournalPeriod,
+    date_key: &str,
+) -> Result<JournalFile> {
+    if !path.exists() {
+        return Ok(JournalFile::new(period, date_key));
+    }
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_journal_file(&content, date)
+}
+
+/// The date a period file's entries belong to, from its key (`2026-10-01`, `2026-W40`, `2026-10`).
+fn period_key_date(period: JournalPeriod, key: &str) -> Option<NaiveDate> {
+    match period {
+        JournalPeriod::Daily => NaiveDate::parse_from_str(key, "%Y-%m-%d").ok(),
+        JournalPeriod::Weekly => {
+            let (year, week) = key.split_once("-W")?;
+            NaiveDate::from_isoywd_opt(year.parse().ok()?, week.parse().ok()?, chrono::Weekday::Mon)
+        }
+        JournalPeriod::Monthly => NaiveDate::parse_from_str(&format!("{key}-01"), "%Y-%m-%d").ok(),
+    }
+}
+
 fn generate_entry_id() -> String {
     use std::collections::hash_map::RandomState;
     use std::hash::{BuildHasher, Hasher};
@@ -645,104 +2054,489 @@ fn generate_entry_id() -> String {
     )
 }
 
-fn parse_journal_file(content: &str, date: NaiveDate) -> Result<Vec<BulletEntry>> {
-    static ENTRY_RE: OnceLock<Regex> = OnceLock::new();
-    let entry_re = ENTRY_RE.get_or_init(|| {
-        Regex::new(r"^- \[(.)\] (.+?) \{id:([a-f0-9]{8,12})\}$").unwrap()
-    });
-    let mut entries = Vec::new();
+impl JournalFile {
+    /// A file with only the period's heading.
+    fn new(period: JournalPeriod, date_key: &str) -> Self {
+        Self {
+            lines: vec![
+                JournalLine::Text(format!("# {}", period_title(period, date_key))),
+                JournalLine::Text(String::new()),
+            ],
+            trailing_newline: true,
+        }
+    }
 
-    for line in content.lines() {
-        if let Some(caps) = entry_re.captures(line) {
-            let marker = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-            let text = caps.get(2).map(|m| m.as_str()).unwrap_or("");
-            let id = caps.get(3).map(|m| m.as_str()).unwrap_or("");
-
-            let (bullet_type, task_state) = match marker {
-                " " => (BulletType::Task, Some(TaskState::Incomplete)),
-                "x" => (BulletType::Task, Some(TaskState::Complete)),
-                ">" => (BulletType::Task, Some(TaskState::Migrated)),
-                "<" => (BulletType::Task, Some(TaskState::Scheduled)),
-                "o" => (BulletType::Event, None),
-                "-" => (BulletType::Note, None),
-                _ => continue,
+    fn entries(&self) -> impl Iterator<Item = &BulletEntry> {
+        self.lines.iter().filter_map(|line| match line {
+            JournalLine::Entry { entry, .. } => Some(entry),
+            JournalLine::Text(_) => None,
+        })
+    }
+
+    fn entries_mut(&mut self) -> impl Iterator<Item = &mut BulletEntry> {
+        self.lines.iter_mut().filter_map(|line| match line {
+            JournalLine::Entry { entry, .. } => Some(entry),
+            JournalLine::Text(_) => None,
+        })
+    }
+
+    fn into_entries(self) -> Vec<BulletEntry> {
+        self.lines
+            .into_iter()
+            .filter_map(|line| match line {
+                JournalLine::Entry { entry, .. } => Some(entry),
+                JournalLine::Text(_) => None,
+            })
+            .collect()
+    }
+
+    /// Ids of the entries nested under `id`, at any depth, in file order.
+    fn descendants(&self, id: &str) -> Vec<String> {
+        let mut found: Vec<String> = Vec::new();
+        for entry in self.entries() {
+            if let Some(parent) = &entry.parent
+                && (parent == id || found.contains(parent))
+            {
+                found.push(entry.id.clone());
+            }
+        }
+        found
+    }
+
+    /// Adds an entry as the last child of `parent_id`, after anything already nested under it.
+    /// Falls back to `push` when the parent is not in this file.
+    fn push_child(&mut self, parent_id: &str, mut entry: BulletEntry) {
+        let Some(position) = self.lines.iter().position(
+            |line| matches!(line, JournalLine::Entry { entry, .. } if entry.id == parent_id),
+        ) else {
+            return self.push(entry);
+        };
+        let JournalLine::Entry { entry: parent, .. } = &self.lines[position] else {
+            unreachable!("position points at an entry")
+        };
+        let parent_indent = parent.indent;
+        let end = self.block_end(position);
+
+        entry.indent = parent_indent + 2;
+        entry.parent = Some(parent_id.to_string());
+        self.lines.insert(
+            end,
+            JournalLine::Entry {
+                entry,
+                original: None,
+            },
+        );
+    }
+
+    /// Index just past the entry at `position` and the lines indented under it.
+    fn block_end(&self, position: usize) -> usize {
+        let JournalLine::Entry { entry: head, .. } = &self.lines[position] else {
+            return position + 1;
+        };
+        // Blank lines belong to the block only when nested lines follow them
+        let mut end = position + 1;
+        for (index, line) in self.lines.iter().enumerate().skip(position + 1) {
+            let nested = match line {
+                JournalLine::Entry { entry, .. } => entry.indent > head.indent,
+                JournalLine::Text(text) if text.trim().is_empty() => continue,
+                JournalLine::Text(text) => indent_width(text) > head.indent,
             };
+            if !nested {
+                break;
+            }
+            end = index + 1;
+        }
+        end
+    }
 
-            entries.push(BulletEntry {
-                id: id.to_string(),
-                bullet_type,
-                task_state,
-                content: text.to_string(),
-                created_at: Utc::now(),
-                date,
-            });
+    /// Removes entry `id` and the lines indented under it, returning them. Empty when `id` is
+    /// not in this file.
+    fn take_block(&mut self, id: &str) -> Vec<JournalLine> {
+        let Some(position) = self.lines.iter().position(
+            |line| matches!(line, JournalLine::Entry { entry, .. } if entry.id == id),
+        ) else {
+            return Vec::new();
+        };
+        let end = self.block_end(position);
+        self.lines.drain(position..end).collect()
+    }
+
+    /// Adds a block taken with `take_block` as a top-level entry of its type's section, with
+    /// the lines under it outdented to match.
+    fn push_block(&mut self, block: Vec<JournalLine>) {
+        let mut lines = block.into_iter();
+        let Some(JournalLine::Entry { entry: mut head, .. }) = lines.next() else {
+            return;
+        };
+        let shift = head.indent;
+        let head_id = head.id.clone();
+        head.indent = 0;
+        head.parent = None;
+        self.push(head);
+
+        let Some(position) = self.lines.iter().position(
+            |line| matches!(line, JournalLine::Entry { entry, .. } if entry.id == head_id),
+        ) else {
+            return;
+        };
+        for (offset, line) in lines.enumerate() {
+            let line = match line {
+                JournalLine::Entry { mut entry, original } => {
+                    entry.indent -= shift;
+                    JournalLine::Entry { entry, original }
+                }
+                JournalLine::Text(text) => JournalLine::Text(outdent(&text, shift)),
+            };
+            self.lines.insert(position + 1 + offset, line);
         }
     }
 
-    Ok(entries)
+    /// Adds an entry at the end of its type's section (`## Tasks`, `## Events` or `## Notes`),
+    /// starting that section at the end of the file when there is none.
+    fn push(&mut self, entry: BulletEntry) {
+        let heading = format!("## {}", section_name(entry.bullet_type));
+        let line = JournalLine::Entry {
+            entry,
+            original: None,
+        };
+
+        let Some(start) = self.lines.iter().position(
+            |line| matches!(line, JournalLine::Text(text) if text.trim() == heading),
+        ) else {
+            if self
+                .lines
+                .last()
+                .is_some_and(|last| !matches!(last, JournalLine::Text(text) if text.trim().is_empty()))
+            {
+                self.lines.push(JournalLine::Text(String::new()));
+            }
+            self.lines.push(JournalLine::Text(heading));
+            self.lines.push(line);
+            self.lines.push(JournalLine::Text(String::new()));
+            self.trailing_newline = true;
+            return;
+        };
+
+        let end = self.lines[start + 1..]
+            .iter()
+            .position(|line| matches!(line, JournalLine::Text(text) if text.starts_with('#')))
+            .map_or(self.lines.len(), |offset| start + 1 + offset);
+        let insert_at = self.lines[start + 1..end]
+            .iter()
+            .rposition(|line| !matches!(line, JournalLine::Text(text) if text.trim().is_empty()))
+            .map_or(start + 1, |offset| start + 2 + offset);
+        self.lines.insert(insert_at, line);
+    }
+}
+
+/// Signifiers written as symbols, such as `*!`; `None` if any character is not one.
+fn parse_signifiers(symbols: &str) -> Option<Vec<Signifier>> {
+    symbols
+        .chars()
+        .map(|symbol| {
+            Signifier::ALL
+                .into_iter()
+                .find(|signifier| signifier.symbol() == symbol)
+        })
+        .collect()
 }
 
-fn format_journal_file(entries: &[BulletEntry], period: JournalPeriod, date_key: &str) -> String {
-    let mut output = String::new();
+/// Heading text of a period file, without the leading `# `.
+fn period_title(period: JournalPeriod, date_key: &str) -> String {
+    match period {
+        JournalPeriod::Daily => format!("Daily Log - {}", date_key),
+        JournalPeriod::Weekly => format!("Weekly Log - {}", date_key),
+        JournalPeriod::Monthly => format!("Monthly Log - {}", date_key),
+    }
+}
 
-    let header = match period {
-        JournalPeriod::Daily => format!("# Daily Log - {}\n\n", date_key),
-        JournalPeriod::Weekly => format!("# Weekly Log - {}\n\n", date_key),
-        JournalPeriod::Monthly => format!("# Monthly Log - {}\n\n", date_key),
+/// Splits leading signifiers (`*`, `!`, `?` followed by a space) off text typed by the user.
+fn split_signifiers(text: &str) -> (Vec<Signifier>, &str) {
+    let Some((symbols, rest)) = text.split_once(' ') else {
+        return (Vec::new(), text);
     };
-    output.push_str(&header);
+    match parse_signifiers(symbols) {
+        Some(found) if !rest.trim().is_empty() => (
+            Signifier::ALL
+                .into_iter()
+                .filter(|signifier| found.contains(signifier))
+                .collect(),
+            rest,
+        ),
+        _ => (Vec::new(), text),
+    }
+}
 
-    let tasks: Vec<_> = entries
-        .iter()
-        .filter(|e| e.bullet_type == BulletType::Task)
-        .collect();
-    let events: Vec<_> = entries
-        .iter()
-        .filter(|e| e.bullet_type == BulletType::Event)
-        .collect();
-    let notes: Vec<_> = entries
+/// Width of a line's leading whitespace, counting a tab as four spaces.
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// Day of the month in `1st`, `2nd`, `15th` or `15` form.
+fn parse_ordinal(text: &str) -> Option<u32> {
+    let digits = ["st", "nd", "rd", "th"]
         .iter()
-        .filter(|e| e.bullet_type == BulletType::Note)
-        .collect();
+        .find_map(|suffix| text.strip_suffix(suffix))
+        .unwrap_or(text);
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
 
-    if !tasks.is_empty() {
-        output.push_str("## Tasks\n");
-        for entry in tasks {
-            output.push_str(&format_entry(entry));
+fn last_day_of_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.pred_opt())
+        .map_or(28, |last| last.day())
+}
+
+/// `line` with up to `width` columns of leading whitespace removed.
+fn outdent(line: &str, width: usize) -> String {
+    let mut removed = 0;
+    let rest = line.trim_start_matches(|c: char| {
+        let step = match c {
+            ' ' => 1,
+            '\t' => 4,
+            _ => return false,
+        };
+        if removed + step > width {
+            return false;
         }
-        output.push('\n');
+        removed += step;
+        true
+    });
+    rest.to_string()
+}
+
+/// Number of entries among `lines`.
+fn count_entries(lines: &[JournalLine]) -> usize {
+    lines
+        .iter()
+        .filter(|line| matches!(line, JournalLine::Entry { .. }))
+        .count()
+}
+
+fn type_name(bullet_type: BulletType) -> &'static str {
+    match bullet_type {
+        BulletType::Task => "task",
+        BulletType::Event => "event",
+        BulletType::Note => "note",
     }
+}
 
-    if !events.is_empty() {
-        output.push_str("## Events\n");
-        for entry in events {
-            output.push_str(&format_entry(entry));
-        }
-        output.push('\n');
+fn section_name(bullet_type: BulletType) -> &'static str {
+    match bullet_type {
+        BulletType::Task => "Tasks",
+        BulletType::Event => "Events",
+        BulletType::Note => "Notes",
     }
+}
+
+/// Parses a period file. Lines that are not entries (headings, free text, sub-notes) are kept
+/// so that `format_journal_file` writes them back unchanged. An entry indented deeper than the
+/// entry above it is that entry's child.
+fn parse_journal_file(content: &str, date: NaiveDate) -> Result<JournalFile> {
+    static ENTRY_RE: OnceLock<Regex> = OnceLock::new();
+    let entry_re = ENTRY_RE.get_or_init(|| {
+        Regex::new(r"^([ \t]*)- \[(.)\] (.+?) \{id:([a-f0-9]{8,12})((?: [a-z][a-z0-9_-]*:[^\s{}]+)*)\}$")
+            .unwrap()
+    });
+    let mut lines = Vec::new();
+    // Indent and id of the entries enclosing the current line
+    let mut open_parents: Vec<(usize, String)> = Vec::new();
+
+    for line in content.lines() {
+        let Some(caps) = entry_re.captures(line) else {
+            if line.starts_with('#') || (!line.trim().is_empty() && indent_width(line) == 0) {
+                open_parents.clear();
+            }
+            lines.push(JournalLine::Text(line.to_string()));
+            continue;
+        };
+        let indent = indent_width(caps.get(1).map(|m| m.as_str()).unwrap_or(""));
+        let marker = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+        let text = caps.get(3).map(|m| m.as_str()).unwrap_or("");
+        let id = caps.get(4).map(|m| m.as_str()).unwrap_or("");
+        let fields = caps.get(5).map(|m| m.as_str()).unwrap_or("");
+
+        let (bullet_type, task_state) = match marker {
+            " " => (BulletType::Task, Some(TaskState::Incomplete)),
+            "x" => (BulletType::Task, Some(TaskState::Complete)),
+            ">" => (BulletType::Task, Some(TaskState::Migrated)),
+            "<" => (BulletType::Task, Some(TaskState::Scheduled)),
+            "~" => (BulletType::Task, Some(TaskState::Cancelled)),
+            "/" => (BulletType::Task, Some(TaskState::InProgress)),
+            "o" => (BulletType::Event, None),
+            "-" => (BulletType::Note, None),
+            _ => {
+                lines.push(JournalLine::Text(line.to_string()));
+                continue;
+            }
+        };
 
-    if !notes.is_empty() {
-        output.push_str("## Notes\n");
-        for entry in notes {
-            output.push_str(&format_entry(entry));
+        let mut created_at = None;
+        let mut scheduled_from = None;
+        let mut scheduled_to = None;
+        let mut recurrence = None;
+        let mut signifiers = None;
+        let mut extra_fields = Vec::new();
+        for field in fields.split_whitespace() {
+            let (key, value) = field.split_once(':').unwrap_or((field, ""));
+            match key {
+                "created" if created_at.is_none() => match DateTime::parse_from_rfc3339(value) {
+                    Ok(parsed) => created_at = Some(parsed.with_timezone(&Utc)),
+                    Err(_) => extra_fields.push((key.to_string(), value.to_string())),
+                },
+                "from" if scheduled_from.is_none() => scheduled_from = Some(value.to_string()),
+                "to" if scheduled_to.is_none() => scheduled_to = Some(value.to_string()),
+                "recur" if recurrence.is_none() => recurrence = Some(value.to_string()),
+                "sig" if signifiers.is_none() => match parse_signifiers(value) {
+                    Some(found) => signifiers = Some((found, value)),
+                    None => extra_fields.push((key.to_string(), value.to_string())),
+                },
+                _ => extra_fields.push((key.to_string(), value.to_string())),
+            }
         }
-        output.push('\n');
+
+        while open_parents
+            .last()
+            .is_some_and(|(parent_indent, _)| *parent_indent >= indent)
+        {
+            open_parents.pop();
+        }
+        let parent = open_parents.last().map(|(_, parent_id)| parent_id.clone());
+        open_parents.push((indent, id.to_string()));
+
+        let text = match task_state {
+            Some(TaskState::Cancelled) => text
+                .strip_prefix("~~")
+                .and_then(|rest| rest.strip_suffix("~~"))
+                .filter(|inner| !inner.is_empty())
+                .unwrap_or(text),
+            _ => text,
+        };
+        // Only lines that record their signifiers have them split off; on older lines a
+        // leading `?` or `*` is part of the text
+        let (signifiers, text) = match signifiers {
+            Some((found, symbols)) => (
+                found,
+                text.strip_prefix(symbols)
+                    .and_then(|rest| rest.strip_prefix(' '))
+                    .unwrap_or(text),
+            ),
+            None => (Vec::new(), text),
+        };
+        let entry = BulletEntry {
+            id: id.to_string(),
+            bullet_type,
+            task_state,
+            content: text.to_string(),
+            signifiers,
+            created_at,
+            date,
+            indent,
+            parent,
+            scheduled_from,
+            scheduled_to,
+            recurrence,
+            extra_fields,
+        };
+        lines.push(JournalLine::Entry {
+            original: Some(Box::new((line.to_string(), entry.clone()))),
+            entry,
+        });
     }
 
+    Ok(JournalFile {
+        lines,
+        trailing_newline: content.is_empty() || content.ends_with('\n'),
+    })
+}
+
+/// Writes a period file back. Entries that were not changed since parsing keep their exact
+/// original line.
+fn format_journal_file(file: &JournalFile) -> String {
+    let mut output = file
+        .lines
+        .iter()
+        .map(|line| match line {
+            JournalLine::Text(text) => text.clone(),
+            JournalLine::Entry {
+                entry,
+                original: Some(original),
+            } if *entry == original.1 => original.0.clone(),
+            JournalLine::Entry { entry, .. } => format_entry(entry),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if file.trailing_newline && !output.is_empty() {
+        output.push('\n');
+    }
     output
 }
 
+fn strip_entry_ids(content: &str) -> String {
+    static ENTRY_ID_RE: OnceLock<Regex> = OnceLock::new();
+    let entry_id_re =
+        ENTRY_ID_RE.get_or_init(|| Regex::new(r"(?m) \{id:[a-f0-9]{8,12}[^{}]*\}$").unwrap());
+    entry_id_re.replace_all(content, "").into_owned()
+}
+
+/// The character between the brackets of an entry's `- [ ]`.
+fn entry_marker(entry: &BulletEntry) -> char {
+    match (entry.bullet_type, entry.task_state) {
+        (BulletType::Task, Some(TaskState::Incomplete)) => ' ',
+        (BulletType::Task, Some(TaskState::Complete)) => 'x',
+        (BulletType::Task, Some(TaskState::Migrated)) => '>',
+        (BulletType::Task, Some(TaskState::Scheduled)) => '<',
+        (BulletType::Task, Some(TaskState::Cancelled)) => '~',
+        (BulletType::Task, Some(TaskState::InProgress)) => '/',
+        (BulletType::Event, _) => 'o',
+        (BulletType::Note, _) => '-',
+        _ => ' ',
+    }
+}
+
 fn format_entry(entry: &BulletEntry) -> String {
-    let marker = match (entry.bullet_type, entry.task_state) {
-        (BulletType::Task, Some(TaskState::Incomplete)) => " ",
-        (BulletType::Task, Some(TaskState::Complete)) => "x",
-        (BulletType::Task, Some(TaskState::Migrated)) => ">",
-        (BulletType::Task, Some(TaskState::Scheduled)) => "<",
-        (BulletType::Event, _) => "o",
-        (BulletType::Note, _) => "-",
-        _ => " ",
+    let marker = entry_marker(entry);
+    let content = if entry.task_state == Some(TaskState::Cancelled) {
+        format!("~~{}~~", entry.display_content())
+    } else {
+        entry.display_content()
     };
-    format!("- [{}] {} {{id:{}}}\n", marker, entry.content, entry.id)
+    let mut trailer = format!("id:{}", entry.id);
+    if let Some(created_at) = entry.created_at {
+        trailer.push_str(" created:");
+        trailer.push_str(&created_at.to_rfc3339_opts(SecondsFormat::Secs, true));
+    }
+    if !entry.signifiers.is_empty() {
+        trailer.push_str(" sig:");
+        trailer.extend(entry.signifiers.iter().map(|signifier| signifier.symbol()));
+    }
+    if let Some(from) = &entry.scheduled_from {
+        trailer.push_str(&format!(" from:{}", from));
+    }
+    if let Some(to) = &entry.scheduled_to {
+        trailer.push_str(&format!(" to:{}", to));
+    }
+    if let Some(recurrence) = &entry.recurrence {
+        trailer.push_str(&format!(" recur:{}", recurrence));
+    }
+    for (key, value) in &entry.extra_fields {
+        trailer.push_str(&format!(" {}:{}", key, value));
+    }
+    format!(
+        "{}- [{}] {} {{{}}}",
+        " ".repeat(entry.indent),
+        marker,
+        content,
+        trailer
+    )
 }
 
 fn print_entries(entries: &[BulletEntry]) {
@@ -751,33 +2545,223 @@ fn print_entries(entries: &[BulletEntry]) {
         return;
     }
 
+    // Children are indented under their parent when both are listed
+    let mut depths: HashMap<&str, usize> = HashMap::new();
     for entry in entries {
-        let marker = match (entry.bullet_type, entry.task_state) {
-            (BulletType::Task, Some(TaskState::Incomplete)) => "[ ]",
-            (BulletType::Task, Some(TaskState::Complete)) => "[x]",
-            (BulletType::Task, Some(TaskState::Migrated)) => "[>]",
-            (BulletType::Task, Some(TaskState::Scheduled)) => "[<]",
-            (BulletType::Event, _) => "[o]",
-            (BulletType::Note, _) => "[-]",
-            _ => "[ ]",
+        let depth = entry
+            .parent
+            .as_deref()
+            .and_then(|parent| depths.get(parent))
+            .map_or(0, |depth| depth + 1);
+        depths.insert(&entry.id, depth);
+        let marker = format!("[{}]", entry_marker(entry));
+        let content = match (&entry.scheduled_to, entry.task_state) {
+            (Some(to), Some(TaskState::Scheduled)) => format!("{} -> {}", entry.content, to),
+            (_, Some(TaskState::Cancelled)) => strike_through(&entry.content),
+            _ => entry.content.clone(),
         };
+        let signifiers: String = entry
+            .signifiers
+            .iter()
+            .map(|signifier| format!("{} ", signifier.symbol()))
+            .collect();
         println!(
-            "{} {} ({}) [{}]",
+            "{}{}{} {} ({}) [{}]",
+            "  ".repeat(depth),
+            signifiers,
             marker,
-            entry.content,
+            content,
             entry.date.format("%Y-%m-%d"),
             &entry.id[..4.min(entry.id.len())]
         );
     }
 }
 
-fn parse_date(s: &str) -> Result<NaiveDate> {
+/// `text` with a combining long stroke after each character, which terminals draw struck through.
+fn strike_through(text: &str) -> String {
+    text.chars().flat_map(|c| [c, '\u{0336}']).collect()
+}
+
+/// Fails when `date` is before today, or for `Monthly` in a month before this one.
+fn ensure_not_past(date: NaiveDate, period: JournalPeriod) -> Result<()> {
     let today = Utc::now().date_naive();
-    match s.to_lowercase().as_str() {
-        "today" => Ok(today),
-        "yesterday" => Ok(today - chrono::Duration::days(1)),
-        "tomorrow" => Ok(today + chrono::Duration::days(1)),
-        _ => NaiveDate::parse_from_str(s, "%Y-%m-%d")
-            .with_context(|| format!("Invalid date format: {}. Use YYYY-MM-DD", s)),
+    if period_start(date, period) < period_start(today, period) {
+        bail!("Cannot schedule into the past: {}", date.format("%Y-%m-%d"));
+    }
+    Ok(())
+}
+
+/// Asks what to do with a task during `bullet migrate --interactive`. An empty answer migrates;
+/// end of input skips.
+fn prompt_migrate_choice(entry: &BulletEntry) -> Result<MigrateChoice> {
+    use std::io::{BufRead, Write};
+
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    println!(
+        "[{}] {} ({})",
+        entry_marker(entry),
+        entry.display_content(),
+        entry.date.format("%Y-%m-%d")
+    );
+    loop {
+        print!("  (m)igrate, (s)chedule, (c)ancel, s(k)ip? [m] ");
+        stdout.flush()?;
+        let mut answer = String::new();
+        if stdin.lock().read_line(&mut answer)? == 0 {
+            println!();
+            return Ok(MigrateChoice::Skip);
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "" | "m" => return Ok(MigrateChoice::Migrate),
+            "c" => return Ok(MigrateChoice::Cancel),
+            "k" => return Ok(MigrateChoice::Skip),
+            "s" => {
+                print!("  Schedule for (a day, week or month, e.g. fri, next week, 2026-12): ");
+                stdout.flush()?;
+                let mut when = String::new();
+                if stdin.lock().read_line(&mut when)? == 0 {
+                    println!();
+                    return Ok(MigrateChoice::Skip);
+                }
+                match parse_when(&when).and_then(|(date, period)| {
+                    ensure_not_past(date, period).map(|_| (date, period))
+                }) {
+                    Ok((date, period)) => return Ok(MigrateChoice::Schedule(date, period)),
+                    Err(err) => println!("  {}", err),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// First day of the day, ISO week or month containing `date`.
+fn period_start(date: NaiveDate, period: JournalPeriod) -> NaiveDate {
+    match period {
+        JournalPeriod::Daily => date,
+        JournalPeriod::Weekly => {
+            let week = date.iso_week();
+            NaiveDate::from_isoywd_opt(week.year(), week.week(), chrono::Weekday::Mon)
+                .unwrap_or(date)
+        }
+        JournalPeriod::Monthly => date.with_day(1).unwrap_or(date),
+    }
+}
+
+/// The log a command works on: the week or month flag when set, otherwise the day, week or
+/// month `date` names (default: today).
+fn target_log(
+    date: Option<String>,
+    weekly: bool,
+    monthly: bool,
+) -> Result<(NaiveDate, JournalPeriod)> {
+    let (date, named) = match date {
+        Some(date) => parse_when(&date)?,
+        None => (Utc::now().date_naive(), JournalPeriod::Daily),
+    };
+    let period = if weekly {
+        JournalPeriod::Weekly
+    } else if monthly {
+        JournalPeriod::Monthly
+    } else {
+        named
+    };
+    Ok((date, period))
+}
+
+/// Parses a day, week or month relative to today. Weeks and months give their first day.
+fn parse_when(input: &str) -> Result<(NaiveDate, JournalPeriod)> {
+    parse_when_from(input, Utc::now().date_naive()).ok_or_else(|| {
+        anyhow!(
+            "Unrecognized date: {}. Use a day (today, tomorrow, 2026-10-18, fri, next monday, \
+             last friday, +3d, -2w, in 2 weeks, 3 days ago), a week (2026-W42, this week, \
+             next week, last week) or a month (2026-11, next month, last month)",
+            input
+        )
+    })
+}
+
+/// `parse_when` with `today` given. A bare day name is today or the next such day; with
+/// `next` it is the first after today and with `last` the last before today.
+fn parse_when_from(input: &str, today: NaiveDate) -> Option<(NaiveDate, JournalPeriod)> {
+    let text = input.trim().to_lowercase();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let date = match words.as_slice() {
+        ["today"] => today,
+        ["yesterday"] => today - chrono::Duration::days(1),
+        ["tomorrow"] => today + chrono::Duration::days(1),
+        [relative @ ("this" | "next" | "last"), unit @ ("week" | "month")] => {
+            let step = match *relative {
+                "this" => 0,
+                "next" => 1,
+                _ => -1,
+            };
+            let period = if *unit == "week" {
+                JournalPeriod::Weekly
+            } else {
+                JournalPeriod::Monthly
+            };
+            let start = shift_date(period_start(today, period), step, unit)?;
+            return Some((period_start(start, period), period));
+        }
+        ["next", day] => {
+            let day = day.parse::<chrono::Weekday>().ok()?;
+            (1..=7)
+                .map(|offset| today + chrono::Duration::days(offset))
+                .find(|date| date.weekday() == day)?
+        }
+        ["last", day] => {
+            let day = day.parse::<chrono::Weekday>().ok()?;
+            (1..=7)
+                .map(|offset| today - chrono::Duration::days(offset))
+                .find(|date| date.weekday() == day)?
+        }
+        ["in", count, unit] => shift_date(today, count.parse().ok()?, unit)?,
+        [count, unit, "ago"] => shift_date(today, -count.parse::<i64>().ok()?, unit)?,
+        [word] => {
+            if let Ok(day) = word.parse::<chrono::Weekday>() {
+                (0..7)
+                    .map(|offset| today + chrono::Duration::days(offset))
+                    .find(|date| date.weekday() == day)?
+            } else if let Some(offset) = word.strip_prefix(['+', '-']) {
+                let split = offset.find(|c: char| !c.is_ascii_digit())?;
+                let count: i64 = offset[..split].parse().ok()?;
+                let count = if word.starts_with('-') { -count } else { count };
+                shift_date(today, count, &offset[split..])?
+            } else if let Some(date) = period_key_date(JournalPeriod::Daily, word) {
+                date
+            } else if let Some(week) = period_key_date(JournalPeriod::Weekly, &word.to_uppercase())
+                && word.len() == 8
+            {
+                return Some((week, JournalPeriod::Weekly));
+            } else if word.len() == 7 {
+                return Some((period_key_date(JournalPeriod::Monthly, word)?, JournalPeriod::Monthly));
+            } else {
+                return None;
+            }
+        }
+        _ => return None,
+    };
+    Some((date, JournalPeriod::Daily))
+}
+
+/// `date` moved by `count` days (`d`), weeks (`w`), months (`m`) or years (`y`); the units can
+/// also be spelled out.
+fn shift_date(date: NaiveDate, count: i64, unit: &str) -> Option<NaiveDate> {
+    let months = |months: i64| {
+        let magnitude = chrono::Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+        if months < 0 {
+            date.checked_sub_months(magnitude)
+        } else {
+            date.checked_add_months(magnitude)
+        }
+    };
+    match unit {
+        "d" | "day" | "days" => date.checked_add_signed(chrono::Duration::try_days(count)?),
+        "w" | "week" | "weeks" => date.checked_add_signed(chrono::Duration::try_weeks(count)?),
+        "m" | "month" | "months" => months(count),
+        "y" | "year" | "years" => months(count.checked_mul(12)?),
+        _ => None,
     }
 }
@@ -1,4 +1,5 @@
 use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "notes", about = "Local notes with version control")]
@@ -10,13 +11,89 @@ pub struct Cli {
 #[derive(Subcommand)]
 pub enum Commands {
     /// Create a new note (optional title)
-    New { title: Option<String> },
+    New {
+        title: Option<String>,
+        /// Encrypt the note with a passphrase
+        #[arg(long)]
+        encrypted: bool,
+        /// Start from a template in the templates directory
+        #[arg(short, long)]
+        template: Option<String>,
+    },
+    /// Manage note templates
+    Templates {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
     /// Open an existing note by title or id
     Open { title: String },
+    /// Give a note a new title and update `[[...]]` links to it in other notes
+    Rename { title: String, new_title: String },
+    /// List the `[[...]]` links of a note, or every broken link
+    Links {
+        #[arg(required_unless_present = "broken")]
+        title: Option<String>,
+        /// Report links that resolve to no note, across all notes
+        #[arg(long, conflicts_with = "title")]
+        broken: bool,
+    },
+    /// List the notes that link to a note
+    Backlinks { title: String },
+    /// Store a file with a note and link it from the working copy
+    Attach { title: String, file: PathBuf },
+    /// Remove attachments no note version links to
+    Gc,
+    /// Encrypt an existing note and its history with a passphrase
+    Encrypt { title: String },
+    /// Snapshot and lock an unlocked encrypted note, or all of them and the encrypted store
+    Lock { title: Option<String> },
+    /// Encrypt the whole store (index, versions, journal and logs) with a passphrase
+    EncryptStore,
+    /// Unlock the encrypted store, keeping the key in an agent for a while
+    Unlock {
+        /// Minutes until the store locks again
+        #[arg(short, long, default_value = "60")]
+        timeout: u64,
+        /// Decrypt into NOTES_HOME when there is no runtime directory (plaintext on disk)
+        #[arg(long)]
+        on_disk: bool,
+    },
+    #[command(hide = true)]
+    /// Serve the key of an unlocked store (started by `unlock`)
+    Agent {
+        /// Seconds until the agent locks the store
+        #[arg(long)]
+        timeout: u64,
+        #[arg(long)]
+        on_disk: bool,
+        /// Hold the keys of unlocked encrypted notes instead (started by `open`)
+        #[arg(long)]
+        notes: bool,
+    },
     /// List all notes and their latest versions
-    List,
+    List {
+        /// Snapshot changed working copies first
+        #[arg(long)]
+        snapshot: bool,
+    },
     /// List all versions for a note
-    Versions { title: String },
+    Versions {
+        title: String,
+        /// Snapshot changed working copies first
+        #[arg(long)]
+        snapshot: bool,
+    },
+    /// Create a version from the working copy of a note (or all notes)
+    Snapshot {
+        #[arg(required_unless_present = "all")]
+        title: Option<String>,
+        /// Snapshot every note with changes
+        #[arg(short, long, conflicts_with = "title")]
+        all: bool,
+        /// Message recorded with the new version
+        #[arg(short, long)]
+        message: Option<String>,
+    },
     /// Roll back to a specific (or previous) version
     Rollback {
         title: String,
@@ -26,7 +103,90 @@ pub enum Commands {
     /// Delete a note by unique title
     Delete { title: String },
     /// Search notes by text in the latest version
-    Search { query: String },
+    Search {
+        query: String,
+        /// Snapshot changed working copies first
+        #[arg(long)]
+        snapshot: bool,
+    },
+    /// Revert the last N recorded operations
+    Undo {
+        #[arg(default_value = "1")]
+        count: usize,
+        /// Apply even if files changed since the operation
+        #[arg(long)]
+        force: bool,
+    },
+    /// Replay the last N undone operations
+    Redo {
+        #[arg(default_value = "1")]
+        count: usize,
+        /// Apply even if files changed since the undo
+        #[arg(long)]
+        force: bool,
+    },
+    /// List recorded operations, newest first
+    Log {
+        /// Number of operations to show
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+    },
+    /// Show statistics about notes and their version history
+    Stats {
+        /// Only count edits made after this time
+        #[arg(short, long)]
+        since: Option<String>,
+
+        /// Group activity by day or week
+        #[arg(short, long, value_enum, default_value = "day")]
+        by: StatsPeriod,
+
+        /// Report notes not edited in this many months as dormant
+        #[arg(long, default_value = "6")]
+        dormant_months: u32,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Export notes to other formats, or the whole notebook as an archive
+    #[command(args_conflicts_with_subcommands = true)]
+    Export {
+        #[command(subcommand)]
+        format: Option<ExportFormat>,
+
+        /// Write notes and journal to a .tar.zst archive
+        #[arg(long, required = true)]
+        archive: Option<PathBuf>,
+
+        /// Only include each note's current version
+        #[arg(long, requires = "archive")]
+        latest_only: bool,
+    },
+    /// Merge notes and journal from a .tar.zst archive, or notes from a git repository
+    #[command(args_conflicts_with_subcommands = true)]
+    Import {
+        #[command(subcommand)]
+        source: Option<ImportSource>,
+
+        /// Archive created by `notes export --archive`
+        #[arg(required = true)]
+        archive: Option<PathBuf>,
+    },
+    /// Create a note for every Markdown file in a folder or Obsidian vault
+    ImportDir {
+        path: PathBuf,
+        /// Turn the folder's git commits into versions
+        #[arg(long)]
+        git_history: bool,
+    },
+    /// View the notebook as it was at a point in time
+    At {
+        /// RFC 3339, local "YYYY-MM-DD HH:MM[:SS]" or YYYY-MM-DD (end of day)
+        time: String,
+        #[command(subcommand)]
+        action: AtAction,
+    },
     /// Bullet journal - quick capture and task management
     #[command(alias = "b")]
     Bullet {
@@ -49,8 +209,20 @@ pub enum Commands {
         #[arg(short = 'n', long, conflicts_with_all = ["task", "event"])]
         note: bool,
 
-        /// Target date (YYYY-MM-DD, "today", "yesterday", "tomorrow")
-        #[arg(short = 'd', long)]
+        /// Mark the entry as a priority (`*`)
+        #[arg(short = 'p', long)]
+        priority: bool,
+
+        /// Mark the entry as an inspiration (`!`)
+        #[arg(short = 'i', long)]
+        inspiration: bool,
+
+        /// Mark the entry as something to explore (`?`)
+        #[arg(short = 'x', long)]
+        explore: bool,
+
+        /// Target day, week or month ("tomorrow", "fri", "+3d", "2026-W42", "next month")
+        #[arg(short = 'd', long, allow_hyphen_values = true)]
         date: Option<String>,
 
         /// Add to weekly log instead of daily
@@ -60,6 +232,11 @@ pub enum Commands {
         /// Add to monthly log instead of daily
         #[arg(short = 'm', long, conflicts_with = "weekly")]
         monthly: bool,
+
+        /// Repeat the entry (day, weekday, weekend, monday, week, month, 1st-of-month,
+        /// last-of-month)
+        #[arg(long, conflicts_with_all = ["weekly", "monthly"])]
+        every: Option<String>,
     },
     /// Interactive bullet journal mode
     #[command(alias = "bi")]
@@ -75,13 +252,113 @@ pub enum Commands {
     Ids,
 }
 
+#[derive(Subcommand)]
+pub enum AtAction {
+    /// List notes and the version that was current
+    List,
+    /// Print a note's content
+    Show { title: String },
+    /// Search note contents
+    Search { query: String },
+    /// Write every note to <dir>/<id>.md
+    Export { dir: PathBuf },
+}
+
+#[derive(Subcommand)]
+pub enum ExportFormat {
+    /// Render notes as a static HTML site
+    Html {
+        /// Output directory
+        dir: PathBuf,
+        /// Only export these notes (title or id, repeatable)
+        #[arg(short, long = "title")]
+        titles: Vec<String>,
+    },
+    /// Render a note's working copy as a printable PDF
+    Pdf {
+        /// Note title or id
+        title: String,
+        /// Output file (default: <id>.pdf)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Mirror notes to a git repository, one commit per version (incremental)
+    Git {
+        /// Repository directory, created on first export
+        dir: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TemplateAction {
+    /// List available templates
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum ImportSource {
+    /// Replay the history of a git repository's Markdown files as versions
+    Git {
+        /// Repository directory
+        dir: PathBuf,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum BulletAction {
+    /// Add an entry, or with --every a recurring one
+    Add {
+        /// Entry text
+        #[arg(required = true, num_args = 1..)]
+        text: Vec<String>,
+
+        /// Create a task entry
+        #[arg(short = 't', long, conflicts_with_all = ["event", "note"])]
+        task: bool,
+
+        /// Create an event entry
+        #[arg(short = 'e', long, conflicts_with_all = ["task", "note"])]
+        event: bool,
+
+        /// Create a note entry
+        #[arg(short = 'n', long, conflicts_with_all = ["task", "event"])]
+        note: bool,
+
+        /// Mark the entry as a priority (`*`)
+        #[arg(short = 'p', long)]
+        priority: bool,
+
+        /// Mark the entry as an inspiration (`!`)
+        #[arg(short = 'i', long)]
+        inspiration: bool,
+
+        /// Mark the entry as something to explore (`?`)
+        #[arg(short = 'x', long)]
+        explore: bool,
+
+        /// Target day, week or month, or the first day of a recurrence (default: today)
+        #[arg(short = 'd', long, allow_hyphen_values = true)]
+        date: Option<String>,
+
+        /// Add to weekly log instead of daily
+        #[arg(short = 'w', long, conflicts_with = "monthly")]
+        weekly: bool,
+
+        /// Add to monthly log instead of daily
+        #[arg(short = 'm', long)]
+        monthly: bool,
+
+        /// Repeat the entry (day, weekday, weekend, monday, week, month, 1st-of-month,
+        /// last-of-month)
+        #[arg(long, conflicts_with_all = ["weekly", "monthly"])]
+        every: Option<String>,
+    },
+
     /// List journal entries
     #[command(alias = "ls")]
     List {
-        /// Specific date to show (default: today)
-        #[arg(short = 'd', long)]
+        /// Day, week or month to show (default: today)
+        #[arg(short = 'd', long, allow_hyphen_values = true)]
         date: Option<String>,
 
         /// Show current week's entries
@@ -91,14 +368,18 @@ pub enum BulletAction {
         /// Show current month's entries
         #[arg(short = 'm', long, conflicts_with_all = ["week", "date"])]
         month: bool,
+
+        /// Only show priority (`*`) entries
+        #[arg(short = 'p', long)]
+        priority: bool,
     },
 
     /// Show incomplete/pending tasks
     #[command(alias = "p")]
     Pending {
-        /// Include tasks from past N days (default: 7)
-        #[arg(short = 'd', long, default_value = "7")]
-        days: u32,
+        /// Only include logs from the past N days (default: all logs)
+        #[arg(short = 'd', long)]
+        days: Option<u32>,
     },
 
     /// Mark a task as complete
@@ -106,25 +387,139 @@ pub enum BulletAction {
     Complete {
         /// Entry ID or partial match
         entry: String,
+
+        /// Also complete the task's open sub-tasks
+        #[arg(short = 's', long)]
+        subtasks: bool,
+    },
+
+    /// Cancel a task that will not be done
+    Cancel {
+        /// Entry ID or partial match
+        entry: String,
+    },
+
+    /// Mark a task as in progress
+    Start {
+        /// Entry ID or partial match
+        entry: String,
+    },
+
+    /// Reopen a completed, cancelled or in-progress task
+    Reopen {
+        /// Entry ID or partial match
+        entry: String,
+    },
+
+    /// Schedule an open task for a future day, week or month
+    #[command(alias = "sc")]
+    Schedule {
+        /// Entry ID or partial match
+        entry: String,
+
+        /// Day, week or month to schedule the task for ("fri", "in 2 weeks", "2026-12")
+        #[arg(
+            short = 't',
+            long,
+            required_unless_present = "month",
+            conflicts_with = "month",
+            allow_hyphen_values = true
+        )]
+        to: Option<String>,
+
+        /// Month to schedule the task for ("2026-12", "next month"), in that month's log
+        #[arg(short = 'm', long, allow_hyphen_values = true)]
+        month: Option<String>,
     },
 
-    /// Migrate incomplete tasks to today
+    /// Change an entry's text
+    Edit {
+        /// Entry ID or partial match
+        entry: String,
+
+        /// New text; leading signifiers (`*`, `!`, `?`) replace the entry's
+        #[arg(required = true, num_args = 1..)]
+        text: Vec<String>,
+    },
+
+    /// Delete an entry and everything nested under it
+    Rm {
+        /// Entry ID or partial match
+        entry: String,
+    },
+
+    /// Move an entry, with everything nested under it, to another log
+    #[command(alias = "mv")]
+    Move {
+        /// Entry ID or partial match
+        entry: String,
+
+        /// Day, week or month to move the entry to (default: today)
+        #[arg(short = 'd', long, allow_hyphen_values = true)]
+        date: Option<String>,
+
+        /// Move to the weekly log
+        #[arg(short = 'w', long, conflicts_with = "monthly")]
+        weekly: bool,
+
+        /// Move to the monthly log
+        #[arg(short = 'm', long)]
+        monthly: bool,
+    },
+
+    /// Change an entry into a task, event or note
+    #[command(group(clap::ArgGroup::new("kind").required(true).args(["task", "event", "note"])))]
+    Retype {
+        /// Entry ID or partial match
+        entry: String,
+
+        /// Make it a task
+        #[arg(short = 't', long)]
+        task: bool,
+
+        /// Make it an event
+        #[arg(short = 'e', long)]
+        event: bool,
+
+        /// Make it a note
+        #[arg(short = 'n', long)]
+        note: bool,
+    },
+
+    /// Show entries in daily, weekly and monthly logs ahead of today
+    #[command(alias = "f")]
+    Future,
+
+    /// Migrate open tasks to today, or to this week's or month's log
     #[command(alias = "mg")]
     Migrate {
-        /// Migrate all incomplete tasks from yesterday
-        #[arg(short = 'a', long)]
+        /// Migrate from every earlier daily log instead of one day
+        #[arg(short = 'a', long, conflicts_with_all = ["from", "week", "month"])]
         all: bool,
 
-        /// Source date to migrate from (default: yesterday)
-        #[arg(short = 'f', long)]
+        /// Day to migrate from, or a day in the week or month to migrate from
+        /// (default: yesterday, last week or last month); a week or month migrates all of it
+        #[arg(short = 'f', long, allow_hyphen_values = true)]
         from: Option<String>,
+
+        /// Migrate last week's weekly and daily logs into this week's log
+        #[arg(short = 'w', long, conflicts_with = "month")]
+        week: bool,
+
+        /// Migrate last month's monthly and daily logs into this month's log
+        #[arg(short = 'm', long)]
+        month: bool,
+
+        /// Choose to migrate, schedule, cancel or skip each task
+        #[arg(short = 'i', long)]
+        interactive: bool,
     },
 
     /// Open the journal file in editor
     #[command(alias = "o")]
     Open {
-        /// Date to open (default: today)
-        #[arg(short = 'd', long)]
+        /// Day, week or month to open (default: today)
+        #[arg(short = 'd', long, allow_hyphen_values = true)]
         date: Option<String>,
 
         /// Open weekly file
@@ -134,6 +529,16 @@ pub enum BulletAction {
         /// Open monthly file
         #[arg(short = 'm', long)]
         monthly: bool,
+
+        /// Fill a new file from a template in the templates directory
+        #[arg(short = 't', long)]
+        template: Option<String>,
+    },
+
+    /// List, pause, resume or delete recurring entries
+    Recur {
+        #[command(subcommand)]
+        action: Option<RecurAction>,
     },
 
     /// Search journal entries
@@ -142,6 +547,12 @@ pub enum BulletAction {
         query: String,
     },
 
+    /// Export a week or month of the journal
+    Export {
+        #[command(subcommand)]
+        format: BulletExportFormat,
+    },
+
     /// Interactive mode
     #[command(alias = "i")]
     Interactive,
@@ -151,6 +562,59 @@ pub enum BulletAction {
     Ids,
 }
 
+#[derive(Subcommand)]
+pub enum RecurAction {
+    /// List recurring entries
+    #[command(alias = "ls")]
+    List,
+
+    /// Stop adding a recurring entry to new logs
+    Pause {
+        /// Recurrence ID or partial match
+        id: String,
+    },
+
+    /// Start adding a paused recurring entry again
+    Resume {
+        /// Recurrence ID or partial match
+        id: String,
+    },
+
+    /// Delete a recurring entry; entries already added stay
+    Rm {
+        /// Recurrence ID or partial match
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BulletExportFormat {
+    /// Render the period's log and its daily logs as a printable PDF
+    Pdf {
+        /// Export the week
+        #[arg(short = 'w', long, required_unless_present = "month", conflicts_with = "month")]
+        week: bool,
+
+        /// Export the month
+        #[arg(short = 'm', long)]
+        month: bool,
+
+        /// Any date in the period (default: today)
+        #[arg(short = 'd', long, allow_hyphen_values = true)]
+        date: Option<String>,
+
+        /// Output file (default: journal-<period>.pdf)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum StatsPeriod {
+    Day,
+    Week,
+}
+
 #[derive(Clone, ValueEnum)]
 pub enum CompletionShell {
     Bash,
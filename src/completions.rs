@@ -25,12 +25,12 @@ _notes_complete() {
   cmd="${COMP_WORDS[1]}"
 
   case "$cmd" in
-    open|versions|delete|rollback)
+    open|versions|delete|rollback|snapshot|encrypt|lock|attach|rename|links|backlinks)
       local has_title=0
       local i=2
       while [[ $i -lt $COMP_CWORD ]]; do
         local word="${COMP_WORDS[i]}"
-        if [[ "$word" == "--version" || "$word" == "-v" ]]; then
+        if [[ "$word" == "--version" || "$word" == "-v" || "$word" == "--message" || "$word" == "-m" ]]; then
           ((i+=2))
           continue
         fi
@@ -49,10 +49,10 @@ _notes_complete() {
       ;;
     bullet|b)
       if [[ $COMP_CWORD -eq 2 ]]; then
-        COMPREPLY=( $(compgen -W "list pending complete migrate open search interactive" -- "$cur") )
+        COMPREPLY=( $(compgen -W "add list pending complete cancel start reopen schedule edit rm move retype future migrate recur open search export interactive" -- "$cur") )
       elif [[ $COMP_CWORD -eq 3 ]]; then
         local subcmd="${COMP_WORDS[2]}"
-        if [[ "$subcmd" == "complete" || "$subcmd" == "x" ]]; then
+        if [[ "$subcmd" == "complete" || "$subcmd" == "x" || "$subcmd" == "schedule" || "$subcmd" == "cancel" || "$subcmd" == "start" || "$subcmd" == "reopen" || "$subcmd" == "edit" || "$subcmd" == "rm" || "$subcmd" == "move" || "$subcmd" == "retype" ]]; then
           local ids
           ids=$(_notes_bullet_ids)
           COMPREPLY=( $(compgen -W "$ids" -- "$cur") )
@@ -85,12 +85,12 @@ _notes_complete() {
   cmd="${COMP_WORDS[1]}"
 
   case "$cmd" in
-    open|versions|delete|rollback)
+    open|versions|delete|rollback|snapshot|encrypt|lock|attach|rename|links|backlinks)
       local has_title=0
       local i=2
       while [[ $i -lt $COMP_CWORD ]]; do
         local word="${COMP_WORDS[i]}"
-        if [[ "$word" == "--version" || "$word" == "-v" ]]; then
+        if [[ "$word" == "--version" || "$word" == "-v" || "$word" == "--message" || "$word" == "-m" ]]; then
           ((i+=2))
           continue
         fi
@@ -109,10 +109,10 @@ _notes_complete() {
       ;;
     bullet|b)
       if [[ $COMP_CWORD -eq 2 ]]; then
-        COMPREPLY=( $(compgen -W "list pending complete migrate open search interactive" -- "$cur") )
+        COMPREPLY=( $(compgen -W "add list pending complete cancel start reopen schedule edit rm move retype future migrate recur open search export interactive" -- "$cur") )
       elif [[ $COMP_CWORD -eq 3 ]]; then
         local subcmd="${COMP_WORDS[2]}"
-        if [[ "$subcmd" == "complete" || "$subcmd" == "x" ]]; then
+        if [[ "$subcmd" == "complete" || "$subcmd" == "x" || "$subcmd" == "schedule" || "$subcmd" == "cancel" || "$subcmd" == "start" || "$subcmd" == "reopen" || "$subcmd" == "edit" || "$subcmd" == "rm" || "$subcmd" == "move" || "$subcmd" == "retype" ]]; then
           local ids
           ids=$(_notes_bullet_ids)
           COMPREPLY=( $(compgen -W "$ids" -- "$cur") )
@@ -142,11 +142,11 @@ function __notes_needs_id
     end
     set -l sub $cmd[2]
     switch $sub
-        case open versions delete rollback
+        case open versions delete rollback snapshot encrypt lock attach rename links backlinks
             set -l i 3
             while test $i -le (count $cmd)
                 set -l word $cmd[$i]
-                if test "$word" = "--version" -o "$word" = "-v"
+                if test "$word" = "--version" -o "$word" = "-v" -o "$word" = "--message" -o "$word" = "-m"
                     set i (math $i + 2)
                     continue
                 end
@@ -174,7 +174,7 @@ function __notes_bullet_needs_id
     set -l cmd (commandline -opc)
     if test (count $cmd) -eq 3
         if test "$cmd[2]" = "bullet" -o "$cmd[2]" = "b"
-            if test "$cmd[3]" = "complete" -o "$cmd[3]" = "x"
+            if test "$cmd[3]" = "complete" -o "$cmd[3]" = "x" -o "$cmd[3]" = "schedule" -o "$cmd[3]" = "cancel" -o "$cmd[3]" = "start" -o "$cmd[3]" = "reopen" -o "$cmd[3]" = "edit" -o "$cmd[3]" = "rm" -o "$cmd[3]" = "move" -o "$cmd[3]" = "retype"
                 return 0
             end
         end
@@ -183,6 +183,6 @@ function __notes_bullet_needs_id
 end
 
 complete -c notes -n '__notes_needs_id' -a '(__notes_ids)'
-complete -c notes -n '__notes_bullet_subcommand' -a 'list pending complete migrate open search interactive'
+complete -c notes -n '__notes_bullet_subcommand' -a 'add list pending complete cancel start reopen schedule edit rm move retype future migrate recur open search export interactive'
 complete -c notes -n '__notes_bullet_needs_id' -a '(__notes_bullet_ids)'
 "#;
@@ -0,0 +1,148 @@
+use crate::utils::{from_hex, to_hex};
+use anyhow::{anyhow, bail, Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::XChaCha20Poly1305;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Prefix of every encrypted file, followed by the nonce and the ciphertext
+const MAGIC: &[u8] = b"NOTESENC1\n";
+const NONCE_LEN: usize = 24;
+const SALT_LEN: usize = 16;
+
+/// Lets scripts and tests supply the passphrase instead of the terminal prompt
+const PASSPHRASE_ENV: &str = "NOTES_PASSPHRASE";
+
+/// A 256-bit key derived from a passphrase with Argon2id. Only ever held in memory: unlocked
+/// notes keep theirs in the key agent.
+#[derive(Clone)]
+pub(crate) struct Key([u8; 32]);
+
+impl Key {
+    pub(crate) fn derive(passphrase: &str, salt: &str) -> Result<Self> {
+        let salt = from_hex(salt)?;
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|err| anyhow!("Key derivation failed: {}", err))?;
+        Ok(Self(key))
+    }
+
+    pub(crate) fn from_hex(hex: &str) -> Result<Self> {
+        let key = from_hex(hex)?
+            .try_into()
+            .map_err(|_| anyhow!("Invalid key length"))?;
+        Ok(Self(key))
+    }
+
+    pub(crate) fn to_hex(&self) -> String {
+        to_hex(&self.0)
+    }
+
+    /// HMAC-SHA256 of `data`, for names that must not reveal what they stand for.
+    pub(crate) fn mac(&self, data: &[u8]) -> String {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.0)
+            .expect("HMAC accepts keys of any length");
+        mac.update(data);
+        to_hex(&mac.finalize().into_bytes())
+    }
+
+    pub(crate) fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let cipher = XChaCha20Poly1305::new(&self.0.into());
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow!("Encryption failed"))?;
+
+        let mut sealed = MAGIC.to_vec();
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    pub(crate) fn decrypt(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        let Some(body) = sealed.strip_prefix(MAGIC) else {
+            bail!("Not an encrypted file");
+        };
+        if body.len() < NONCE_LEN {
+            bail!("Encrypted file is truncated");
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        XChaCha20Poly1305::new(&self.0.into())
+            .decrypt(nonce.into(), ciphertext)
+            .map_err(|_| anyhow!("Wrong passphrase or corrupted file"))
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        for byte in &mut self.0 {
+            // Volatile so the wipe is not optimized away.
+            unsafe { std::ptr::write_volatile(byte, 0) };
+        }
+    }
+}
+
+pub(crate) fn new_salt() -> String {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    to_hex(&salt)
+}
+
+/// Prompts for a passphrase without echo, asking twice when `confirm` is set.
+/// `NOTES_PASSPHRASE` takes precedence over the prompt; either must not be empty.
+pub(crate) fn read_passphrase(prompt: &str, confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        if passphrase.is_empty() {
+            bail!("{} must not be empty", PASSPHRASE_ENV);
+        }
+        return Ok(passphrase);
+    }
+
+    let passphrase = rpassword::prompt_password(prompt).context("Failed to read passphrase")?;
+    if passphrase.is_empty() {
+        bail!("Passphrase must not be empty");
+    }
+    if confirm {
+        let again = rpassword::prompt_password("Repeat passphrase: ")
+            .context("Failed to read passphrase")?;
+        if again != passphrase {
+            bail!("Passphrases do not match");
+        }
+    }
+    Ok(passphrase)
+}
+
+/// Creates a directory only the current user can enter.
+pub(crate) fn create_private_dir(dir: &Path) -> Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))
+}
+
+/// Writes a file with mode 0600, creating its directory with mode 0700.
+pub(crate) fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    if let Some(parent) = path.parent() {
+        create_private_dir(parent)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    file.write_all(content)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
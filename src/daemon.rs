@@ -1,4 +1,5 @@
 use crate::app::NotesApp;
+use crate::crypto;
 use crate::paths::DataPaths;
 use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
@@ -77,8 +78,8 @@ fn daemon_running(paths: &DataPaths) -> Result<bool> {
 pub fn run_daemon(paths: &DataPaths) -> Result<()> {
     paths.ensure_dirs()?;
     paths.ensure_journal_dirs()?;
-    write_pid(&paths)?;
-    log_line(&paths, "daemon started")?;
+    write_pid(paths)?;
+    log_line(paths, "daemon started")?;
 
     let (tx, rx) = mpsc::channel();
     let mut watcher: RecommendedWatcher = notify::recommended_watcher(move |res| {
@@ -91,6 +92,9 @@ pub fn run_daemon(paths: &DataPaths) -> Result<()> {
     let _ = watcher.watch(&paths.journal_daily_dir(), RecursiveMode::NonRecursive);
     let _ = watcher.watch(&paths.journal_weekly_dir(), RecursiveMode::NonRecursive);
     let _ = watcher.watch(&paths.journal_monthly_dir(), RecursiveMode::NonRecursive);
+    // Unlocked encrypted notes are edited here and re-encrypted on snapshot.
+    crypto::create_private_dir(&paths.unlocked)?;
+    let _ = watcher.watch(&paths.unlocked, RecursiveMode::NonRecursive);
 
     let cooldown = Duration::from_secs(30);
     let mut pending = false;
@@ -109,12 +113,12 @@ pub fn run_daemon(paths: &DataPaths) -> Result<()> {
                 }
             }
             Ok(Err(err)) => {
-                let _ = log_line(&paths, &format!("watch error: {err}"));
+                let _ = log_line(paths, &format!("watch error: {err}"));
             }
             Err(mpsc::RecvTimeoutError::Timeout) => {
                 if pending && last_event.elapsed() >= cooldown {
-                    if let Err(err) = sync_snapshots(&paths) {
-                        let _ = log_line(&paths, &format!("sync error: {err}"));
+                    if let Err(err) = sync_snapshots(paths) {
+                        let _ = log_line(paths, &format!("sync error: {err}"));
                     }
                     pending = false;
                 }
@@ -230,13 +234,13 @@ fn install_launchd(paths: &DataPaths) -> Result<()> {
         ])
         .status();
 
-    if let Ok(status) = status {
-        if !status.success() {
-            let _ = log_line(
-                paths,
-                "launchctl bootstrap failed; you may need to load the LaunchAgent manually",
-            );
-        }
+    if let Ok(status) = status
+        && !status.success()
+    {
+        let _ = log_line(
+            paths,
+            "launchctl bootstrap failed; you may need to load the LaunchAgent manually",
+        );
     }
 
     Ok(())
@@ -284,13 +288,13 @@ WantedBy=default.target
         .args(["--user", "enable", "--now", "notes-daemon.service"])
         .status();
 
-    if let Ok(status) = status {
-        if !status.success() {
-            let _ = log_line(
-                paths,
-                "systemctl enable failed; you may need to enable the service manually",
-            );
-        }
+    if let Ok(status) = status
+        && !status.success()
+    {
+        let _ = log_line(
+            paths,
+            "systemctl enable failed; you may need to enable the service manually",
+        );
     }
 
     Ok(())
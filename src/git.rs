@@ -0,0 +1,119 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// One committed revision of a file.
+pub struct FileRevision {
+    pub committed_at: DateTime<Utc>,
+    /// Commit subject
+    pub message: String,
+    /// Commit message after the subject, if any
+    pub body: Option<String>,
+    pub content: Vec<u8>,
+}
+
+/// Whether `dir` is inside a git work tree and the `git` binary is available.
+pub(crate) fn is_work_tree(dir: &Path) -> bool {
+    Command::new("git")
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// Committed revisions of `path` (relative to `repo`), oldest first, following renames.
+pub(crate) fn file_history(repo: &Path, path: &Path) -> Result<Vec<FileRevision>> {
+    // `--name-only` prints paths relative to the top of the work tree.
+    let top = git(repo, &["rev-parse", "--show-toplevel"])?;
+    let top = PathBuf::from(String::from_utf8_lossy(&top).trim());
+    let output = git(
+        repo,
+        &[
+            "-c",
+            "core.quotepath=off",
+            "log",
+            "--follow",
+            "--format=%x01%H%x00%cI%x00%s%x00%b%x02",
+            "--name-only",
+            "--",
+            &path.to_string_lossy(),
+        ],
+    )?;
+
+    let mut revisions = Vec::new();
+    for record in String::from_utf8_lossy(&output).split('\u{1}').skip(1) {
+        let Some((header, names)) = record.split_once('\u{2}') else {
+            continue;
+        };
+        let mut fields = header.splitn(4, '\0');
+        let (Some(hash), Some(date), Some(subject), body) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let Some(name) = names.lines().find(|line| !line.trim().is_empty()) else {
+            continue;
+        };
+
+        let committed_at = DateTime::parse_from_rfc3339(date)
+            .with_context(|| format!("Invalid commit date {}", date))?
+            .with_timezone(&Utc);
+        // A deletion at the tip of history has no content to read.
+        let Ok(content) = git(&top, &["show", &format!("{}:{}", hash, name)]) else {
+            continue;
+        };
+        revisions.push(FileRevision {
+            committed_at,
+            message: subject.to_string(),
+            body: body
+                .map(str::trim)
+                .filter(|body| !body.is_empty())
+                .map(str::to_string),
+            content,
+        });
+    }
+
+    revisions.reverse();
+    Ok(revisions)
+}
+
+/// Paths of the files tracked at HEAD, relative to `repo`.
+pub(crate) fn tracked_files(repo: &Path) -> Result<Vec<PathBuf>> {
+    let output = git(
+        repo,
+        &["-c", "core.quotepath=off", "ls-tree", "-r", "--name-only", "HEAD", "."],
+    )?;
+    let top = git(repo, &["rev-parse", "--show-prefix"])?;
+    let prefix = String::from_utf8_lossy(&top).trim().to_string();
+    Ok(String::from_utf8_lossy(&output)
+        .lines()
+        .map(|line| PathBuf::from(line.strip_prefix(prefix.as_str()).unwrap_or(line)))
+        .collect())
+}
+
+/// Content of `path` (relative to `repo`) at HEAD, if it is tracked.
+pub(crate) fn show_at_head(repo: &Path, path: &str) -> Option<Vec<u8>> {
+    git(repo, &["show", &format!("HEAD:./{}", path)]).ok()
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(repo)
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
@@ -0,0 +1,275 @@
+use crate::app::{NoteHistory, NotesApp};
+use crate::utils::wikilink_re;
+use anyhow::{Context, Result};
+use pulldown_cmark::{Options, Parser};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:46rem;margin:2rem auto;\
+padding:0 1rem;line-height:1.5;color:#1d1d1d}a{color:#265c55}nav{font-size:.9rem;margin-bottom:1.5rem}\
+.meta{color:#777;font-size:.85rem}.broken-link{color:#b04545;text-decoration:underline dotted}\
+pre{background:#f3efe8;padding:.75rem;overflow:auto}";
+
+const SEARCH_SCRIPT: &str = r#"<script>
+fetch("search.json").then(r => r.json()).then(notes => {
+  const input = document.getElementById("search");
+  const results = document.getElementById("results");
+  input.addEventListener("input", () => {
+    const needle = input.value.toLowerCase();
+    results.innerHTML = "";
+    if (!needle) return;
+    for (const note of notes) {
+      if (note.title.toLowerCase().includes(needle) || note.text.toLowerCase().includes(needle)) {
+        const item = document.createElement("li");
+        const link = document.createElement("a");
+        link.href = note.url;
+        link.textContent = note.title;
+        item.appendChild(link);
+        results.appendChild(item);
+      }
+    }
+  });
+});
+</script>"#;
+
+#[derive(serde::Serialize)]
+struct SearchEntry {
+    slug: String,
+    title: String,
+    url: String,
+    text: String,
+}
+
+/// Resolves `[[...]]` targets among the exported notes, by id or case-insensitive title.
+struct LinkTargets {
+    by_key: HashMap<String, (String, String)>,
+}
+
+impl LinkTargets {
+    fn new(notes: &[NoteHistory]) -> Self {
+        let mut by_key = HashMap::new();
+        for note in notes {
+            by_key
+                .entry(note.title.to_lowercase())
+                .or_insert((note.slug.clone(), note.title.clone()));
+        }
+        for note in notes {
+            by_key.insert(note.slug.clone(), (note.slug.clone(), note.title.clone()));
+        }
+        Self { by_key }
+    }
+
+    fn resolve(&self, target: &str) -> Option<&(String, String)> {
+        self.by_key
+            .get(target)
+            .or_else(|| self.by_key.get(&target.to_lowercase()))
+    }
+}
+
+/// Renders the current version of every note (or only `titles`) as a static site in `dir`:
+/// an index page, a page per note, per-version history pages and a `search.json`.
+/// Output depends only on the notebook's content, so re-exports diff cleanly.
+pub fn export_html(app: &NotesApp, dir: &Path, titles: &[String]) -> Result<usize> {
+    let wanted: Vec<String> = titles.iter().map(|title| title.to_lowercase()).collect();
+    let mut notes: Vec<NoteHistory> = app
+        .note_histories()
+        .into_iter()
+        .filter(|note| note.encryption.is_none())
+        .filter(|note| {
+            wanted.is_empty()
+                || wanted.contains(&note.title.to_lowercase())
+                || wanted.contains(&note.slug)
+        })
+        .collect();
+    notes.sort_by(|a, b| a.slug.cmp(&b.slug));
+
+    let links = LinkTargets::new(&notes);
+    let notes_dir = dir.join("notes");
+    fs::create_dir_all(&notes_dir)
+        .with_context(|| format!("Failed to create {}", notes_dir.display()))?;
+
+    let mut search = Vec::new();
+    for note in &notes {
+        let current = note
+            .versions
+            .iter()
+            .find(|version| version.version == note.current_version)
+            .or(note.versions.last());
+        let content = current
+            .map(|version| fs::read_to_string(&version.path))
+            .transpose()?
+            .unwrap_or_default();
+
+        let body = format!(
+            "<nav><a href=\"../index.html\">All notes</a> · <a href=\"{slug}/history.html\">History</a></nav>\n\
+             <h1>{title}</h1>\n<p class=\"meta\">v{version} · {updated}</p>\n{content}",
+            slug = note.slug,
+            title = escape(&note.title),
+            version = note.current_version,
+            updated = note.updated_at.format("%Y-%m-%d %H:%M UTC"),
+            content = render_markdown(&content, &links, ""),
+        );
+        write_page(&notes_dir.join(format!("{}.html", note.slug)), &note.title, &body)?;
+        write_history(&notes_dir.join(&note.slug), note, &links)?;
+
+        search.push(SearchEntry {
+            slug: note.slug.clone(),
+            title: note.title.clone(),
+            url: format!("notes/{}.html", note.slug),
+            text: content,
+        });
+    }
+
+    write_index(app, dir, &notes)?;
+    copy_attachments(app, dir, &notes)?;
+
+    let search_path = dir.join("search.json");
+    fs::write(&search_path, serde_json::to_string_pretty(&search)? + "\n")
+        .with_context(|| format!("Failed to write {}", search_path.display()))?;
+
+    Ok(notes.len())
+}
+
+fn write_history(note_dir: &Path, note: &NoteHistory, links: &LinkTargets) -> Result<()> {
+    fs::create_dir_all(note_dir)
+        .with_context(|| format!("Failed to create {}", note_dir.display()))?;
+
+    let mut items = String::new();
+    for version in note.versions.iter().rev() {
+        let content = fs::read_to_string(&version.path)
+            .with_context(|| format!("Failed to read {}", version.path.display()))?;
+        let message = version
+            .message
+            .as_deref()
+            .map(|message| format!(" · {}", escape(message)))
+            .unwrap_or_default();
+
+        let body = format!(
+            "<nav><a href=\"../../index.html\">All notes</a> · <a href=\"history.html\">History</a></nav>\n\
+             <h1>{title}</h1>\n<p class=\"meta\">v{version} · {created}{message}</p>\n{content}",
+            title = escape(&note.title),
+            version = version.version,
+            created = version.created_at.format("%Y-%m-%d %H:%M UTC"),
+            content = render_markdown(&content, links, "../"),
+        );
+        write_page(
+            &note_dir.join(format!("v{}.html", version.version)),
+            &note.title,
+            &body,
+        )?;
+
+        items.push_str(&format!(
+            "<li><a href=\"v{version}.html\">v{version}</a> <span class=\"meta\">{created}{message}</span></li>\n",
+            version = version.version,
+            created = version.created_at.format("%Y-%m-%d %H:%M UTC"),
+        ));
+    }
+
+    let body = format!(
+        "<nav><a href=\"../../index.html\">All notes</a> · <a href=\"../{slug}.html\">Current</a></nav>\n\
+         <h1>{title}: history</h1>\n<ul>\n{items}</ul>",
+        slug = note.slug,
+        title = escape(&note.title),
+    );
+    write_page(&note_dir.join("history.html"), &note.title, &body)
+}
+
+/// Copies the attachments linked from any exported version to `dir/attachments`.
+fn copy_attachments(app: &NotesApp, dir: &Path, notes: &[NoteHistory]) -> Result<()> {
+    let names: BTreeSet<&String> = notes
+        .iter()
+        .flat_map(|note| &note.versions)
+        .flat_map(|version| &version.attachments)
+        .collect();
+    if names.is_empty() {
+        return Ok(());
+    }
+
+    let target_dir = dir.join("attachments");
+    fs::create_dir_all(&target_dir)
+        .with_context(|| format!("Failed to create {}", target_dir.display()))?;
+    for name in names {
+        let source = app.paths().attachment_file(name);
+        if source.exists() {
+            fs::copy(&source, target_dir.join(name))
+                .with_context(|| format!("Failed to copy {}", source.display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn write_index(app: &NotesApp, dir: &Path, notes: &[NoteHistory]) -> Result<()> {
+    let mut summaries: Vec<_> = app
+        .note_summaries()
+        .into_iter()
+        .filter(|summary| notes.iter().any(|note| note.slug == summary.slug))
+        .collect();
+    summaries.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then(a.slug.cmp(&b.slug)));
+
+    let mut items = String::new();
+    for summary in &summaries {
+        items.push_str(&format!(
+            "<li><a href=\"notes/{slug}.html\">{title}</a> <span class=\"meta\">v{version} · {versions} versions · {updated}</span></li>\n",
+            slug = summary.slug,
+            title = escape(&summary.title),
+            version = summary.current_version,
+            versions = summary.versions,
+            updated = summary.updated_at.format("%Y-%m-%d %H:%M UTC"),
+        ));
+    }
+
+    let body = format!(
+        "<h1>Notes</h1>\n<input id=\"search\" type=\"search\" placeholder=\"Search\">\n\
+         <ul id=\"results\"></ul>\n<ul>\n{items}</ul>\n{SEARCH_SCRIPT}"
+    );
+    write_page(&dir.join("index.html"), "Notes", &body)
+}
+
+fn write_page(path: &Path, title: &str, body: &str) -> Result<()> {
+    let page = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    );
+    fs::write(path, page).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn render_markdown(content: &str, links: &LinkTargets, prefix: &str) -> String {
+    // Working copies link attachments relative to `files/`, which matches `notes/` here.
+    let linked = replace_wikilinks(content, links, prefix)
+        .replace("](../attachments/", &format!("]({}../attachments/", prefix));
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, Parser::new_ext(&linked, options));
+    html
+}
+
+/// Turns `[[Target]]` and `[[Target|label]]` into Markdown links to the exported page.
+/// Links to notes that are not part of the export are marked as broken.
+fn replace_wikilinks(content: &str, links: &LinkTargets, prefix: &str) -> String {
+    wikilink_re()
+        .replace_all(content, |caps: &regex::Captures| {
+            let target = caps[1].trim();
+            let label = caps.get(2).map(|m| m.as_str().trim());
+            match links.resolve(target) {
+                Some((slug, title)) => {
+                    format!("[{}]({}{}.html)", label.unwrap_or(title), prefix, slug)
+                }
+                None => format!(
+                    "<span class=\"broken-link\">{}</span>",
+                    escape(label.unwrap_or(target))
+                ),
+            }
+        })
+        .into_owned()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,150 @@
+use crate::app::{ImportedNote, ImportedVersion, NotesApp};
+use crate::git;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub struct DirImportSummary {
+    /// Source path (relative to the imported folder) and the id it was imported as
+    pub imported: Vec<(PathBuf, String)>,
+    /// Source paths skipped because an identical note already exists
+    pub skipped: Vec<PathBuf>,
+    /// Imported notes whose id differs from the file name
+    pub renamed: Vec<(PathBuf, String)>,
+}
+
+/// Creates a note for every Markdown file under `dir`, skipping hidden folders such as
+/// `.git` and `.obsidian`. With `git_history`, each commit touching a file becomes a version.
+pub fn import_dir(app: &mut NotesApp, dir: &Path, git_history: bool) -> Result<DirImportSummary> {
+    if !dir.is_dir() {
+        bail!("{} is not a directory", dir.display());
+    }
+    if git_history && !git::is_work_tree(dir) {
+        bail!("{} is not inside a git repository", dir.display());
+    }
+
+    let mut files = Vec::new();
+    collect_markdown(dir, dir, &mut files)?;
+    files.sort();
+
+    let mut summary = DirImportSummary {
+        imported: Vec::new(),
+        skipped: Vec::new(),
+        renamed: Vec::new(),
+    };
+
+    for rel in files {
+        let path = dir.join(&rel);
+        let content =
+            fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let modified: DateTime<Utc> = fs::metadata(&path)?.modified()?.into();
+        let stem = rel
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let title = note_title(&String::from_utf8_lossy(&content)).unwrap_or_else(|| stem.clone());
+
+        let mut versions = Vec::new();
+        if git_history {
+            for revision in git::file_history(dir, &rel)? {
+                if versions
+                    .last()
+                    .is_some_and(|last: &ImportedVersion| last.content == revision.content)
+                {
+                    continue;
+                }
+                versions.push(ImportedVersion {
+                    version: versions.len() as u32 + 1,
+                    created_at: revision.committed_at,
+                    message: Some(revision.message),
+                    content: revision.content,
+                    hash: None,
+                });
+            }
+        }
+        if versions.last().is_none_or(|last| last.content != content) {
+            versions.push(ImportedVersion {
+                version: versions.len() as u32 + 1,
+                created_at: modified,
+                message: None,
+                content,
+                hash: None,
+            });
+        }
+
+        if app.has_identical_note(&title, &versions) {
+            summary.skipped.push(rel);
+            continue;
+        }
+
+        // Git history predates the file's mtime; otherwise the mtime is all we know.
+        let created_at = versions.first().map(|v| v.created_at).unwrap_or(modified);
+        let slug = app.import_note(ImportedNote {
+            title,
+            slug: None,
+            created_at,
+            versions,
+            working: None,
+            encryption: None,
+        })?;
+
+        if slug != stem {
+            summary.renamed.push((rel.clone(), slug.clone()));
+        }
+        summary.imported.push((rel, slug));
+    }
+
+    Ok(summary)
+}
+
+/// Title from YAML front matter (`title:`), else the first Markdown heading.
+pub(crate) fn note_title(content: &str) -> Option<String> {
+    let mut lines = content.lines();
+    let mut body_start = 0;
+
+    if content.starts_with("---") {
+        lines.next();
+        for (index, line) in lines.by_ref().enumerate() {
+            if line.trim() == "---" || line.trim() == "..." {
+                body_start = index + 2;
+                break;
+            }
+            if let Some(value) = line.strip_prefix("title:") {
+                let value = value.trim().trim_matches(|c| c == '"' || c == '\'').trim();
+                if !value.is_empty() {
+                    return Some(value.to_string());
+                }
+            }
+        }
+    }
+
+    content
+        .lines()
+        .skip(body_start)
+        .find_map(|line| line.strip_prefix("# "))
+        .map(|heading| heading.trim().to_string())
+        .filter(|heading| !heading.is_empty())
+}
+
+fn collect_markdown(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_markdown(root, &path, files)?;
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+            && let Ok(rel) = path.strip_prefix(root)
+        {
+            files.push(rel.to_path_buf());
+        }
+    }
+    Ok(())
+}
@@ -1,23 +1,90 @@
+mod agent;
 mod app;
+mod archive;
 mod bullet;
 mod cli;
 mod completions;
+mod crypto;
 mod daemon;
+mod git;
+mod html;
+mod importer;
+mod mirror;
+mod oplog;
 mod paths;
+mod pdf;
+mod stats;
+mod templates;
 mod ui;
 mod utils;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use chrono::Local;
 use clap::Parser;
-use cli::{Cli, Commands};
+use cli::{AtAction, Cli, Commands, ExportFormat, ImportSource, TemplateAction};
 use completions::print_completions;
 use daemon::{ensure_daemon_running, run_daemon};
+use html::export_html;
 use bullet::{handle_bullet_command, run_interactive};
+use stats::print_stats;
 use ui::run_ui;
-use utils::launch_subl_if_installed;
+use std::time::Duration;
+use utils::{env_flag, launch_subl_if_installed, parse_timestamp};
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+
+    // These work on the encrypted store itself, before it can be loaded.
+    let home = paths::notes_home()?;
+    match cli.command {
+        Commands::EncryptStore => {
+            let passphrase = crypto::read_passphrase("New store passphrase: ", true)?;
+            let count = agent::encrypt_store(&home, &passphrase)?;
+            println!(
+                "Encrypted {} file(s) in {}. Run `notes unlock` to use it.",
+                count,
+                home.display()
+            );
+            return Ok(());
+        }
+        Commands::Unlock { timeout, on_disk } => {
+            if !agent::is_encrypted_store(&home) {
+                bail!("{} is not encrypted. Run `notes encrypt-store` first.", home.display());
+            }
+            if let Some(until) = agent::unlocked_until(&home)? {
+                println!("Already unlocked until {}", until.with_timezone(&Local).format("%H:%M"));
+                return Ok(());
+            }
+            let passphrase = crypto::read_passphrase("Store passphrase: ", false)?;
+            let workspace =
+                agent::unlock(&home, &passphrase, Duration::from_secs(timeout * 60), on_disk)?;
+            if on_disk && workspace == agent::disk_workspace(&home)? {
+                eprintln!(
+                    "Warning: the decrypted store is on persistent disk until it locks again"
+                );
+            }
+            println!("Unlocked for {} minute(s) in {}", timeout, workspace.display());
+            return Ok(());
+        }
+        Commands::Agent {
+            timeout,
+            on_disk,
+            notes,
+        } => {
+            if notes {
+                return agent::run_key_agent(Duration::from_secs(timeout));
+            }
+            return agent::run_agent(&home, Duration::from_secs(timeout), on_disk);
+        }
+        Commands::Lock { title: None }
+            if agent::is_encrypted_store(&home) && agent::unlocked_until(&home)?.is_none() =>
+        {
+            println!("The notes store is locked.");
+            return Ok(());
+        }
+        _ => {}
+    }
+
     let mut app = app::NotesApp::load()?;
 
     if !matches!(
@@ -32,28 +99,148 @@ fn main() -> Result<()> {
     }
 
     match cli.command {
-        Commands::New { title } => {
-            let path = app.create_note(title)?;
+        Commands::New {
+            title,
+            encrypted,
+            template,
+        } => {
+            let path = if encrypted {
+                let passphrase = crypto::read_passphrase("New passphrase: ", true)?;
+                let (slug, _) = app.create_note_with_slug(title, template.as_deref())?;
+                app.encrypt_note(&slug, &passphrase)?;
+                app.unlock_note(&slug, &passphrase)?
+            } else {
+                app.create_note(title, template.as_deref())?
+            };
             app.save()?;
             launch_subl_if_installed(&path);
             println!("{}", path.display());
         }
+        Commands::Templates {
+            action: TemplateAction::List,
+        } => {
+            let names = templates::list_templates(app.paths())?;
+            if names.is_empty() {
+                println!(
+                    "No templates. Add Markdown files to {}",
+                    app.paths().templates.display()
+                );
+            }
+            for name in names {
+                println!("{}", name);
+            }
+        }
         Commands::Open { title } => {
+            if app.needs_unlock(&title)? {
+                let passphrase = crypto::read_passphrase("Passphrase: ", false)?;
+                app.unlock_note(&title, &passphrase)?;
+            }
             let path = app.open_note(&title)?;
             app.save()?;
             launch_subl_if_installed(&path);
             println!("{}", path.display());
         }
-        Commands::List => {
-            let _ = app.snapshot_all_changes()?;
-            app.list_notes()?;
+        Commands::Rename { title, new_title } => {
+            let rewritten = app.rename_note(&title, &new_title)?;
             app.save()?;
+            println!("Renamed {} to {}", title, new_title);
+            if !rewritten.is_empty() {
+                println!("Updated links in: {}", rewritten.join(", "));
+            }
         }
-        Commands::Versions { title } => {
-            let _ = app.snapshot_all_changes()?;
-            app.list_versions(&title)?;
+        Commands::Links { title: Some(title), .. } => {
+            let (title, links) = app.links_of(&title)?;
+            if links.is_empty() {
+                println!("No links from {}.", title);
+            }
+            for link in links {
+                match link.slug {
+                    Some(slug) => println!("- [[{}]] -> {}", link.target, slug),
+                    None => println!("- [[{}]] (broken)", link.target),
+                }
+            }
+        }
+        Commands::Links { title: None, .. } => {
+            let broken = app.broken_links();
+            if broken.is_empty() {
+                println!("No broken links.");
+            }
+            for (title, slug, target) in broken {
+                println!("- {} (id: {}) -> [[{}]]", title, slug, target);
+            }
+        }
+        Commands::Backlinks { title } => {
+            let (title, sources) = app.backlinks_of(&title)?;
+            if sources.is_empty() {
+                println!("No notes link to {}.", title);
+            }
+            for (title, slug) in sources {
+                println!("- {} (id: {})", title, slug);
+            }
+        }
+        Commands::Attach { title, file } => {
+            let link = app.attach_file(&title, &file)?;
+            app.save()?;
+            println!("{}", link);
+        }
+        Commands::Gc => {
+            let (removed, bytes) = app.gc_attachments()?;
+            println!("Removed {} unreferenced attachment(s), {} bytes", removed, bytes);
+        }
+        Commands::Encrypt { title } => {
+            let passphrase = crypto::read_passphrase("New passphrase: ", true)?;
+            let slug = app.encrypt_note(&title, &passphrase)?;
+            app.save()?;
+            println!("Encrypted {}. Run `notes open {}` to unlock it.", title, slug);
+        }
+        Commands::Lock { title } => {
+            let locked = app.lock_notes(title.as_deref())?;
             app.save()?;
+            if !locked.is_empty() {
+                println!("Locked {} note(s): {}", locked.len(), locked.join(", "));
+            }
+            if title.is_none() && agent::lock(&home)? {
+                println!("Locked the notes store.");
+            } else if locked.is_empty() {
+                println!("No unlocked notes.");
+            }
+        }
+        Commands::List { snapshot } => {
+            if snapshot_on_read(snapshot)? {
+                let _ = app.snapshot_all_changes()?;
+                app.save()?;
+            }
+            app.list_notes()?;
         }
+        Commands::Versions { title, snapshot } => {
+            if snapshot_on_read(snapshot)? {
+                let _ = app.snapshot_all_changes()?;
+                app.save()?;
+            }
+            app.list_versions(&title)?;
+        }
+        Commands::Snapshot {
+            title,
+            all: _,
+            message,
+        } => match title {
+            Some(title) => {
+                match app.snapshot_note(&title, message.as_deref())? {
+                    Some(version) => println!("Snapshot {}: v{}", title, version),
+                    None => println!("No changes in {}", title),
+                }
+                app.save()?;
+            }
+            None => {
+                let updated = app.snapshot_all_with_message(message.as_deref())?;
+                app.save()?;
+                if updated.is_empty() {
+                    println!("No changes.");
+                } else {
+                    println!("Snapshot {} note(s): {}", updated.len(), updated.join(", "));
+                }
+            }
+        },
         Commands::Rollback { title, version } => {
             let path = app.rollback(&title, version)?;
             app.save()?;
@@ -64,10 +251,124 @@ fn main() -> Result<()> {
             app.save()?;
             println!("Deleted note: {}", deleted);
         }
-        Commands::Search { query } => {
-            let _ = app.snapshot_all_changes()?;
+        Commands::Search { query, snapshot } => {
+            if snapshot_on_read(snapshot)? {
+                let _ = app.snapshot_all_changes()?;
+                app.save()?;
+            }
             app.search(&query)?;
+        }
+        Commands::Undo { count, force } => {
+            let undone = oplog::undo(&mut app, count, force)?;
+            if undone.is_empty() {
+                println!("Nothing to undo.");
+            }
+            for operation in undone {
+                println!("Undid {}", operation);
+            }
+        }
+        Commands::Redo { count, force } => {
+            let redone = oplog::redo(&mut app, count, force)?;
+            if redone.is_empty() {
+                println!("Nothing to redo.");
+            }
+            for operation in redone {
+                println!("Redid {}", operation);
+            }
+        }
+        Commands::Log { limit } => {
+            oplog::print_log(app.paths(), limit)?;
+        }
+        Commands::Stats {
+            since,
+            by,
+            dormant_months,
+            json,
+        } => {
+            let since = since.map(|s| parse_timestamp(&s)).transpose()?;
+            print_stats(&app, since, by, dormant_months, json)?;
+        }
+        Commands::Export {
+            format,
+            archive,
+            latest_only,
+        } => match (format, archive) {
+            (Some(ExportFormat::Html { dir, titles }), _) => {
+                let count = export_html(&app, &dir, &titles)?;
+                println!("Exported {} note(s) to {}", count, dir.display());
+            }
+            (Some(ExportFormat::Pdf { title, output }), _) => {
+                let note = app.working_copy(&title)?;
+                let target = output.unwrap_or_else(|| format!("{}.pdf", note.slug).into());
+                if let Some(warning) = pdf::unsupported_warning(&note.content) {
+                    eprintln!("{}", warning);
+                }
+                let pdf = pdf::render_markdown(&note.title, &note.content);
+                std::fs::write(&target, pdf)
+                    .with_context(|| format!("Failed to write {}", target.display()))?;
+                println!("Exported {} to {}", note.title, target.display());
+            }
+            (Some(ExportFormat::Git { dir }), _) => {
+                let count = mirror::export_git(&app, &dir)?;
+                println!("Wrote {} commit(s) to {}", count, dir.display());
+            }
+            (None, Some(target)) => {
+                let count = archive::export_archive(&app, &target, latest_only)?;
+                println!("Archived {} note(s) to {}", count, target.display());
+            }
+            (None, None) => unreachable!("clap requires --archive without a format"),
+        },
+        Commands::Import { source, archive } => {
+            let summary = match (source, archive) {
+                (Some(ImportSource::Git { dir }), _) => mirror::import_git(&mut app, &dir)?,
+                (None, Some(archive)) => archive::import_archive(&mut app, &archive)?,
+                (None, None) => unreachable!("clap requires an archive without a source"),
+            };
+            app.save()?;
+            for (original, slug) in &summary.imported {
+                if original == slug {
+                    println!("Imported {}", slug);
+                } else {
+                    println!("Imported {} as {}", original, slug);
+                }
+            }
+            for slug in &summary.skipped {
+                println!("Skipped {} (already present)", slug);
+            }
+            println!(
+                "Imported {} note(s), {} journal entries",
+                summary.imported.len(),
+                summary.journal_entries
+            );
+        }
+        Commands::ImportDir { path, git_history } => {
+            let summary = importer::import_dir(&mut app, &path, git_history)?;
             app.save()?;
+            for (source, slug) in &summary.imported {
+                println!("Imported {} as {}", source.display(), slug);
+            }
+            for source in &summary.skipped {
+                println!("Skipped {} (already present)", source.display());
+            }
+            if !summary.renamed.is_empty() {
+                println!("Renamed ids:");
+                for (source, slug) in &summary.renamed {
+                    println!("  {} -> {}", source.display(), slug);
+                }
+            }
+            println!("Imported {} note(s)", summary.imported.len());
+        }
+        Commands::At { time, action } => {
+            let at = parse_timestamp(&time)?;
+            match action {
+                AtAction::List => app.list_notes_at(at)?,
+                AtAction::Show { title } => app.show_note_at(&title, at)?,
+                AtAction::Search { query } => app.search_at(&query, at)?,
+                AtAction::Export { dir } => {
+                    let count = app.export_at(&dir, at)?;
+                    println!("Exported {} note(s) to {}", count, dir.display());
+                }
+            }
         }
         Commands::Bullet {
             action,
@@ -75,11 +376,29 @@ fn main() -> Result<()> {
             task,
             event,
             note,
+            priority,
+            inspiration,
+            explore,
             date,
             weekly,
             monthly,
+            every,
         } => {
-            handle_bullet_command(action, text, task, event, note, date, weekly, monthly)?;
+            handle_bullet_command(
+                action,
+                text,
+                task,
+                event,
+                note,
+                priority,
+                inspiration,
+                explore,
+                date,
+                weekly,
+                monthly,
+                every,
+            )?;
+            agent::sync(&home)?;
         }
         Commands::BulletInteractive => {
             run_interactive()?;
@@ -87,6 +406,9 @@ fn main() -> Result<()> {
         Commands::Ui => {
             run_ui()?;
         }
+        Commands::EncryptStore | Commands::Unlock { .. } | Commands::Agent { .. } => {
+            unreachable!("handled before loading the store")
+        }
         Commands::Daemon => {
             run_daemon(app.paths())?;
         }
@@ -100,3 +422,9 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Read-only commands only create versions when asked to, either per invocation with
+/// `--snapshot` or always via `NOTES_SNAPSHOT_ON_READ=true`.
+fn snapshot_on_read(flag: bool) -> Result<bool> {
+    Ok(flag || env_flag("NOTES_SNAPSHOT_ON_READ")?)
+}
@@ -0,0 +1,505 @@
+use crate::app::{ImportedNote, ImportedVersion, NotesApp};
+use crate::archive::ImportSummary;
+use crate::git;
+use crate::importer::note_title;
+use crate::utils::{from_hex, slugify, to_hex};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Branch the mirror is written to
+const BRANCH: &str = "main";
+
+/// Tracked file mapping note ids to titles, so a mirror can be imported back losslessly
+const TITLES_FILE: &str = ".notes.json";
+
+/// Export progress, kept inside `.git` so the next export only adds new versions
+const STATE_FILE: &str = "notes-export.json";
+
+const AUTHOR: &str = "Notes <notes@localhost>";
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct MirrorState {
+    head: Option<String>,
+    /// Head the last export started from; the branch is still here if that export stopped
+    /// before moving it
+    #[serde(default)]
+    base: Option<String>,
+    /// Last exported version number per note id
+    versions: BTreeMap<String, u32>,
+    /// Date of the last exported version per note id; a removal is never dated before it
+    #[serde(default)]
+    dates: BTreeMap<String, DateTime<Utc>>,
+    /// Notes whose removal was exported, so one that comes back with the same history (for
+    /// example by `undo`) continues where it stopped
+    #[serde(default)]
+    removed: BTreeMap<String, RemovedNote>,
+    /// Blob id per tracked path
+    files: BTreeMap<String, String>,
+    titles: BTreeMap<String, String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RemovedNote {
+    version: u32,
+    /// Blob id of that version
+    blob: String,
+    at: DateTime<Utc>,
+}
+
+/// A commit to be written for one note.
+struct PendingCommit {
+    at: DateTime<Utc>,
+    slug: String,
+    title: String,
+    kind: CommitKind,
+}
+
+enum CommitKind {
+    /// Brings back the last exported version of a note whose removal was exported
+    Restore { number: u32, path: PathBuf },
+    Rename { from: String },
+    Version {
+        number: u32,
+        message: Option<String>,
+        path: PathBuf,
+    },
+    /// Removes a deleted or encrypted note
+    Delete,
+}
+
+impl CommitKind {
+    /// Order of commits for the same note and date.
+    fn rank(&self) -> (u8, u32) {
+        match self {
+            CommitKind::Restore { number, .. } => (0, *number),
+            CommitKind::Rename { .. } => (1, 0),
+            CommitKind::Version { number, .. } => (2, *number),
+            CommitKind::Delete => (3, 0),
+        }
+    }
+}
+
+/// Mirrors the notebook into a git repository at `dir`: one `<id>.md` per note and one commit
+/// per version, dated with the version's `created_at`. A rename is one commit dated when the
+/// title changed, and a removal is dated when the note went to the trash. Objects, refs and the
+/// index are written directly; later runs only append commits for changes since. Returns the
+/// number of commits written.
+pub fn export_git(app: &NotesApp, dir: &Path) -> Result<usize> {
+    let git_dir = dir.join(".git");
+    let state_path = git_dir.join(STATE_FILE);
+    let mut state = if state_path.exists() {
+        let content = fs::read_to_string(&state_path)
+            .with_context(|| format!("Failed to read {}", state_path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", state_path.display()))?
+    } else if git_dir.exists() {
+        bail!("{} is a git repository not created by notes export", dir.display());
+    } else {
+        init_repository(&git_dir)?;
+        MirrorState::default()
+    };
+
+    let ref_path = git_dir.join("refs/heads").join(BRANCH);
+    let branch_head = fs::read_to_string(&ref_path)
+        .ok()
+        .map(|head| head.trim().to_string());
+    if branch_head != state.head && branch_head != state.base {
+        bail!(
+            "Branch {} in {} has moved since the last export",
+            BRANCH,
+            dir.display()
+        );
+    }
+
+    state.base = state.head.clone();
+
+    let mut pending = Vec::new();
+    let mut live = Vec::new();
+    let mut encrypted = BTreeMap::new();
+    // Encrypted notes stay out of the mirror; encrypting one removes it like a delete.
+    for note in app.note_histories() {
+        if note.encryption.is_some() {
+            encrypted.insert(note.slug, note.updated_at);
+            continue;
+        }
+        let mut exported = state.versions.get(&note.slug).copied().unwrap_or(0);
+        if exported == 0
+            && let Some(removed) = state.removed.get(&note.slug)
+            && let Some(version) = note.versions.iter().find(|v| v.version == removed.version)
+        {
+            let content = fs::read(&version.path)
+                .with_context(|| format!("Failed to read {}", version.path.display()))?;
+            if object_id("blob", &content) == removed.blob {
+                pending.push(PendingCommit {
+                    at: removed.at,
+                    slug: note.slug.clone(),
+                    title: note.title.clone(),
+                    kind: CommitKind::Restore {
+                        number: version.version,
+                        path: version.path.clone(),
+                    },
+                });
+                exported = version.version;
+            }
+        }
+
+        // Renaming sets `updated_at`; versions from before it keep the old title.
+        let renamed_from = state
+            .titles
+            .get(&note.slug)
+            .filter(|title| exported > 0 && **title != note.title)
+            .cloned();
+        for version in note.versions.iter().filter(|v| v.version > exported) {
+            let title = match &renamed_from {
+                Some(old) if version.created_at < note.updated_at => old.clone(),
+                _ => note.title.clone(),
+            };
+            pending.push(PendingCommit {
+                at: version.created_at,
+                slug: note.slug.clone(),
+                title,
+                kind: CommitKind::Version {
+                    number: version.version,
+                    message: version.message.clone(),
+                    path: version.path.clone(),
+                },
+            });
+        }
+        if let Some(from) = renamed_from {
+            pending.push(PendingCommit {
+                at: note.updated_at,
+                slug: note.slug.clone(),
+                title: note.title.clone(),
+                kind: CommitKind::Rename { from },
+            });
+        }
+        live.push(note.slug);
+    }
+    for slug in state.versions.keys().filter(|slug| !live.contains(slug)) {
+        let removed_at = match app.deleted_at(slug)? {
+            Some(at) => Some(at),
+            None => encrypted.get(slug).copied(),
+        };
+        // Exports from before `dates` was kept fall back to the last commit's date.
+        let last_version = match state.dates.get(slug) {
+            Some(at) => Some(*at),
+            None => state.head.as_deref().map(|head| commit_time(&git_dir, head)).transpose()?,
+        };
+        let Some(at) = removed_at.max(last_version) else {
+            bail!("No date to record the removal of {} at", slug);
+        };
+        pending.push(PendingCommit {
+            at,
+            slug: slug.clone(),
+            title: state.titles.get(slug).cloned().unwrap_or_else(|| slug.clone()),
+            kind: CommitKind::Delete,
+        });
+    }
+    pending.sort_by(|a, b| {
+        a.at.cmp(&b.at)
+            .then_with(|| a.slug.cmp(&b.slug))
+            .then_with(|| a.kind.rank().cmp(&b.kind.rank()))
+    });
+
+    let mut changed: BTreeMap<String, Option<Vec<u8>>> = BTreeMap::new();
+    for commit in &pending {
+        let file = format!("{}.md", commit.slug);
+        let subject = match &commit.kind {
+            CommitKind::Restore { number, path } => {
+                let content =
+                    fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+                state.files.insert(file.clone(), write_object(&git_dir, "blob", &content)?);
+                state.versions.insert(commit.slug.clone(), *number);
+                state.dates.insert(commit.slug.clone(), commit.at);
+                state.titles.insert(commit.slug.clone(), commit.title.clone());
+                state.removed.remove(&commit.slug);
+                changed.insert(file, Some(content));
+                format!("Restore {}", commit.title)
+            }
+            CommitKind::Rename { from } => {
+                state.titles.insert(commit.slug.clone(), commit.title.clone());
+                format!("Rename {} to {}", from, commit.title)
+            }
+            CommitKind::Version {
+                number,
+                message,
+                path,
+            } => {
+                let content =
+                    fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+                state.files.insert(file.clone(), write_object(&git_dir, "blob", &content)?);
+                state.versions.insert(commit.slug.clone(), *number);
+                state.dates.insert(commit.slug.clone(), commit.at);
+                state.titles.insert(commit.slug.clone(), commit.title.clone());
+                state.removed.remove(&commit.slug);
+                changed.insert(file, Some(content));
+                match message {
+                    Some(message) => format!("{} v{}\n\n{}", commit.title, number, message),
+                    None => format!("{} v{}", commit.title, number),
+                }
+            }
+            CommitKind::Delete => {
+                let blob = state.files.remove(&file);
+                let version = state.versions.remove(&commit.slug);
+                state.dates.remove(&commit.slug);
+                state.titles.remove(&commit.slug);
+                if let (Some(blob), Some(version)) = (blob, version) {
+                    let removed = RemovedNote {
+                        version,
+                        blob,
+                        at: commit.at,
+                    };
+                    state.removed.insert(commit.slug.clone(), removed);
+                }
+                changed.insert(file, None);
+                format!("Delete {}", commit.title)
+            }
+        };
+
+        let titles = serde_json::to_string_pretty(&state.titles)? + "\n";
+        state.files.insert(
+            TITLES_FILE.to_string(),
+            write_object(&git_dir, "blob", titles.as_bytes())?,
+        );
+        changed.insert(TITLES_FILE.to_string(), Some(titles.into_bytes()));
+
+        let tree = write_tree(&git_dir, &state.files)?;
+        let mut body = format!("tree {}\n", tree);
+        if let Some(parent) = &state.head {
+            body.push_str(&format!("parent {}\n", parent));
+        }
+        let signature = format!("{} {} +0000", AUTHOR, commit.at.timestamp());
+        body.push_str(&format!(
+            "author {signature}\ncommitter {signature}\n\n{subject}\n"
+        ));
+        state.head = Some(write_object(&git_dir, "commit", body.as_bytes())?);
+    }
+
+    // The branch moves last: until then a failed export leaves it where the saved state
+    // expects, and the next run picks up from there.
+    update_work_tree(dir, &state, &changed)?;
+    let temp_path = state_path.with_extension("json.tmp");
+    fs::write(&temp_path, serde_json::to_string_pretty(&state)?)
+        .with_context(|| format!("Failed to write {}", temp_path.display()))?;
+    fs::rename(&temp_path, &state_path)
+        .with_context(|| format!("Failed to write {}", state_path.display()))?;
+    if let Some(head) = &state.head {
+        fs::write(&ref_path, format!("{}\n", head))
+            .with_context(|| format!("Failed to write {}", ref_path.display()))?;
+    }
+
+    Ok(pending.len())
+}
+
+/// Replays the history of every `*.md` file tracked at HEAD of the repository at `dir`, one
+/// version per commit that touched the file. Ids and titles come from `.notes.json` when the
+/// repository is a notes mirror.
+pub fn import_git(app: &mut NotesApp, dir: &Path) -> Result<ImportSummary> {
+    if !git::is_work_tree(dir) {
+        bail!("{} is not inside a git repository", dir.display());
+    }
+
+    let titles: BTreeMap<String, String> = git::show_at_head(dir, TITLES_FILE)
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_default();
+
+    let mut summary = ImportSummary {
+        imported: Vec::new(),
+        skipped: Vec::new(),
+        journal_entries: 0,
+    };
+
+    for rel in git::tracked_files(dir)? {
+        if !rel.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("md")) {
+            continue;
+        }
+        let stem = rel
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut versions: Vec<ImportedVersion> = Vec::new();
+        let mut title = titles.get(&stem).cloned();
+        for revision in git::file_history(dir, &rel)? {
+            if versions
+                .last()
+                .is_some_and(|last| last.content == revision.content)
+            {
+                continue;
+            }
+            // Mirror commits carry the version message in the body, after a generated subject.
+            let message = match &title {
+                Some(_) if is_generated_subject(&revision.message) => revision.body,
+                _ => Some(revision.message),
+            };
+            versions.push(ImportedVersion {
+                version: versions.len() as u32 + 1,
+                created_at: revision.committed_at,
+                message,
+                content: revision.content,
+                hash: None,
+            });
+        }
+        let Some(first) = versions.first() else {
+            continue;
+        };
+
+        let title = title.take().unwrap_or_else(|| {
+            let last = versions.last().expect("checked above");
+            note_title(&String::from_utf8_lossy(&last.content)).unwrap_or_else(|| stem.clone())
+        });
+        if app.has_identical_note(&title, &versions) {
+            summary.skipped.push(rel.to_string_lossy().into_owned());
+            continue;
+        }
+
+        let original = slugify(&stem);
+        let slug = app.import_note(ImportedNote {
+            title,
+            slug: Some(original.clone()),
+            created_at: first.created_at,
+            versions,
+            working: None,
+            encryption: None,
+        })?;
+        summary.imported.push((original, slug));
+    }
+
+    Ok(summary)
+}
+
+/// Whether `subject` is the `<title> v<N>` subject written by `export_git`. The title may be
+/// one the note had before a rename, so only the version part is checked.
+fn is_generated_subject(subject: &str) -> bool {
+    subject
+        .rsplit_once(" v")
+        .is_some_and(|(_, number)| number.parse::<u32>().is_ok())
+}
+
+fn init_repository(git_dir: &Path) -> Result<()> {
+    for sub in ["objects", "refs/heads", "refs/tags"] {
+        fs::create_dir_all(git_dir.join(sub))
+            .with_context(|| format!("Failed to create {}", git_dir.join(sub).display()))?;
+    }
+    fs::write(git_dir.join("HEAD"), format!("ref: refs/heads/{}\n", BRANCH))?;
+    fs::write(
+        git_dir.join("config"),
+        "[core]\n\trepositoryformatversion = 0\n\tfilemode = true\n\tbare = false\n",
+    )?;
+    Ok(())
+}
+
+/// Hex id git gives an object of `kind` holding `body`.
+fn object_id(kind: &str, body: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("{} {}\0", kind, body.len()));
+    hasher.update(body);
+    to_hex(&hasher.finalize())
+}
+
+/// Stores a loose object and returns its hex id.
+fn write_object(git_dir: &Path, kind: &str, body: &[u8]) -> Result<String> {
+    let id = object_id(kind, body);
+    let mut data = format!("{} {}\0", kind, body.len()).into_bytes();
+    data.extend_from_slice(body);
+
+    let path = git_dir.join("objects").join(&id[..2]).join(&id[2..]);
+    if !path.exists() {
+        fs::create_dir_all(path.parent().expect("object path has a parent"))?;
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data)?;
+        fs::write(&path, encoder.finish()?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(id)
+}
+
+/// Committer date of the commit `id`.
+fn commit_time(git_dir: &Path, id: &str) -> Result<DateTime<Utc>> {
+    let path = git_dir.join("objects").join(&id[..2]).join(&id[2..]);
+    let compressed =
+        fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut data = String::new();
+    ZlibDecoder::new(compressed.as_slice())
+        .read_to_string(&mut data)
+        .with_context(|| format!("Failed to read commit {}", id))?;
+    data.lines()
+        .find_map(|line| line.strip_prefix("committer "))
+        .and_then(|signature| signature.rsplit(' ').nth(1))
+        .and_then(|seconds| seconds.parse::<i64>().ok())
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+        .ok_or_else(|| anyhow!("Commit {} has no committer date", id))
+}
+
+/// Writes a flat tree of regular files; `files` is already sorted by name.
+fn write_tree(git_dir: &Path, files: &BTreeMap<String, String>) -> Result<String> {
+    let mut body = Vec::new();
+    for (name, id) in files {
+        body.extend_from_slice(format!("100644 {}\0", name).as_bytes());
+        body.extend_from_slice(&from_hex(id)?);
+    }
+    write_object(git_dir, "tree", &body)
+}
+
+/// Brings the checkout and `.git/index` in line with the exported tree, so `git status` is clean.
+fn update_work_tree(
+    dir: &Path,
+    state: &MirrorState,
+    changed: &BTreeMap<String, Option<Vec<u8>>>,
+) -> Result<()> {
+    for (name, content) in changed {
+        let path = dir.join(name);
+        match content {
+            Some(content) => fs::write(&path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?,
+            None if path.exists() => fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?,
+            None => {}
+        }
+    }
+
+    let mut index = Vec::new();
+    index.extend_from_slice(b"DIRC");
+    index.extend_from_slice(&2u32.to_be_bytes());
+    index.extend_from_slice(&(state.files.len() as u32).to_be_bytes());
+    for (name, id) in &state.files {
+        let meta = fs::metadata(dir.join(name))
+            .with_context(|| format!("Failed to stat {}", dir.join(name).display()))?;
+        let start = index.len();
+        for field in [
+            meta.ctime() as u32,
+            meta.ctime_nsec() as u32,
+            meta.mtime() as u32,
+            meta.mtime_nsec() as u32,
+            meta.dev() as u32,
+            meta.ino() as u32,
+            0o100644,
+            meta.uid(),
+            meta.gid(),
+            meta.size() as u32,
+        ] {
+            index.extend_from_slice(&field.to_be_bytes());
+        }
+        index.extend_from_slice(&from_hex(id)?);
+        index.extend_from_slice(&(name.len().min(0xfff) as u16).to_be_bytes());
+        index.extend_from_slice(name.as_bytes());
+        // Entries are NUL-padded to a multiple of eight bytes, with at least one NUL.
+        let padding = 8 - (index.len() - start) % 8;
+        index.extend(std::iter::repeat_n(0u8, padding));
+    }
+    let checksum = Sha1::digest(&index);
+    index.extend_from_slice(&checksum);
+
+    let index_path = dir.join(".git/index");
+    fs::write(&index_path, index)
+        .with_context(|| format!("Failed to write {}", index_path.display()))
+}
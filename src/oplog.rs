@@ -0,0 +1,443 @@
+use crate::app::NotesApp;
+use crate::paths::DataPaths;
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of operations kept in the journal
+const MAX_OPERATIONS: usize = 100;
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct OperationLog {
+    operations: Vec<Operation>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Operation {
+    id: u64,
+    action: String,
+    description: String,
+    at: DateTime<Utc>,
+    #[serde(default)]
+    undone: bool,
+    changes: Vec<Change>,
+}
+
+/// A single reversible change. Paths are relative to the notes root.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Change {
+    Note {
+        slug: String,
+        before: Option<serde_json::Value>,
+        after: Option<serde_json::Value>,
+    },
+    File {
+        path: String,
+        before: Option<FileContent>,
+        after: Option<FileContent>,
+    },
+    Move {
+        from: String,
+        to: String,
+    },
+}
+
+/// A file's bytes as kept in the journal: UTF-8 text as a JSON string, anything else (such as
+/// an attachment) base64 encoded as `{"base64": "..."}`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "StoredContent", into = "StoredContent")]
+pub(crate) struct FileContent(Vec<u8>);
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum StoredContent {
+    Text(String),
+    Binary { base64: String },
+}
+
+impl From<String> for FileContent {
+    fn from(text: String) -> Self {
+        Self(text.into_bytes())
+    }
+}
+
+impl TryFrom<StoredContent> for FileContent {
+    type Error = base64::DecodeError;
+
+    fn try_from(stored: StoredContent) -> Result<Self, Self::Error> {
+        match stored {
+            StoredContent::Text(text) => Ok(text.into()),
+            StoredContent::Binary { base64 } => BASE64.decode(base64).map(Self),
+        }
+    }
+}
+
+impl From<FileContent> for StoredContent {
+    fn from(content: FileContent) -> Self {
+        match String::from_utf8(content.0) {
+            Ok(text) => StoredContent::Text(text),
+            Err(err) => StoredContent::Binary {
+                base64: BASE64.encode(err.into_bytes()),
+            },
+        }
+    }
+}
+
+/// What `apply_change` did, so a failed undo or redo can be rolled back.
+enum Applied {
+    File {
+        path: PathBuf,
+        previous: Option<Vec<u8>>,
+    },
+    Move {
+        from: PathBuf,
+        to: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Undo,
+    Redo,
+}
+
+impl OperationLog {
+    fn load(paths: &DataPaths) -> Result<Self> {
+        if !paths.operations.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&paths.operations)
+            .with_context(|| format!("Failed to read {}", paths.operations.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", paths.operations.display()))
+    }
+
+    fn save(&self, paths: &DataPaths) -> Result<()> {
+        let serialized = serde_json::to_string_pretty(self)?;
+        fs::write(&paths.operations, serialized)
+            .with_context(|| format!("Failed to write {}", paths.operations.display()))
+    }
+}
+
+/// Appends an operation to the journal. Recording a new operation drops anything that was
+/// undone and not redone, like an editor's undo stack.
+pub(crate) fn record(
+    paths: &DataPaths,
+    action: &str,
+    description: String,
+    changes: Vec<Change>,
+) -> Result<()> {
+    let mut log = OperationLog::load(paths)?;
+    log.operations.retain(|op| !op.undone);
+
+    let id = log.operations.last().map(|op| op.id + 1).unwrap_or(1);
+    log.operations.push(Operation {
+        id,
+        action: action.to_string(),
+        description,
+        at: Utc::now(),
+        undone: false,
+        changes,
+    });
+
+    let overflow = log.operations.len().saturating_sub(MAX_OPERATIONS);
+    log.operations.drain(..overflow);
+    log.save(paths)
+}
+
+/// Drops every operation that touches `slug`. Used when a note is encrypted, since the
+/// journal would otherwise keep its plaintext.
+pub(crate) fn forget_note(paths: &DataPaths, slug: &str) -> Result<()> {
+    let mut log = OperationLog::load(paths)?;
+    let before = log.operations.len();
+    log.operations.retain(|op| {
+        !op.changes.iter().any(|change| match change {
+            Change::Note { slug: changed, .. } => changed == slug,
+            Change::File { .. } | Change::Move { .. } => false,
+        })
+    });
+    if log.operations.len() == before {
+        return Ok(());
+    }
+    log.save(paths)
+}
+
+pub(crate) fn relative_path(paths: &DataPaths, path: &Path) -> String {
+    path.strip_prefix(&paths.root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// The bytes of `path`, or `None` when there is no such file.
+pub(crate) fn read_optional(path: &Path) -> Option<FileContent> {
+    fs::read(path).ok().map(FileContent)
+}
+
+pub fn undo(app: &mut NotesApp, count: usize, force: bool) -> Result<Vec<String>> {
+    apply(app, count, force, Direction::Undo)
+}
+
+pub fn redo(app: &mut NotesApp, count: usize, force: bool) -> Result<Vec<String>> {
+    apply(app, count, force, Direction::Redo)
+}
+
+fn apply(
+    app: &mut NotesApp,
+    count: usize,
+    force: bool,
+    direction: Direction,
+) -> Result<Vec<String>> {
+    let mut log = OperationLog::load(app.paths())?;
+    let mut applied = Vec::new();
+    let mut done = Vec::new();
+    if let Err(err) = apply_operations(app, &mut log, count, force, direction, &mut applied, &mut done)
+    {
+        rollback(done);
+        return Err(err);
+    }
+
+    app.save()?;
+    log.save(app.paths())?;
+    Ok(applied)
+}
+
+/// Applies up to `count` operations, recording every file write and move in `done`.
+fn apply_operations(
+    app: &mut NotesApp,
+    log: &mut OperationLog,
+    count: usize,
+    force: bool,
+    direction: Direction,
+    applied: &mut Vec<String>,
+    done: &mut Vec<Applied>,
+) -> Result<()> {
+    for _ in 0..count {
+        let position = match direction {
+            Direction::Undo => log.operations.iter().rposition(|op| !op.undone),
+            Direction::Redo => log.operations.iter().position(|op| op.undone),
+        };
+        let Some(position) = position else {
+            break;
+        };
+
+        let operation = &log.operations[position];
+        if !force {
+            verify_state(app, operation, direction)?;
+        }
+        for change in &operation.changes {
+            check_target(app, change, direction)?;
+        }
+
+        match direction {
+            Direction::Undo => {
+                for change in operation.changes.iter().rev() {
+                    apply_change(app, change, direction, done)?;
+                }
+            }
+            Direction::Redo => {
+                for change in &operation.changes {
+                    apply_change(app, change, direction, done)?;
+                }
+            }
+        }
+
+        applied.push(format!("#{} {}", operation.id, operation.description));
+        log.operations[position].undone = direction == Direction::Undo;
+    }
+    Ok(())
+}
+
+/// Makes sure a change can be written before anything is: files must not be directories or
+/// read-only, and a move needs its source and a free destination.
+fn check_target(app: &NotesApp, change: &Change, direction: Direction) -> Result<()> {
+    let root = &app.paths().root;
+    match change {
+        Change::Note { .. } => {}
+        Change::File {
+            path,
+            before,
+            after,
+        } => {
+            let target = if direction == Direction::Undo { before } else { after };
+            let full_path = root.join(path);
+            if let Ok(metadata) = fs::metadata(&full_path) {
+                if metadata.is_dir() {
+                    bail!("Cannot write {}: it is a directory", full_path.display());
+                }
+                if target.is_some() && metadata.permissions().readonly() {
+                    bail!("Cannot write {}: it is read-only", full_path.display());
+                }
+            }
+        }
+        Change::Move { from, to } => {
+            let (source, destination) = if direction == Direction::Undo {
+                (root.join(to), root.join(from))
+            } else {
+                (root.join(from), root.join(to))
+            };
+            if !source.exists() {
+                bail!("Cannot move {}: it does not exist", source.display());
+            }
+            if destination.exists() {
+                bail!("Cannot move to {}: it already exists", destination.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Undoes the writes and moves of a failed undo or redo, newest first.
+fn rollback(done: Vec<Applied>) {
+    for applied in done.into_iter().rev() {
+        let _ = match applied {
+            Applied::File {
+                path,
+                previous: Some(content),
+            } => fs::write(&path, content),
+            Applied::File {
+                path,
+                previous: None,
+            } => fs::remove_file(&path),
+            Applied::Move { from, to } => fs::rename(&to, &from),
+        };
+    }
+}
+
+/// Refuses to touch anything that changed after the operation (or after its undo), so an
+/// undo never silently discards newer edits.
+fn verify_state(app: &NotesApp, operation: &Operation, direction: Direction) -> Result<()> {
+    let root = &app.paths().root;
+    for change in &operation.changes {
+        let (matches, target) = match change {
+            Change::Note {
+                slug,
+                before,
+                after,
+            } => {
+                let expected = if direction == Direction::Undo { after } else { before };
+                (&app.note_meta_value(slug)? == expected, slug.clone())
+            }
+            Change::File {
+                path,
+                before,
+                after,
+            } => {
+                let expected = if direction == Direction::Undo { after } else { before };
+                (&read_optional(&root.join(path)) == expected, path.clone())
+            }
+            Change::Move { from, to } => {
+                let (present, absent) = if direction == Direction::Undo {
+                    (to, from)
+                } else {
+                    (from, to)
+                };
+                (
+                    root.join(present).exists() && !root.join(absent).exists(),
+                    present.clone(),
+                )
+            }
+        };
+
+        if !matches {
+            bail!(
+                "Cannot {} #{} ({}): {} changed since. Use --force to override.",
+                if direction == Direction::Undo { "undo" } else { "redo" },
+                operation.id,
+                operation.description,
+                target
+            );
+        }
+    }
+    Ok(())
+}
+
+fn apply_change(
+    app: &mut NotesApp,
+    change: &Change,
+    direction: Direction,
+    done: &mut Vec<Applied>,
+) -> Result<()> {
+    let root = app.paths().root.clone();
+    match change {
+        Change::Note {
+            slug,
+            before,
+            after,
+        } => {
+            let target = if direction == Direction::Undo { before } else { after };
+            app.restore_note_meta(slug, target.as_ref())?;
+        }
+        Change::File {
+            path,
+            before,
+            after,
+        } => {
+            let target = if direction == Direction::Undo { before } else { after };
+            let full_path = root.join(path);
+            let previous = fs::read(&full_path).ok();
+            match target {
+                Some(content) => {
+                    if let Some(parent) = full_path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(&full_path, &content.0)
+                        .with_context(|| format!("Failed to write {}", full_path.display()))?;
+                }
+                None => {
+                    if previous.is_none() {
+                        return Ok(());
+                    }
+                    fs::remove_file(&full_path).with_context(|| {
+                        format!("Failed to remove {}", full_path.display())
+                    })?;
+                }
+            }
+            done.push(Applied::File {
+                path: full_path,
+                previous,
+            });
+        }
+        Change::Move { from, to } => {
+            let (source, destination): (PathBuf, PathBuf) = if direction == Direction::Undo {
+                (root.join(to), root.join(from))
+            } else {
+                (root.join(from), root.join(to))
+            };
+            if let Some(parent) = destination.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(&source, &destination)
+                .with_context(|| format!("Failed to move {}", source.display()))?;
+            done.push(Applied::Move {
+                from: source,
+                to: destination,
+            });
+        }
+    }
+    Ok(())
+}
+
+pub fn print_log(paths: &DataPaths, limit: usize) -> Result<()> {
+    let log = OperationLog::load(paths)?;
+    if log.operations.is_empty() {
+        println!("No operations recorded.");
+        return Ok(());
+    }
+
+    for operation in log.operations.iter().rev().take(limit) {
+        println!(
+            "#{} {} {:<16} {}{}",
+            operation.id,
+            operation.at.to_rfc3339(),
+            operation.action,
+            operation.description,
+            if operation.undone { " [undone]" } else { "" }
+        );
+    }
+    Ok(())
+}
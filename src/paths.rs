@@ -1,33 +1,56 @@
+use crate::agent;
 use anyhow::{anyhow, Result};
 use std::env;
 use std::fs;
 use std::path::PathBuf;
 
 pub struct DataPaths {
+    /// `NOTES_HOME`. Differs from `root` when the store is encrypted: `root` is then the
+    /// unlock agent's plaintext workspace.
+    pub home: PathBuf,
     pub root: PathBuf,
     pub versions: PathBuf,
     pub files: PathBuf,
     pub index: PathBuf,
+    pub trash: PathBuf,
+    /// Content-addressed files linked from notes
+    pub attachments: PathBuf,
+    /// Markdown templates for new notes and journal files
+    pub templates: PathBuf,
+    pub operations: PathBuf,
+    /// Decrypted working copies of unlocked encrypted notes and the key agent's socket
+    pub unlocked: PathBuf,
     pub daemon_pid: PathBuf,
     pub daemon_log: PathBuf,
 }
 
 impl DataPaths {
     pub(crate) fn new() -> Result<Self> {
-        let root = match env::var("NOTES_HOME") {
+        let home = notes_home()?;
+        let root = if agent::is_encrypted_store(&home) {
+            agent::workspace(&home)?
+        } else {
+            home.clone()
+        };
+
+        let unlocked = match env::var("NOTES_UNLOCKED_DIR") {
             Ok(path) => PathBuf::from(path),
-            Err(_) => dirs::home_dir()
-                .map(|p| p.join(".notes"))
-                .ok_or_else(|| anyhow!("Unable to determine home directory"))?,
+            Err(_) => root.join("unlocked"),
         };
 
         Ok(Self {
             index: root.join("index.json"),
             versions: root.join("versions"),
             files: root.join("files"),
+            trash: root.join("trash"),
+            attachments: root.join("attachments"),
+            templates: root.join("templates"),
+            operations: root.join("operations.json"),
+            unlocked,
             daemon_pid: root.join("daemon.pid"),
             daemon_log: root.join("daemon.log"),
             root,
+            home,
         })
     }
 
@@ -42,10 +65,26 @@ impl DataPaths {
         self.files.join(format!("{slug}.md"))
     }
 
+    pub(crate) fn attachment_file(&self, name: &str) -> PathBuf {
+        self.attachments.join(name)
+    }
+
+    pub(crate) fn template_file(&self, name: &str) -> PathBuf {
+        self.templates.join(format!("{name}.md"))
+    }
+
+    pub(crate) fn unlocked_file(&self, slug: &str) -> PathBuf {
+        self.unlocked.join(format!("{slug}.md"))
+    }
+
     pub(crate) fn journal_index(&self) -> PathBuf {
         self.journal_root().join("index.json")
     }
 
+    pub(crate) fn journal_recurrences(&self) -> PathBuf {
+        self.journal_root().join("recurrences.json")
+    }
+
     pub(crate) fn journal_daily_dir(&self) -> PathBuf {
         self.journal_root().join("daily")
     }
@@ -80,7 +119,17 @@ impl DataPaths {
         Ok(())
     }
 
-    fn journal_root(&self) -> PathBuf {
+    pub(crate) fn journal_root(&self) -> PathBuf {
         self.root.join("journal")
     }
 }
+
+/// `NOTES_HOME`, defaulting to `~/.notes`.
+pub(crate) fn notes_home() -> Result<PathBuf> {
+    match env::var("NOTES_HOME") {
+        Ok(path) => Ok(PathBuf::from(path)),
+        Err(_) => dirs::home_dir()
+            .map(|p| p.join(".notes"))
+            .ok_or_else(|| anyhow!("Unable to determine home directory")),
+    }
+}
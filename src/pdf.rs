@@ -0,0 +1,704 @@
+use crate::utils::wikilink_re;
+use pdf_writer::{Content, Name, Pdf, Rect, Ref, Str, TextStr};
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// A4 in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+
+const BODY_SIZE: f32 = 11.0;
+const CODE_SIZE: f32 = 9.5;
+const LINE_SPACING: f32 = 1.4;
+
+/// Horizontal space reserved for a list marker, and the extra indent per nesting level
+const MARKER_WIDTH: f32 = 16.0;
+const NEST_INDENT: f32 = 16.0;
+
+/// Glyph advance widths (1/1000 em) of Helvetica and Helvetica-Bold for ASCII 32..=126
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+/// The standard PDF fonts used; none need embedding.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Font {
+    Regular,
+    Bold,
+    Italic,
+    Mono,
+}
+
+impl Font {
+    const ALL: [Font; 4] = [Font::Regular, Font::Bold, Font::Italic, Font::Mono];
+
+    fn resource(self) -> Name<'static> {
+        match self {
+            Font::Regular => Name(b"F1"),
+            Font::Bold => Name(b"F2"),
+            Font::Italic => Name(b"F3"),
+            Font::Mono => Name(b"F4"),
+        }
+    }
+
+    fn base_font(self) -> Name<'static> {
+        match self {
+            Font::Regular => Name(b"Helvetica"),
+            Font::Bold => Name(b"Helvetica-Bold"),
+            Font::Italic => Name(b"Helvetica-Oblique"),
+            Font::Mono => Name(b"Courier"),
+        }
+    }
+
+    fn width(self, text: &[u8], size: f32) -> f32 {
+        let units: u32 = text
+            .iter()
+            .map(|&byte| match (self, byte) {
+                (Font::Mono, _) => 600,
+                (Font::Bold, 32..=126) => HELVETICA_BOLD_WIDTHS[(byte - 32) as usize] as u32,
+                (_, 32..=126) => HELVETICA_WIDTHS[(byte - 32) as usize] as u32,
+                (_, 0x91 | 0x92) => 222,
+                (_, 0x85 | 0x97) => 1000,
+                (_, 0x95) => 350,
+                _ => 556,
+            })
+            .sum();
+        units as f32 * size / 1000.0
+    }
+}
+
+/// Bullet-journal signifiers, drawn as vector shapes rather than font glyphs.
+#[derive(Clone, Copy)]
+enum Glyph {
+    /// `[ ]` open task: a dot
+    Task,
+    /// `[x]` completed task: a cross
+    Done,
+    /// `[>]` migrated task
+    Migrated,
+    /// `[<]` scheduled task
+    Scheduled,
+    /// `[~]` cancelled task: a struck-through dot
+    Cancelled,
+    /// `[/]` task in progress: a single stroke
+    InProgress,
+    /// `o` event: an open circle
+    Event,
+    /// `-` note: a dash
+    Note,
+    /// `*` or `+` list item
+    Bullet,
+}
+
+enum Marker {
+    Glyph(Glyph),
+    /// Ordered list number
+    Text(String),
+}
+
+struct Span {
+    text: String,
+    font: Font,
+}
+
+/// One block of the document after line-based Markdown parsing.
+enum Block {
+    Heading(usize, Vec<Span>),
+    Paragraph(Vec<Span>),
+    Item {
+        level: usize,
+        marker: Marker,
+        spans: Vec<Span>,
+    },
+    Quote(Vec<Span>),
+    Code(Vec<String>),
+    Rule,
+}
+
+/// Lays out Markdown as an A4 PDF. Headings, lists, quotes, code blocks and rules are
+/// recognised, and task markers are drawn as bullet-journal signifiers.
+pub fn render_markdown(title: &str, markdown: &str) -> Vec<u8> {
+    let mut layout = Layout::new();
+    let mut previous_was_item = false;
+    for block in parse_blocks(markdown) {
+        let is_item = matches!(block, Block::Item { .. });
+        if !(is_item && previous_was_item) {
+            layout.space(BODY_SIZE * 0.5);
+        }
+        previous_was_item = is_item;
+
+        match block {
+            Block::Heading(level, spans) => {
+                let size = match level {
+                    1 => 20.0,
+                    2 => 15.0,
+                    _ => 12.5,
+                };
+                let spans: Vec<Span> = spans
+                    .into_iter()
+                    .map(|span| Span {
+                        font: if span.font == Font::Mono { Font::Mono } else { Font::Bold },
+                        ..span
+                    })
+                    .collect();
+                layout.space(size * 0.4);
+                layout.paragraph(&spans, size, 0.0, None, 0.0);
+            }
+            Block::Paragraph(spans) => layout.paragraph(&spans, BODY_SIZE, 0.0, None, 0.0),
+            Block::Item {
+                level,
+                marker,
+                spans,
+            } => {
+                let indent = level as f32 * NEST_INDENT + MARKER_WIDTH;
+                layout.paragraph(&spans, BODY_SIZE, indent, Some(&marker), 0.0);
+            }
+            Block::Quote(spans) => layout.paragraph(&spans, BODY_SIZE, MARKER_WIDTH, None, 0.4),
+            Block::Code(lines) => {
+                for line in lines {
+                    layout.code_line(&line);
+                }
+            }
+            Block::Rule => layout.rule(),
+        }
+    }
+    layout.finish(title)
+}
+
+fn parse_blocks(markdown: &str) -> Vec<Block> {
+    static HEADING_RE: OnceLock<Regex> = OnceLock::new();
+    static ITEM_RE: OnceLock<Regex> = OnceLock::new();
+    let heading_re = HEADING_RE.get_or_init(|| Regex::new(r"^(#{1,6})\s+(.*?)\s*#*$").unwrap());
+    let item_re = ITEM_RE.get_or_init(|| {
+        Regex::new(r"^(\s*)(?:([-*+])|(\d+)[.)])\s+(?:\[([ xX><o~/-])\]\s+)?(.*)$").unwrap()
+    });
+
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<Vec<String>> = None;
+
+    for line in markdown.lines() {
+        if let Some(lines) = code.as_mut() {
+            if line.trim_start().starts_with("```") {
+                blocks.push(Block::Code(code.take().unwrap_or_default()));
+            } else {
+                lines.push(line.replace('\t', "    "));
+            }
+            continue;
+        }
+
+        let trimmed = line.trim();
+        let is_paragraph_text = !(trimmed.is_empty()
+            || trimmed.starts_with("```")
+            || trimmed.starts_with('>')
+            || heading_re.is_match(line)
+            || item_re.is_match(line)
+            || is_rule(trimmed));
+        if is_paragraph_text {
+            paragraph.push(trimmed);
+            continue;
+        }
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(parse_inline(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+
+        if trimmed.starts_with("```") {
+            code = Some(Vec::new());
+        } else if let Some(caps) = heading_re.captures(line) {
+            blocks.push(Block::Heading(caps[1].len(), parse_inline(&caps[2])));
+        } else if is_rule(trimmed) {
+            blocks.push(Block::Rule);
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            blocks.push(Block::Quote(parse_inline(quote.trim())));
+        } else if let Some(caps) = item_re.captures(line) {
+            let level = caps[1].replace('\t', "    ").len() / 2;
+            let marker = match (caps.get(2).map(|m| m.as_str()), caps.get(4).map(|m| m.as_str())) {
+                (_, Some(" ")) => Marker::Glyph(Glyph::Task),
+                (_, Some("x" | "X")) => Marker::Glyph(Glyph::Done),
+                (_, Some(">")) => Marker::Glyph(Glyph::Migrated),
+                (_, Some("<")) => Marker::Glyph(Glyph::Scheduled),
+                (_, Some("~")) => Marker::Glyph(Glyph::Cancelled),
+                (_, Some("/")) => Marker::Glyph(Glyph::InProgress),
+                (_, Some("o")) => Marker::Glyph(Glyph::Event),
+                (_, Some(_)) | (Some("-"), None) => Marker::Glyph(Glyph::Note),
+                (Some(_), None) => Marker::Glyph(Glyph::Bullet),
+                (None, None) => Marker::Text(format!("{}.", &caps[3])),
+            };
+            blocks.push(Block::Item {
+                level,
+                marker,
+                spans: item_spans(&caps[5], caps.get(4).map(|m| m.as_str())),
+            });
+        }
+    }
+
+    if let Some(lines) = code {
+        blocks.push(Block::Code(lines));
+    }
+    if !paragraph.is_empty() {
+        blocks.push(Block::Paragraph(parse_inline(&paragraph.join(" "))));
+    }
+    blocks
+}
+
+/// Spans of a list item with the given `[ ]` marker. On journal entries, leading signifiers
+/// (`*`, `!`, `?`) are printed as written rather than read as emphasis, and the `~~` around a
+/// cancelled task's text is dropped since the marker already shows it.
+fn item_spans(text: &str, entry_marker: Option<&str>) -> Vec<Span> {
+    let text = match entry_marker {
+        Some("~") => text
+            .strip_prefix("~~")
+            .and_then(|rest| rest.strip_suffix("~~"))
+            .unwrap_or(text),
+        _ => text,
+    };
+    if entry_marker.is_some()
+        && let Some((symbols, rest)) = text.split_once(' ')
+        && !symbols.is_empty()
+        && symbols.chars().all(|c| matches!(c, '*' | '!' | '?'))
+    {
+        let mut spans = vec![Span {
+            text: format!("{} ", symbols),
+            font: span_font(false, false, false),
+        }];
+        spans.extend(parse_inline(rest));
+        return spans;
+    }
+    parse_inline(text)
+}
+
+fn is_rule(line: &str) -> bool {
+    let mut chars = line.chars().filter(|c| !c.is_whitespace());
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    matches!(first, '-' | '*' | '_') && chars.clone().count() >= 2 && chars.all(|c| c == first)
+}
+
+/// Splits inline Markdown into styled spans: `**bold**`, `*italic*` and `` `code` ``.
+/// Links keep their text and wikilinks their label.
+fn parse_inline(text: &str) -> Vec<Span> {
+    static LINK_RE: OnceLock<Regex> = OnceLock::new();
+    let link_re = LINK_RE.get_or_init(|| Regex::new(r"!?\[([^\[\]]*)\]\([^)]*\)").unwrap());
+    let text = wikilink_re().replace_all(text, |caps: &regex::Captures| {
+        caps.get(2).unwrap_or_else(|| caps.get(1).unwrap()).as_str().trim().to_string()
+    });
+    let text = link_re.replace_all(&text, "$1");
+
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let (mut bold, mut italic, mut mono) = (false, false, false);
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let is_toggle = match c {
+            '`' => true,
+            '*' => !mono,
+            _ => false,
+        };
+        if !is_toggle {
+            current.push(c);
+            continue;
+        }
+
+        if !current.is_empty() {
+            spans.push(Span {
+                text: std::mem::take(&mut current),
+                font: span_font(bold, italic, mono),
+            });
+        }
+        if c == '`' {
+            mono = !mono;
+        } else if chars.peek() == Some(&'*') {
+            chars.next();
+            bold = !bold;
+        } else {
+            italic = !italic;
+        }
+    }
+    if !current.is_empty() {
+        spans.push(Span {
+            text: current,
+            font: span_font(bold, italic, mono),
+        });
+    }
+    spans
+}
+
+fn span_font(bold: bool, italic: bool, mono: bool) -> Font {
+    if mono {
+        Font::Mono
+    } else if bold {
+        Font::Bold
+    } else if italic {
+        Font::Italic
+    } else {
+        Font::Regular
+    }
+}
+
+/// Maps text to WinAnsiEncoding, the encoding of the standard fonts. Characters outside it
+/// become `?`; `unsupported_warning` names them so callers can tell the user.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars().map(|c| win_ansi(c).unwrap_or(b'?')).collect()
+}
+
+fn win_ansi(c: char) -> Option<u8> {
+    Some(match c {
+        ' '..='~' => c as u8,
+        '\t' => b' ',
+        '\u{a0}'..='\u{ff}' => c as u32 as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        '„' => 0x84,
+        '…' => 0x85,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '™' => 0x99,
+        _ => return None,
+    })
+}
+
+/// A warning naming the characters of `markdown` the standard fonts cannot show, which
+/// `render_markdown` prints as `?`; `None` when there are none.
+pub fn unsupported_warning(markdown: &str) -> Option<String> {
+    let mut unsupported: Vec<char> = Vec::new();
+    for c in markdown.chars() {
+        if c != '\n' && c != '\r' && win_ansi(c).is_none() && !unsupported.contains(&c) {
+            unsupported.push(c);
+        }
+    }
+    if unsupported.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "Warning: the PDF fonts cannot show {}; printed as `?`",
+        unsupported.iter().map(|c| format!("'{}'", c)).collect::<Vec<_>>().join(", ")
+    ))
+}
+
+struct Word {
+    text: Vec<u8>,
+    font: Font,
+    /// Whether whitespace separated this word from the previous one
+    spaced: bool,
+}
+
+struct Layout {
+    pages: Vec<Vec<u8>>,
+    content: Content,
+    y: f32,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Self {
+            pages: Vec::new(),
+            content: Content::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn new_page(&mut self) {
+        let content = std::mem::replace(&mut self.content, Content::new());
+        self.pages.push(finish_page(content, self.pages.len() + 1));
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Moves down by `height`, starting a new page if it does not fit.
+    fn advance(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+        self.y -= height;
+    }
+
+    fn space(&mut self, height: f32) {
+        if self.y < PAGE_HEIGHT - MARGIN {
+            self.y = (self.y - height).max(MARGIN);
+        }
+    }
+
+    fn rule(&mut self) {
+        self.advance(BODY_SIZE);
+        self.content
+            .set_stroke_gray(0.7)
+            .set_line_width(0.5)
+            .move_to(MARGIN, self.y)
+            .line_to(PAGE_WIDTH - MARGIN, self.y)
+            .stroke();
+    }
+
+    /// Word-wraps `spans` between `indent` and the right margin. The marker, if any, is drawn
+    /// in the space just left of `indent` on the first line; `gray` is the text colour.
+    fn paragraph(
+        &mut self,
+        spans: &[Span],
+        size: f32,
+        indent: f32,
+        marker: Option<&Marker>,
+        gray: f32,
+    ) {
+        let left = MARGIN + indent;
+        let max_width = PAGE_WIDTH - MARGIN - left;
+        let space = Font::Regular.width(b" ", size);
+        let line_height = size * LINE_SPACING;
+
+        let mut lines: Vec<Vec<(f32, &Word)>> = Vec::new();
+        let mut line = Vec::new();
+        let mut x = 0.0;
+        let words = split_words(spans, size, max_width);
+        for word in &words {
+            let gap = if word.spaced && !line.is_empty() { space } else { 0.0 };
+            let width = word.font.width(&word.text, size);
+            if !line.is_empty() && x + gap + width > max_width {
+                lines.push(std::mem::take(&mut line));
+                x = 0.0;
+            } else {
+                x += gap;
+            }
+            line.push((x, word));
+            x += width;
+        }
+        if !line.is_empty() || lines.is_empty() {
+            lines.push(line);
+        }
+
+        for (index, line) in lines.iter().enumerate() {
+            self.advance(line_height);
+            let baseline = self.y + (line_height - size) / 2.0;
+            if index == 0 {
+                match marker {
+                    Some(Marker::Glyph(glyph)) => self.glyph(*glyph, left - MARKER_WIDTH, baseline, size),
+                    Some(Marker::Text(number)) => {
+                        let text = encode(number);
+                        let x = left - Font::Regular.width(&text, size) - size * 0.4;
+                        self.text(x, baseline, Font::Regular, size, &text, 0.0);
+                    }
+                    None => {}
+                }
+            }
+            for (x, word) in line {
+                self.text(left + x, baseline, word.font, size, &word.text, gray);
+            }
+        }
+    }
+
+    /// Draws a preformatted line, keeping its spacing and wrapping only at the right margin.
+    fn code_line(&mut self, line: &str) {
+        let left = MARGIN + MARKER_WIDTH / 2.0;
+        let columns = ((PAGE_WIDTH - MARGIN - left) / Font::Mono.width(b" ", CODE_SIZE)) as usize;
+        let line_height = CODE_SIZE * LINE_SPACING;
+        let text = encode(line);
+        for chunk in text.chunks(columns.max(1)).chain(text.is_empty().then_some(&[][..])) {
+            self.advance(line_height);
+            let baseline = self.y + (line_height - CODE_SIZE) / 2.0;
+            self.text(left, baseline, Font::Mono, CODE_SIZE, chunk, 0.25);
+        }
+    }
+
+    fn text(&mut self, x: f32, y: f32, font: Font, size: f32, text: &[u8], gray: f32) {
+        self.content
+            .set_fill_gray(gray)
+            .begin_text()
+            .set_font(font.resource(), size)
+            .set_text_matrix([1.0, 0.0, 0.0, 1.0, x, y])
+            .show(Str(text))
+            .end_text();
+    }
+
+    /// Draws a signifier centred in a marker cell that starts at `x`.
+    fn glyph(&mut self, glyph: Glyph, x: f32, baseline: f32, size: f32) {
+        let cx = x + MARKER_WIDTH / 2.0 - 2.0;
+        let cy = baseline + size * 0.33;
+        let r = size * 0.22;
+        let content = &mut self.content;
+        content
+            .set_fill_gray(0.0)
+            .set_stroke_gray(0.0)
+            .set_line_width(size * 0.08);
+
+        match glyph {
+            Glyph::Task => {
+                circle(content, cx, cy, r * 0.55);
+                content.fill_nonzero();
+            }
+            Glyph::Bullet => {
+                circle(content, cx, cy, r * 0.4);
+                content.fill_nonzero();
+            }
+            Glyph::Event => {
+                circle(content, cx, cy, r * 0.8);
+                content.stroke();
+            }
+            Glyph::Done => {
+                content
+                    .move_to(cx - r, cy - r)
+                    .line_to(cx + r, cy + r)
+                    .move_to(cx - r, cy + r)
+                    .line_to(cx + r, cy - r)
+                    .stroke();
+            }
+            Glyph::Migrated => {
+                content
+                    .move_to(cx - r * 0.6, cy + r)
+                    .line_to(cx + r * 0.6, cy)
+                    .line_to(cx - r * 0.6, cy - r)
+                    .stroke();
+            }
+            Glyph::Scheduled => {
+                content
+                    .move_to(cx + r * 0.6, cy + r)
+                    .line_to(cx - r * 0.6, cy)
+                    .line_to(cx + r * 0.6, cy - r)
+                    .stroke();
+            }
+            Glyph::Note => {
+                content.move_to(cx - r, cy).line_to(cx + r, cy).stroke();
+            }
+            Glyph::Cancelled => {
+                circle(content, cx, cy, r * 0.55);
+                content.fill_nonzero();
+                content.move_to(cx - r, cy).line_to(cx + r, cy).stroke();
+            }
+            Glyph::InProgress => {
+                content
+                    .move_to(cx - r, cy - r)
+                    .line_to(cx + r, cy + r)
+                    .stroke();
+            }
+        }
+    }
+
+    fn finish(mut self, title: &str) -> Vec<u8> {
+        self.new_page();
+
+        let catalog_id = Ref::new(1);
+        let tree_id = Ref::new(2);
+        let info_id = Ref::new(3);
+        let font_ids: Vec<Ref> = (0..Font::ALL.len() as i32).map(|i| Ref::new(4 + i)).collect();
+        let first_page = 4 + Font::ALL.len() as i32;
+        let page_ids: Vec<Ref> = (0..self.pages.len() as i32)
+            .map(|i| Ref::new(first_page + 2 * i))
+            .collect();
+
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(tree_id);
+        pdf.pages(tree_id)
+            .kids(page_ids.iter().copied())
+            .count(page_ids.len() as i32);
+        pdf.document_info(info_id)
+            .title(TextStr(title))
+            .creator(TextStr("notes"));
+        for (font, id) in Font::ALL.iter().zip(&font_ids) {
+            pdf.type1_font(*id)
+                .base_font(font.base_font())
+                .encoding_predefined(Name(b"WinAnsiEncoding"));
+        }
+
+        for (page, (id, content)) in page_ids.iter().zip(&self.pages).enumerate() {
+            let content_id = Ref::new(first_page + 2 * page as i32 + 1);
+            {
+                let mut page = pdf.page(*id);
+                page.parent(tree_id)
+                    .media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+                    .contents(content_id);
+                let mut resources = page.resources();
+                let mut fonts = resources.fonts();
+                for (font, font_id) in Font::ALL.iter().zip(&font_ids) {
+                    fonts.pair(font.resource(), *font_id);
+                }
+            }
+            pdf.stream(content_id, content);
+        }
+
+        pdf.finish()
+    }
+}
+
+/// Ends a page's content with its page number.
+fn finish_page(mut content: Content, number: usize) -> Vec<u8> {
+    let text = encode(&number.to_string());
+    let size = 9.0;
+    let x = (PAGE_WIDTH - Font::Regular.width(&text, size)) / 2.0;
+    content
+        .set_fill_gray(0.5)
+        .begin_text()
+        .set_font(Font::Regular.resource(), size)
+        .set_text_matrix([1.0, 0.0, 0.0, 1.0, x, MARGIN / 2.0])
+        .show(Str(&text))
+        .end_text();
+    content.finish()
+}
+
+/// Splits spans into words; words wider than a line are broken up.
+fn split_words(spans: &[Span], size: f32, max_width: f32) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut spaced = false;
+    for span in spans {
+        let mut rest = span.text.as_str();
+        loop {
+            let trimmed = rest.trim_start();
+            spaced |= trimmed.len() != rest.len();
+            rest = trimmed;
+            if rest.is_empty() {
+                break;
+            }
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let mut text = encode(&rest[..end]);
+            rest = &rest[end..];
+
+            while span.font.width(&text, size) > max_width && text.len() > 1 {
+                let mut split = text.len() - 1;
+                while split > 1 && span.font.width(&text[..split], size) > max_width {
+                    split -= 1;
+                }
+                let tail = text.split_off(split);
+                words.push(Word {
+                    text,
+                    font: span.font,
+                    spaced,
+                });
+                text = tail;
+                spaced = true;
+            }
+            words.push(Word {
+                text,
+                font: span.font,
+                spaced,
+            });
+            spaced = false;
+        }
+    }
+    words
+}
+
+/// Appends a circle path made of four Bézier arcs.
+fn circle(content: &mut Content, cx: f32, cy: f32, r: f32) {
+    let k = r * 0.5523;
+    content
+        .move_to(cx + r, cy)
+        .cubic_to(cx + r, cy + k, cx + k, cy + r, cx, cy + r)
+        .cubic_to(cx - k, cy + r, cx - r, cy + k, cx - r, cy)
+        .cubic_to(cx - r, cy - k, cx - k, cy - r, cx, cy - r)
+        .cubic_to(cx + k, cy - r, cx + r, cy - k, cx + r, cy)
+        .close_path();
+}
@@ -0,0 +1,263 @@
+use crate::app::{NoteHistory, NotesApp};
+use crate::cli::StatsPeriod;
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Months, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+/// Number of rows shown in the ranked tables
+const TOP_NOTES: usize = 10;
+
+#[derive(serde::Serialize)]
+struct NotebookStats {
+    generated_at: DateTime<Utc>,
+    since: Option<DateTime<Utc>>,
+    notes: usize,
+    versions: usize,
+    storage_bytes: u64,
+    per_note: Vec<NoteStats>,
+    activity: Vec<ActivityBucket>,
+    dormant_months: u32,
+    dormant: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+struct NoteStats {
+    title: String,
+    slug: String,
+    words: usize,
+    bytes: usize,
+    versions: usize,
+    edits: usize,
+    storage_bytes: u64,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Default, serde::Serialize)]
+struct ActivityBucket {
+    period: String,
+    edits: usize,
+    words_added: usize,
+    words_removed: usize,
+}
+
+pub fn print_stats(
+    app: &NotesApp,
+    since: Option<DateTime<Utc>>,
+    period: StatsPeriod,
+    dormant_months: u32,
+    json: bool,
+) -> Result<()> {
+    let stats = collect_stats(app, since, period, dormant_months)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        print_table(&stats);
+    }
+    Ok(())
+}
+
+fn collect_stats(
+    app: &NotesApp,
+    since: Option<DateTime<Utc>>,
+    period: StatsPeriod,
+    dormant_months: u32,
+) -> Result<NotebookStats> {
+    let now = Utc::now();
+    let dormant_cutoff = now
+        .checked_sub_months(Months::new(dormant_months))
+        .unwrap_or(now);
+
+    let mut per_note = Vec::new();
+    let mut activity: BTreeMap<String, ActivityBucket> = BTreeMap::new();
+    let mut dormant = Vec::new();
+    let mut total_versions = 0;
+
+    for note in app.note_histories() {
+        total_versions += note.versions.len();
+        let mut previous = HashMap::new();
+        let mut edits = 0;
+
+        for version in &note.versions {
+            // Encrypted versions count as edits but contribute no words.
+            let content = match note.encryption {
+                Some(_) => String::new(),
+                None => fs::read_to_string(&version.path).unwrap_or_default(),
+            };
+            let words = word_counts(&content);
+            if since.is_none_or(|since| version.created_at >= since) {
+                let (added, removed) = word_delta(&previous, &words);
+                let bucket = activity
+                    .entry(period_key(version.created_at, period))
+                    .or_default();
+                bucket.edits += 1;
+                bucket.words_added += added;
+                bucket.words_removed += removed;
+                edits += 1;
+            }
+            previous = words;
+        }
+
+        if note.updated_at < dormant_cutoff {
+            dormant.push(note.title.clone());
+        }
+
+        per_note.push(note_stats(app, &note, edits));
+    }
+
+    let activity = activity
+        .into_iter()
+        .map(|(period, bucket)| ActivityBucket { period, ..bucket })
+        .collect();
+
+    Ok(NotebookStats {
+        generated_at: now,
+        since,
+        notes: per_note.len(),
+        versions: total_versions,
+        storage_bytes: per_note.iter().map(|note| note.storage_bytes).sum(),
+        per_note,
+        activity,
+        dormant_months,
+        dormant,
+    })
+}
+
+fn note_stats(app: &NotesApp, note: &NoteHistory, edits: usize) -> NoteStats {
+    let current = note
+        .versions
+        .iter()
+        .find(|version| version.version == note.current_version)
+        .or(note.versions.last())
+        .filter(|_| note.encryption.is_none())
+        .and_then(|version| fs::read_to_string(&version.path).ok())
+        .unwrap_or_default();
+
+    NoteStats {
+        title: note.title.clone(),
+        slug: note.slug.clone(),
+        words: current.split_whitespace().count(),
+        bytes: current.len(),
+        versions: note.versions.len(),
+        edits,
+        storage_bytes: dir_size(&app.paths().versions.join(&note.slug)),
+        updated_at: note.updated_at,
+    }
+}
+
+fn word_counts(content: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for word in content.split_whitespace() {
+        *counts.entry(word.to_string()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Words added and removed between two versions, compared as multisets.
+fn word_delta(old: &HashMap<String, usize>, new: &HashMap<String, usize>) -> (usize, usize) {
+    let added = new
+        .iter()
+        .map(|(word, &count)| count.saturating_sub(old.get(word).copied().unwrap_or(0)))
+        .sum();
+    let removed = old
+        .iter()
+        .map(|(word, &count)| count.saturating_sub(new.get(word).copied().unwrap_or(0)))
+        .sum();
+    (added, removed)
+}
+
+fn period_key(at: DateTime<Utc>, period: StatsPeriod) -> String {
+    match period {
+        StatsPeriod::Day => at.format("%Y-%m-%d").to_string(),
+        StatsPeriod::Week => {
+            let week = at.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        }
+    }
+}
+
+fn dir_size(path: &std::path::Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+fn print_table(stats: &NotebookStats) {
+    println!(
+        "Notes: {}  Versions: {}  Storage: {}",
+        stats.notes,
+        stats.versions,
+        format_bytes(stats.storage_bytes)
+    );
+    if let Some(since) = stats.since {
+        println!("Edits counted since {}", since.to_rfc3339());
+    }
+
+    let mut by_size: Vec<&NoteStats> = stats.per_note.iter().collect();
+    by_size.sort_by_key(|note| std::cmp::Reverse(note.bytes));
+    println!();
+    println!("Largest notes");
+    println!("  {:<30} {:>8} {:>10}", "title", "words", "bytes");
+    for note in by_size.iter().take(TOP_NOTES) {
+        println!("  {:<30} {:>8} {:>10}", note.title, note.words, note.bytes);
+    }
+
+    let mut by_edits: Vec<&NoteStats> = stats.per_note.iter().collect();
+    by_edits.sort_by_key(|note| std::cmp::Reverse(note.edits));
+    println!();
+    println!("Most edited");
+    println!("  {:<30} {:>8} {:>10}", "title", "edits", "versions");
+    for note in by_edits.iter().take(TOP_NOTES).filter(|note| note.edits > 0) {
+        println!("  {:<30} {:>8} {:>10}", note.title, note.edits, note.versions);
+    }
+
+    println!();
+    println!("Activity");
+    println!("  {:<12} {:>8} {:>10} {:>10}", "period", "edits", "added", "removed");
+    for bucket in &stats.activity {
+        println!(
+            "  {:<12} {:>8} {:>10} {:>10}",
+            bucket.period,
+            bucket.edits,
+            format!("+{}", bucket.words_added),
+            format!("-{}", bucket.words_removed)
+        );
+    }
+
+    println!();
+    println!("Dormant (no edits in {} months)", stats.dormant_months);
+    if stats.dormant.is_empty() {
+        println!("  none");
+    }
+    for title in &stats.dormant {
+        println!("  {}", title);
+    }
+
+    let mut by_storage: Vec<&NoteStats> = stats.per_note.iter().collect();
+    by_storage.sort_by_key(|note| std::cmp::Reverse(note.storage_bytes));
+    println!();
+    println!("Storage per note");
+    for note in by_storage {
+        println!(
+            "  {:<30} {:>10}",
+            note.title,
+            format_bytes(note.storage_bytes)
+        );
+    }
+}
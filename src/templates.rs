@@ -0,0 +1,70 @@
+use crate::paths::DataPaths;
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, NaiveDate};
+use regex::{Captures, Regex};
+use std::env;
+use std::fs;
+use std::sync::OnceLock;
+
+/// Names of the templates under `templates/`, without the `.md` extension.
+pub fn list_templates(paths: &DataPaths) -> Result<Vec<String>> {
+    let Ok(entries) = fs::read_dir(&paths.templates) else {
+        return Ok(Vec::new());
+    };
+    let mut names = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "md")
+            && let Some(stem) = path.file_stem()
+        {
+            names.push(stem.to_string_lossy().into_owned());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Reads `templates/<name>.md` and fills in `{{title}}`, `{{date}}`, `{{week}}`, `{{month}}`
+/// and `{{user}}` in one pass, so placeholders inside the title stay as written. Other
+/// `{{...}}` text is left alone. `name` must be a plain file name without a directory.
+pub(crate) fn expand_template(
+    paths: &DataPaths,
+    name: &str,
+    title: &str,
+    date: NaiveDate,
+) -> Result<String> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        bail!("Invalid template name: {}", name);
+    }
+    let path = paths.template_file(name);
+    if !path.exists() {
+        let available = list_templates(paths)?;
+        if available.is_empty() {
+            bail!(
+                "Template not found: {}. Add templates as Markdown files in {}",
+                name,
+                paths.templates.display()
+            );
+        }
+        bail!("Template not found: {}. Available: {}", name, available.join(", "));
+    }
+    let template =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let week = date.iso_week();
+    let user = env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_default();
+    static PLACEHOLDER_RE: OnceLock<Regex> = OnceLock::new();
+    let re = PLACEHOLDER_RE
+        .get_or_init(|| Regex::new(r"\{\{(title|date|week|month|user)\}\}").unwrap());
+    Ok(re
+        .replace_all(&template, |caps: &Captures| match &caps[1] {
+            "title" => title.to_string(),
+            "date" => date.format("%Y-%m-%d").to_string(),
+            "week" => format!("{}-W{:02}", week.year(), week.week()),
+            "month" => format!("{}-{:02}", date.year(), date.month()),
+            _ => user.clone(),
+        })
+        .into_owned())
+}
@@ -1,4 +1,4 @@
-use crate::app::{NoteSummary, NotesApp};
+use crate::app::{NoteSummary, NotesApp, WriteOutcome};
 use anyhow::{anyhow, Result};
 use iced::widget::{
     button, column, container, row, scrollable, text, text_editor, text_input, Column,
@@ -111,10 +111,10 @@ impl Application for NotesUi {
         };
 
         let first_slug = ui.summaries.first().map(|note| note.slug.clone());
-        if let Some(slug) = first_slug {
-            if let Err(err) = ui.load_note(&slug) {
-                ui.error_message = Some(err.to_string());
-            }
+        if let Some(slug) = first_slug
+            && let Err(err) = ui.load_note(&slug)
+        {
+            ui.error_message = Some(err.to_string());
         }
 
         (ui, Command::none())
@@ -218,7 +218,7 @@ impl NotesUi {
             Some(self.new_title.trim().to_string())
         };
         let app = self.app_mut()?;
-        let (slug, _) = app.create_note_with_slug(title)?;
+        let (slug, _) = app.create_note_with_slug(title, None)?;
         app.save()?;
         self.new_title.clear();
         self.refresh_summaries();
@@ -237,11 +237,19 @@ impl NotesUi {
             return Ok(false);
         }
         let app = self.app_mut()?;
-        app.write_working_content(&slug, &content)?;
+        let outcome = app.write_working_content(&slug, &content)?;
         let _ = app.snapshot_if_changed(&slug)?;
         app.save()?;
         self.loaded_text = content;
-        self.status_message = Some(label.to_string());
+        self.status_message = Some(match outcome {
+            WriteOutcome::Written => label.to_string(),
+            WriteOutcome::Conflict {
+                external_version,
+                local_version,
+            } => format!(
+                "File changed on disk: external edit kept as v{external_version}, yours saved as v{local_version}"
+            ),
+        });
         self.error_message = None;
         self.refresh_summaries();
         Ok(true)
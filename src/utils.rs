@@ -1,16 +1,18 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
 use sha2::{Digest, Sha256};
 use std::path::PathBuf;
 use std::process::Stdio;
+use std::sync::OnceLock;
 
 pub(crate) fn slugify(input: &str) -> String {
     let mut slug = String::new();
     for c in input.chars() {
         if c.is_ascii_alphanumeric() {
             slug.push(c.to_ascii_lowercase());
-        } else if c.is_whitespace() || c == '-' || c == '_' {
-            if !slug.ends_with('-') {
-                slug.push('-');
-            }
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
         }
     }
 
@@ -31,6 +33,96 @@ pub(crate) fn hash_bytes(bytes: &[u8]) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        bail!("Invalid hex string");
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| anyhow!("Invalid hex string")))
+        .collect()
+}
+
+/// Parses a point in time as RFC 3339, or as local `YYYY-MM-DD HH:MM[:SS]`.
+/// A bare `YYYY-MM-DD` means the end of that day.
+pub(crate) fn parse_timestamp(input: &str) -> Result<DateTime<Utc>> {
+    let input = input.trim();
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(input) {
+        return Ok(parsed.with_timezone(&Utc));
+    }
+
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(input, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(23, 59, 59))
+        });
+
+    let Some(naive) = naive else {
+        bail!(
+            "Invalid time: {}. Use RFC 3339, \"YYYY-MM-DD HH:MM[:SS]\" or YYYY-MM-DD",
+            input
+        );
+    };
+
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|local| local.with_timezone(&Utc))
+        .ok_or_else(|| anyhow!("Time does not exist in the local timezone: {}", input))
+}
+
+/// Reads a boolean environment variable: `1`, `true`, `yes` or `on` enable it, and `0`,
+/// `false`, `no`, `off`, an empty value or no variable at all leave it off.
+pub(crate) fn env_flag(name: &str) -> Result<bool> {
+    let Ok(value) = std::env::var(name) else {
+        return Ok(false);
+    };
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "" | "0" | "false" | "no" | "off" => Ok(false),
+        _ => bail!("Invalid {}: {}. Use true or false", name, value),
+    }
+}
+
+/// Matches `[[Target]]` and `[[Target|label]]`; group 1 is the target, group 2 the label.
+pub(crate) fn wikilink_re() -> &'static Regex {
+    static WIKILINK_RE: OnceLock<Regex> = OnceLock::new();
+    WIKILINK_RE.get_or_init(|| Regex::new(r"\[\[([^\[\]|]+)(?:\|([^\[\]]+))?\]\]").unwrap())
+}
+
+/// Attachment file names linked from `content`, as `attachments/<sha256>[.ext]`.
+pub(crate) fn attachment_refs(content: &[u8]) -> Vec<String> {
+    static ATTACHMENT_RE: OnceLock<Regex> = OnceLock::new();
+    let re = ATTACHMENT_RE
+        .get_or_init(|| Regex::new(r"attachments/([0-9a-f]{64}(?:\.[A-Za-z0-9]+)?)").unwrap());
+    let mut names: Vec<String> = re
+        .captures_iter(&String::from_utf8_lossy(content))
+        .map(|caps| caps[1].to_string())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Targets of the `[[...]]` links in `content`, trimmed, in order of first appearance.
+pub(crate) fn wikilink_targets(content: &[u8]) -> Vec<String> {
+    let mut targets: Vec<String> = Vec::new();
+    for caps in wikilink_re().captures_iter(&String::from_utf8_lossy(content)) {
+        let target = caps[1].trim().to_string();
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    targets
+}
+
 pub fn launch_subl_if_installed(path: &PathBuf) {
     if !is_subl_available() {
         return;
@@ -1,4 +1,5 @@
 use assert_cmd::Command;
+use predicates::prelude::*;
 use predicates::str::contains;
 use std::fs;
 use std::path::Path;
@@ -133,3 +134,1685 @@ fn search_finds_notes_by_content() {
         .success()
         .stdout(contains("Ideas"));
 }
+
+#[test]
+fn at_shows_history_including_deleted_notes() {
+    let home = setup_home();
+    let output = notes_cmd(&home)
+        .args(["new", "Runbook"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    let working = Path::new(&path);
+
+    fs::write(working, "step one").expect("write working file");
+    notes_cmd(&home).args(["open", "Runbook"]).assert().success();
+    let at = chrono::Utc::now().to_rfc3339();
+
+    fs::write(working, "step two").expect("write working file");
+    notes_cmd(&home).args(["open", "Runbook"]).assert().success();
+    notes_cmd(&home).args(["new", "Later"]).assert().success();
+    notes_cmd(&home).args(["delete", "runbook"]).assert().success();
+
+    notes_cmd(&home)
+        .args(["at", &at, "show", "Runbook"])
+        .assert()
+        .success()
+        .stdout("step one");
+    notes_cmd(&home)
+        .args(["at", &at, "list"])
+        .assert()
+        .success()
+        .stdout(contains("Runbook").and(contains("[deleted]")))
+        .stdout(contains("Later").not());
+}
+
+#[test]
+fn stats_reports_edits_and_word_changes_as_json() {
+    let home = setup_home();
+    let output = notes_cmd(&home)
+        .args(["new", "Journal"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    let working = Path::new(&path);
+
+    fs::write(working, "one two three").expect("write working file");
+    notes_cmd(&home).args(["open", "Journal"]).assert().success();
+    fs::write(working, "one two four five").expect("write working file");
+    notes_cmd(&home).args(["open", "Journal"]).assert().success();
+
+    let output = notes_cmd(&home)
+        .args(["stats", "--json"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stats: serde_json::Value = serde_json::from_slice(&output).expect("stats json");
+    assert_eq!(stats["versions"], 3);
+    assert_eq!(stats["per_note"][0]["words"], 4);
+    let added: u64 = stats["activity"]
+        .as_array()
+        .expect("activity")
+        .iter()
+        .map(|bucket| bucket["words_added"].as_u64().unwrap_or(0))
+        .sum();
+    assert_eq!(added, 5, "three words in v2, two more in v3");
+}
+
+#[test]
+fn read_only_commands_do_not_snapshot_unless_asked() {
+    let home = setup_home();
+    let output = notes_cmd(&home)
+        .args(["new", "Plan"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    let index = home.path().join("index.json");
+    let before = read_to_string(&index);
+
+    fs::write(Path::new(&path), "draft").expect("write working file");
+    notes_cmd(&home).arg("list").assert().success();
+    notes_cmd(&home)
+        .args(["search", "draft"])
+        .env("NOTES_SNAPSHOT_ON_READ", "false")
+        .assert()
+        .success();
+    assert_eq!(before, read_to_string(&index), "index.json should be untouched");
+    notes_cmd(&home)
+        .arg("list")
+        .env("NOTES_SNAPSHOT_ON_READ", "sometimes")
+        .assert()
+        .failure()
+        .stderr(contains("Invalid NOTES_SNAPSHOT_ON_READ"));
+
+    notes_cmd(&home)
+        .args(["snapshot", "Plan", "-m", "first draft"])
+        .assert()
+        .success()
+        .stdout(contains("v2"));
+    notes_cmd(&home)
+        .args(["versions", "Plan"])
+        .assert()
+        .success()
+        .stdout(contains("first draft"));
+}
+
+#[test]
+fn undo_and_redo_delete_restore_history() {
+    let home = setup_home();
+    let output = notes_cmd(&home)
+        .args(["new", "Draft"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    fs::write(Path::new(&path), "keep me").expect("write working file");
+    notes_cmd(&home).args(["snapshot", "Draft"]).assert().success();
+
+    notes_cmd(&home).args(["delete", "draft"]).assert().success();
+    assert!(!Path::new(&path).exists());
+
+    notes_cmd(&home)
+        .arg("undo")
+        .assert()
+        .success()
+        .stdout(contains("Delete draft"));
+    assert_eq!(read_to_string(Path::new(&path)), "keep me");
+    notes_cmd(&home)
+        .args(["versions", "Draft"])
+        .assert()
+        .success()
+        .stdout(contains("v2"));
+
+    notes_cmd(&home).arg("redo").assert().success();
+    notes_cmd(&home).args(["open", "Draft"]).assert().failure();
+    notes_cmd(&home)
+        .arg("log")
+        .assert()
+        .success()
+        .stdout(contains("delete"));
+}
+
+#[test]
+fn undo_delete_restores_binary_working_copy() {
+    let home = setup_home();
+    let output = notes_cmd(&home)
+        .args(["new", "Blob"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    let bytes = [b'a', b'b', 0xff, 0xfe];
+    fs::write(Path::new(&path), bytes).expect("write working file");
+
+    notes_cmd(&home).args(["delete", "blob"]).assert().success();
+    notes_cmd(&home).arg("undo").assert().success();
+    assert_eq!(fs::read(Path::new(&path)).expect("read working file"), bytes);
+}
+
+#[test]
+fn undo_bullet_complete_restores_task() {
+    let home = setup_home();
+    notes_cmd(&home)
+        .args(["bullet", "Write report"])
+        .assert()
+        .success();
+    let ids = notes_cmd(&home)
+        .args(["bullet", "ids"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let ids = String::from_utf8_lossy(&ids);
+    let id = ids.split('\t').next().expect("entry id").to_string();
+
+    notes_cmd(&home)
+        .args(["bullet", "complete", &id])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "list"])
+        .assert()
+        .success()
+        .stdout(contains("[x] Write report"));
+
+    notes_cmd(&home).arg("undo").assert().success();
+    notes_cmd(&home)
+        .args(["bullet", "list"])
+        .assert()
+        .success()
+        .stdout(contains("[ ] Write report"));
+
+    notes_cmd(&home)
+        .args(["bullet", "interactive"])
+        .write_stdin(format!("t Call Sam\nx {}\nq\n", id))
+        .assert()
+        .success()
+        .stdout(contains("Marked complete"));
+    notes_cmd(&home).arg("undo").assert().success();
+    notes_cmd(&home)
+        .args(["bullet", "list"])
+        .assert()
+        .success()
+        .stdout(contains("[ ] Write report"))
+        .stdout(contains("[ ] Call Sam"));
+}
+
+#[test]
+fn export_html_renders_pages_links_and_search_index() {
+    let home = setup_home();
+    let output = notes_cmd(&home)
+        .args(["new", "Runbook"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    notes_cmd(&home).args(["new", "On Call"]).assert().success();
+    fs::write(Path::new(&path), "# Steps\nSee [[On Call]] and [[Missing]].").expect("write");
+    notes_cmd(&home).args(["snapshot", "Runbook"]).assert().success();
+
+    let site = home.path().join("site");
+    let site_arg = site.to_string_lossy().to_string();
+    notes_cmd(&home)
+        .args(["export", "html", &site_arg])
+        .assert()
+        .success()
+        .stdout(contains("Exported 2 note(s)"));
+
+    let page = read_to_string(&site.join("notes/runbook.html"));
+    assert!(page.contains("<h1>Steps</h1>"));
+    assert!(page.contains("<a href=\"on-call.html\">On Call</a>"));
+    assert!(page.contains("class=\"broken-link\">Missing"));
+    assert!(site.join("notes/runbook/history.html").exists());
+    assert!(site.join("notes/runbook/v2.html").exists());
+    assert!(read_to_string(&site.join("search.json")).contains("\"slug\": \"runbook\""));
+
+    let first = read_to_string(&site.join("index.html"));
+    notes_cmd(&home)
+        .args(["export", "html", &site_arg])
+        .assert()
+        .success();
+    assert_eq!(first, read_to_string(&site.join("index.html")));
+}
+
+#[test]
+fn archive_round_trip_merges_into_existing_store() {
+    let source = setup_home();
+    let output = notes_cmd(&source)
+        .args(["new", "Ideas"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    fs::write(Path::new(&path), "archived idea").expect("write working file");
+    notes_cmd(&source).args(["snapshot", "Ideas"]).assert().success();
+    notes_cmd(&source)
+        .args(["bullet", "Carry over"])
+        .assert()
+        .success();
+
+    let archive = source.path().join("notes.tar.zst");
+    let archive_arg = archive.to_string_lossy().to_string();
+    notes_cmd(&source)
+        .args(["export", "--archive", &archive_arg])
+        .assert()
+        .success()
+        .stdout(contains("Archived 1 note(s)"));
+
+    let target = setup_home();
+    notes_cmd(&target).args(["new", "Ideas"]).assert().success();
+    notes_cmd(&target)
+        .args(["import", &archive_arg])
+        .assert()
+        .success()
+        .stdout(contains("Imported ideas as ideas-2"));
+
+    notes_cmd(&target)
+        .args(["versions", "ideas-2"])
+        .assert()
+        .success()
+        .stdout(contains("v2"));
+    assert_eq!(
+        read_to_string(&target.path().join("files/ideas-2.md")),
+        "archived idea"
+    );
+    notes_cmd(&target)
+        .args(["bullet", "list"])
+        .assert()
+        .success()
+        .stdout(contains("Carry over"));
+
+    notes_cmd(&target)
+        .args(["import", &archive_arg])
+        .assert()
+        .success()
+        .stdout(contains("Skipped ideas"));
+}
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .expect("run git");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+#[test]
+fn import_dir_titles_notes_and_ingests_git_history() {
+    let home = setup_home();
+    let vault = setup_home();
+    let root = vault.path();
+    fs::create_dir_all(root.join("projects")).expect("create folder");
+    fs::create_dir_all(root.join(".obsidian")).expect("create folder");
+    fs::write(root.join(".obsidian/app.md"), "ignored").expect("write");
+    fs::write(
+        root.join("projects/plan.md"),
+        "---\ntitle: Launch Plan\ntags: [work]\n---\n# Ignored heading\n",
+    )
+    .expect("write");
+    fs::write(root.join("Daily Log.md"), "no heading here").expect("write");
+
+    git(root, &["init", "-q"]);
+    fs::write(root.join("recipes.md"), "# Recipes\nsoup").expect("write");
+    git(root, &["add", "recipes.md"]);
+    git(root, &["commit", "-q", "-m", "First recipes"]);
+    fs::write(root.join("recipes.md"), "# Recipes\nsoup\nbread").expect("write");
+    git(root, &["commit", "-q", "-am", "Add bread"]);
+    fs::write(root.join("recipes.md"), "# Recipes\nsoup\nbread\ncake").expect("write");
+
+    let root_arg = root.to_string_lossy().to_string();
+    notes_cmd(&home)
+        .args(["import-dir", &root_arg, "--git-history"])
+        .assert()
+        .success()
+        .stdout(contains("Imported 3 note(s)"))
+        .stdout(contains("Daily Log.md -> daily-log"))
+        .stdout(contains("projects/plan.md -> launch-plan"))
+        .stdout(contains("recipes.md -> recipes").not());
+
+    notes_cmd(&home)
+        .args(["versions", "Recipes"])
+        .assert()
+        .success()
+        .stdout(contains("v1").and(contains("First recipes")))
+        .stdout(contains("v2").and(contains("Add bread")))
+        .stdout(contains("v3"));
+    assert_eq!(
+        read_to_string(&home.path().join("files/recipes.md")),
+        "# Recipes\nsoup\nbread\ncake"
+    );
+    notes_cmd(&home)
+        .args(["open", "Launch Plan"])
+        .assert()
+        .success();
+
+    notes_cmd(&home)
+        .args(["import-dir", &root_arg])
+        .assert()
+        .success()
+        .stdout(contains("Skipped Daily Log.md"));
+}
+
+fn git_output(dir: &Path, args: &[&str]) -> String {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("run git");
+    assert!(output.status.success(), "git {:?} failed", args);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn git_export_is_incremental_and_imports_back() {
+    let home = setup_home();
+    let output = notes_cmd(&home)
+        .args(["new", "Ideas"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    fs::write(Path::new(&path), "first").expect("write working file");
+    notes_cmd(&home)
+        .args(["snapshot", "Ideas", "-m", "draft"])
+        .assert()
+        .success();
+
+    let repo = setup_home();
+    let repo_arg = repo.path().join("mirror").to_string_lossy().to_string();
+    notes_cmd(&home)
+        .args(["export", "git", &repo_arg])
+        .assert()
+        .success()
+        .stdout(contains("Wrote 2 commit(s)"));
+
+    fs::write(Path::new(&path), "second").expect("write working file");
+    notes_cmd(&home).args(["snapshot", "Ideas"]).assert().success();
+    notes_cmd(&home)
+        .args(["export", "git", &repo_arg])
+        .assert()
+        .success()
+        .stdout(contains("Wrote 1 commit(s)"));
+
+    // A failed export leaves the branch alone, so the next run can finish it.
+    notes_cmd(&home).args(["new", "Plans"]).assert().success();
+    let blocker = Path::new(&repo_arg).join("plans.md");
+    fs::create_dir(&blocker).expect("create blocking directory");
+    notes_cmd(&home)
+        .args(["export", "git", &repo_arg])
+        .assert()
+        .failure();
+    fs::remove_dir(&blocker).expect("remove blocking directory");
+    notes_cmd(&home)
+        .args(["export", "git", &repo_arg])
+        .assert()
+        .success()
+        .stdout(contains("Wrote 1 commit(s)"));
+    notes_cmd(&home).args(["delete", "plans"]).assert().success();
+    notes_cmd(&home)
+        .args(["export", "git", &repo_arg])
+        .assert()
+        .success();
+
+    let mirror = Path::new(&repo_arg);
+    let log = git_output(mirror, &["log", "--format=%s"]);
+    assert_eq!(log, "Delete Plans\nPlans v1\nIdeas v3\nIdeas v2\nIdeas v1\n");
+    assert_eq!(git_output(mirror, &["status", "--porcelain"]), "");
+    assert_eq!(read_to_string(&mirror.join("ideas.md")), "second");
+    git_output(mirror, &["fsck", "--strict"]);
+
+    // A note brought back continues its history, and a rename is a single commit.
+    notes_cmd(&home).arg("undo").assert().success();
+    notes_cmd(&home)
+        .args(["rename", "Ideas", "Big Ideas"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["export", "git", &repo_arg])
+        .assert()
+        .success()
+        .stdout(contains("Wrote 2 commit(s)"));
+    let log = git_output(mirror, &["log", "--format=%s", "-3"]);
+    assert_eq!(log, "Rename Ideas to Big Ideas\nRestore Plans\nDelete Plans\n");
+    let dates = git_output(mirror, &["log", "--format=%ct", "-3"]);
+    let dates: Vec<&str> = dates.lines().collect();
+    assert!(dates[0] >= dates[1] && dates[1] == dates[2]);
+    assert!(read_to_string(&mirror.join(".notes.json")).contains("\"Big Ideas\""));
+    assert_eq!(git_output(mirror, &["status", "--porcelain"]), "");
+    git_output(mirror, &["fsck", "--strict"]);
+
+    let target = setup_home();
+    notes_cmd(&target)
+        .args(["import", "git", &repo_arg])
+        .assert()
+        .success()
+        .stdout(contains("Imported ideas"));
+    notes_cmd(&target)
+        .args(["versions", "Big Ideas"])
+        .assert()
+        .success()
+        .stdout(contains("v2").and(contains("- draft")))
+        .stdout(contains("v3"));
+}
+
+#[test]
+fn export_pdf_renders_note_and_journal_week() {
+    let home = setup_home();
+    let output = notes_cmd(&home)
+        .args(["new", "Meeting"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    fs::write(Path::new(&path), "# Sync\n\n- [ ] Draft plan\n- [x] Book room\n").expect("write");
+
+    let note_pdf = home.path().join("meeting.pdf");
+    notes_cmd(&home)
+        .args(["export", "pdf", "Meeting", "-o"])
+        .arg(&note_pdf)
+        .assert()
+        .success()
+        .stdout(contains("Exported Meeting"));
+    let bytes = fs::read(&note_pdf).expect("read pdf");
+    let text = String::from_utf8_lossy(&bytes);
+    assert!(text.starts_with("%PDF-"));
+    assert!(text.contains("(Draft) Tj") && text.contains("(room) Tj"));
+    assert!(!text.contains("[x]"), "task markers are drawn, not printed");
+
+    fs::write(Path::new(&path), "# Café → 東京\n").expect("write");
+    notes_cmd(&home)
+        .args(["export", "pdf", "Meeting", "-o"])
+        .arg(&note_pdf)
+        .assert()
+        .success()
+        .stderr(contains("cannot show '→', '東', '京'").and(contains("é").not()));
+
+    notes_cmd(&home)
+        .args(["bullet", "Review budget"])
+        .assert()
+        .success();
+    let week_pdf = home.path().join("week.pdf");
+    notes_cmd(&home)
+        .args(["bullet", "export", "pdf", "--week", "-o"])
+        .arg(&week_pdf)
+        .assert()
+        .success()
+        .stdout(contains("Weekly Review"));
+    let bytes = fs::read(&week_pdf).expect("read pdf");
+    let text = String::from_utf8_lossy(&bytes);
+    assert!(text.contains("(budget) Tj"));
+    assert!(!text.contains("id:"), "entry ids are left out");
+
+    notes_cmd(&home)
+        .args(["bullet", "export", "pdf"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn encrypted_notes_are_sealed_at_rest_and_searchable_only_when_unlocked() {
+    let home = setup_home();
+    let unlocked = home.path().join("tmpfs");
+    let output = notes_cmd(&home)
+        .env("NOTES_UNLOCKED_DIR", &unlocked)
+        .args(["new", "Diary"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    fs::write(Path::new(&path), "secret plans").expect("write working file");
+
+    notes_cmd(&home)
+        .env("NOTES_UNLOCKED_DIR", &unlocked)
+        .env("NOTES_PASSPHRASE", "hunter2")
+        .args(["encrypt", "Diary"])
+        .assert()
+        .success();
+    assert!(!Path::new(&path).exists(), "plaintext working copy is removed");
+    let index = read_to_string(&home.path().join("index.json"));
+    assert!(
+        !index.contains(&sha256_hex(b"secret plans")),
+        "the index must not hold a plain hash of the content"
+    );
+    for entry in fs::read_dir(home.path().join("versions/diary")).expect("versions") {
+        let content = fs::read(entry.expect("entry").path()).expect("read version");
+        assert!(content.starts_with(b"NOTESENC1\n"));
+    }
+
+    notes_cmd(&home)
+        .env("NOTES_UNLOCKED_DIR", &unlocked)
+        .args(["search", "secret"])
+        .assert()
+        .success()
+        .stdout(contains("No matches").and(contains("Skipped 1 locked")));
+
+    notes_cmd(&home)
+        .env("NOTES_UNLOCKED_DIR", &unlocked)
+        .env("NOTES_PASSPHRASE", "")
+        .args(["open", "Diary"])
+        .assert()
+        .failure()
+        .stderr(contains("must not be empty"));
+    notes_cmd(&home)
+        .env("NOTES_UNLOCKED_DIR", &unlocked)
+        .env("NOTES_PASSPHRASE", "wrong")
+        .args(["open", "Diary"])
+        .assert()
+        .failure()
+        .stderr(contains("Wrong passphrase"));
+    notes_cmd(&home)
+        .env("NOTES_UNLOCKED_DIR", &unlocked)
+        .env("NOTES_PASSPHRASE", "hunter2")
+        .env("NOTES_KEY_TIMEOUT", "soon")
+        .args(["open", "Diary"])
+        .assert()
+        .failure()
+        .stderr(contains("NOTES_KEY_TIMEOUT must be a positive number of minutes"));
+    let output = notes_cmd(&home)
+        .env("NOTES_UNLOCKED_DIR", &unlocked)
+        .env("NOTES_PASSPHRASE", "hunter2")
+        .args(["open", "Diary"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let working = String::from_utf8_lossy(&output).trim().to_string();
+    assert!(working.starts_with(unlocked.to_str().unwrap()));
+    assert_eq!(read_to_string(Path::new(&working)), "secret plans");
+    for entry in fs::read_dir(&unlocked).expect("unlocked") {
+        let path = entry.expect("entry").path();
+        assert!(path.extension().is_none_or(|ext| ext != "key"), "keys stay in memory");
+    }
+
+    let hidden = format!("{}.png", "cd".repeat(32));
+    let revised = format!("secret plans, revised ![map](../attachments/{})", hidden);
+    fs::write(Path::new(&working), revised).expect("edit");
+    notes_cmd(&home)
+        .env("NOTES_UNLOCKED_DIR", &unlocked)
+        .args(["search", "revised", "--snapshot"])
+        .assert()
+        .success()
+        .stdout(contains("Diary"));
+    assert!(!read_to_string(&home.path().join("index.json")).contains(&hidden));
+
+    notes_cmd(&home)
+        .env("NOTES_UNLOCKED_DIR", &unlocked)
+        .arg("lock")
+        .assert()
+        .success()
+        .stdout(contains("Locked 1 note(s): diary"));
+    assert!(!Path::new(&working).exists(), "lock removes the plaintext");
+    let latest = home.path().join("versions/diary/0000003.md");
+    assert!(fs::read(latest).expect("read version").starts_with(b"NOTESENC1\n"));
+    notes_cmd(&home)
+        .env("NOTES_UNLOCKED_DIR", &unlocked)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(contains("[encrypted, locked]"));
+}
+
+#[test]
+fn encrypting_a_note_also_seals_its_copies_in_the_trash() {
+    let home = setup_home();
+    let unlocked = home.path().join("tmpfs");
+    let output = notes_cmd(&home)
+        .args(["new", "Diary"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    fs::write(Path::new(&path), "old secret").expect("write working file");
+    notes_cmd(&home).args(["snapshot", "Diary"]).assert().success();
+    notes_cmd(&home).args(["delete", "diary"]).assert().success();
+    notes_cmd(&home).args(["new", "Diary"]).assert().success();
+
+    notes_cmd(&home)
+        .env("NOTES_UNLOCKED_DIR", &unlocked)
+        .env("NOTES_PASSPHRASE", "hunter2")
+        .args(["encrypt", "Diary"])
+        .assert()
+        .success();
+    let mut sealed = 0;
+    for dir in ["versions", "trash"] {
+        for entry in fs::read_dir(home.path().join(dir)).expect("read dir") {
+            for file in fs::read_dir(entry.expect("entry").path()).expect("read note dir") {
+                let file = file.expect("entry").path();
+                if file.file_name().is_some_and(|name| name == "note.json") {
+                    assert!(!read_to_string(&file).contains(&sha256_hex(b"old secret")));
+                    continue;
+                }
+                let content = fs::read(&file).expect("read version");
+                assert!(content.starts_with(b"NOTESENC1\n"), "{} is sealed", file.display());
+                sealed += 1;
+            }
+        }
+    }
+    assert_eq!(sealed, 3);
+}
+
+#[test]
+fn encrypted_store_needs_the_agent_and_locks_back_to_ciphertext() {
+    let home = setup_home();
+    let unlocked = home.path().join("tmpfs");
+    let notes = |args: &[&str]| {
+        let mut cmd = notes_cmd(&home);
+        cmd.env("NOTES_UNLOCKED_DIR", &unlocked)
+            .env("NOTES_PASSPHRASE", "correct horse")
+            .args(args);
+        cmd
+    };
+    notes(&["new", "Plans"]).assert().success();
+    notes(&["bullet", "Call the bank"]).assert().success();
+
+    notes(&["encrypt-store"]).assert().success();
+    assert!(!home.path().join("index.json").exists());
+    assert!(!home.path().join("journal").exists());
+    notes(&["list"])
+        .assert()
+        .failure()
+        .stderr(contains("Run `notes unlock`"));
+
+    notes(&["unlock"]).assert().success().stdout(contains("Unlocked"));
+    notes(&["new", "Second"]).assert().success();
+    notes(&["bullet", "list"])
+        .assert()
+        .success()
+        .stdout(contains("Call the bank"));
+    notes(&["lock"])
+        .assert()
+        .success()
+        .stdout(contains("Locked the notes store"));
+    assert!(!unlocked.join("store").exists(), "lock wipes the workspace");
+    let sealed = home.path().join("sealed");
+    for entry in fs::read_dir(&sealed).expect("sealed") {
+        let content = fs::read(entry.expect("entry").path()).expect("read blob");
+        assert!(content.starts_with(b"NOTESENC1\n"));
+    }
+
+    notes(&["unlock"]).assert().success();
+    notes(&["list"])
+        .assert()
+        .success()
+        .stdout(contains("Plans").and(contains("Second")));
+    notes(&["lock"]).assert().success();
+    notes(&["lock"])
+        .assert()
+        .success()
+        .stdout(contains("store is locked"));
+
+    // Without a runtime directory the plaintext would land on disk, which needs --on-disk.
+    let on_disk = |args: &[&str]| {
+        let mut cmd = notes_cmd(&home);
+        cmd.env_remove("XDG_RUNTIME_DIR")
+            .env_remove("NOTES_UNLOCKED_DIR")
+            .env("NOTES_PASSPHRASE", "correct horse")
+            .args(args);
+        cmd
+    };
+    on_disk(&["unlock"])
+        .assert()
+        .failure()
+        .stderr(contains("--on-disk"));
+    on_disk(&["unlock", "--on-disk"])
+        .assert()
+        .success()
+        .stderr(contains("persistent disk"));
+    on_disk(&["lock"]).assert().success();
+    assert!(!home.path().join("unlocked/store").exists());
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Writes a notes archive holding `files`, as a hand-made or tampered archive would.
+fn write_archive(path: &Path, files: &[(&str, &[u8])]) {
+    let file = fs::File::create(path).expect("create archive");
+    let mut builder = tar::Builder::new(zstd::Encoder::new(file, 3).expect("zstd").auto_finish());
+    for (name, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_entry_type(tar::EntryType::Regular);
+        builder
+            .append_data(&mut header, name, *content)
+            .expect("append to archive");
+    }
+    builder.finish().expect("finish archive");
+}
+
+#[test]
+fn import_keeps_archive_paths_inside_the_store() {
+    let home = setup_home();
+    let store = home.path().join("store");
+    let manifest = r#"{"format":1,"created_at":"2026-10-01T00:00:00Z","latest_only":false,
+        "notes":[{"title":"Escape","slug":"../../escaped","created_at":"2026-10-01T00:00:00Z",
+        "versions":[{"version":1,"created_at":"2026-10-01T00:00:00Z","message":null,
+        "path":"notes/escape/1.md"}],"working":null}]}"#;
+    let archive = home.path().join("evil.tar.zst");
+    write_archive(
+        &archive,
+        &[("manifest.json", manifest.as_bytes()), ("notes/escape/1.md", b"hi")],
+    );
+    let mut import = Command::new(assert_cmd::cargo::cargo_bin!("notes"));
+    import
+        .env("NOTES_HOME", &store)
+        .env("NOTES_DISABLE_DAEMON", "1")
+        .arg("import")
+        .arg(&archive)
+        .assert()
+        .success()
+        .stdout(contains("escaped"));
+    assert!(store.join("files/escaped.md").exists());
+    assert!(!home.path().join("escaped.md").exists() && !home.path().join("escaped").exists());
+
+    let forged = format!("attachments/{}.png", "ab".repeat(32));
+    write_archive(
+        &archive,
+        &[("manifest.json", manifest.as_bytes()), (&forged, b"not that hash")],
+    );
+    notes_cmd(&home)
+        .arg("import")
+        .arg(&archive)
+        .assert()
+        .failure()
+        .stderr(contains("does not match its hash"));
+    assert!(!home.path().join(forged).exists());
+}
+
+#[test]
+fn attach_stores_file_by_hash_and_exports_it() {
+    let home = setup_home();
+    notes_cmd(&home).args(["new", "Standup"]).assert().success();
+    let screenshot = home.path().join("Screen Shot.png");
+    fs::write(&screenshot, b"\x89PNG fake image").expect("write image");
+
+    let output = notes_cmd(&home)
+        .args(["attach", "Standup"])
+        .arg(&screenshot)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let link = String::from_utf8_lossy(&output).trim().to_string();
+    assert!(link.starts_with("![Screen Shot.png](../attachments/"));
+    let name = link
+        .trim_end_matches(')')
+        .rsplit('/')
+        .next()
+        .expect("attachment name")
+        .to_string();
+    assert!(home.path().join("attachments").join(&name).exists());
+    assert!(read_to_string(&home.path().join("files/standup.md")).contains(&link));
+    notes_cmd(&home)
+        .args(["versions", "Standup"])
+        .assert()
+        .success()
+        .stdout(contains("Attach Screen Shot.png"));
+
+    let site = home.path().join("site");
+    notes_cmd(&home).args(["export", "html"]).arg(&site).assert().success();
+    assert!(site.join("attachments").join(&name).exists());
+    let page = read_to_string(&site.join("notes/standup.html"));
+    assert!(page.contains(&format!("src=\"../attachments/{}\"", name)));
+
+    let archive = home.path().join("notes.tar.zst");
+    notes_cmd(&home)
+        .args(["export", "--archive"])
+        .arg(&archive)
+        .assert()
+        .success();
+    let other = setup_home();
+    notes_cmd(&other).arg("import").arg(&archive).assert().success();
+    assert!(other.path().join("attachments").join(&name).exists());
+
+    fs::write(home.path().join("attachments/stray.pdf"), b"orphan").expect("write stray");
+    // Linked only from a working copy that has not been snapshotted yet
+    let unsaved = format!("{}.txt", "ab".repeat(32));
+    fs::write(home.path().join("attachments").join(&unsaved), b"draft").expect("write unsaved");
+    let standup = home.path().join("files/standup.md");
+    let content = read_to_string(&standup) + &format!("\n[draft](../attachments/{})\n", unsaved);
+    fs::write(&standup, content).expect("write working file");
+    notes_cmd(&home)
+        .arg("gc")
+        .assert()
+        .success()
+        .stdout(contains("Removed 1 unreferenced attachment(s), 6 bytes"));
+    assert!(home.path().join("attachments").join(&name).exists());
+    assert!(home.path().join("attachments").join(&unsaved).exists());
+}
+
+#[test]
+fn links_backlinks_and_rename_rewrites_links() {
+    let home = setup_home();
+    notes_cmd(&home).args(["new", "Roadmap"]).assert().success();
+    notes_cmd(&home).args(["new", "Standup"]).assert().success();
+    fs::write(
+        home.path().join("files/standup.md"),
+        "See [[roadmap]], [[Roadmap|the plan]] and [[Missing Page]].\n",
+    )
+    .expect("write working file");
+    notes_cmd(&home).args(["snapshot", "Standup"]).assert().success();
+
+    notes_cmd(&home)
+        .args(["links", "Standup"])
+        .assert()
+        .success()
+        .stdout(contains("[[roadmap]] -> roadmap"))
+        .stdout(contains("[[Missing Page]] (broken)"));
+    notes_cmd(&home)
+        .args(["backlinks", "Roadmap"])
+        .assert()
+        .success()
+        .stdout(contains("- Standup (id: standup)"));
+    notes_cmd(&home)
+        .args(["links", "--broken"])
+        .assert()
+        .success()
+        .stdout(contains("- Standup (id: standup) -> [[Missing Page]]"));
+
+    notes_cmd(&home)
+        .args(["rename", "Roadmap", "Product Plan"])
+        .assert()
+        .success()
+        .stdout(contains("Updated links in: standup"));
+    assert_eq!(
+        read_to_string(&home.path().join("files/standup.md")),
+        "See [[roadmap]], [[Product Plan|the plan]] and [[Missing Page]].\n"
+    );
+    notes_cmd(&home)
+        .args(["versions", "Standup"])
+        .assert()
+        .success()
+        .stdout(contains("Update links to Product Plan"));
+    notes_cmd(&home)
+        .args(["backlinks", "Product Plan"])
+        .assert()
+        .success()
+        .stdout(contains("Standup"));
+
+    notes_cmd(&home).arg("undo").assert().success();
+    notes_cmd(&home)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(contains("Roadmap (id: roadmap)"));
+}
+
+#[test]
+fn new_and_bullet_open_expand_templates() {
+    let home = setup_home();
+    notes_cmd(&home)
+        .args(["templates", "list"])
+        .assert()
+        .success()
+        .stdout(contains("No templates"));
+
+    let templates = home.path().join("templates");
+    fs::create_dir_all(&templates).expect("templates dir");
+    fs::write(
+        templates.join("meeting.md"),
+        "# {{title}}\n\nDate: {{date}} ({{week}})\nBy: {{user}}\n{{unknown}}\n",
+    )
+    .expect("write template");
+    fs::write(templates.join("daily.md"), "# {{title}}\n\n## Gratitude\n").expect("write");
+
+    notes_cmd(&home)
+        .args(["templates", "list"])
+        .assert()
+        .success()
+        .stdout(contains("daily").and(contains("meeting")));
+
+    let output = notes_cmd(&home)
+        .env("USER", "sam")
+        .args(["new", "--template", "meeting", "Sync with infra"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    let content = read_to_string(Path::new(&path));
+    assert!(content.starts_with("# Sync with infra\n\nDate: 20"));
+    assert!(content.contains("-W") && content.contains("By: sam\n{{unknown}}"));
+    let version = read_to_string(&home.path().join("versions/sync-with-infra/0000001.md"));
+    assert_eq!(version, content, "the template is version 1");
+
+    notes_cmd(&home)
+        .args(["new", "--template", "missing", "Other"])
+        .assert()
+        .failure()
+        .stderr(contains("Available: daily, meeting"));
+    fs::write(home.path().join("outside.md"), "not a template").expect("write");
+    notes_cmd(&home)
+        .args(["new", "--template", "../outside", "Other"])
+        .assert()
+        .failure()
+        .stderr(contains("Invalid template name"));
+
+    let output = notes_cmd(&home)
+        .env("USER", "sam")
+        .args(["new", "--template", "meeting", "About {{user}}"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    assert!(read_to_string(Path::new(&path)).starts_with("# About {{user}}\n"));
+
+    let output = notes_cmd(&home)
+        .args(["bullet", "open", "-d", "2026-10-05", "--template", "daily"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    assert_eq!(
+        read_to_string(Path::new(&path)),
+        "# Daily Log - 2026-10-05\n\n## Gratitude\n"
+    );
+}
+
+#[test]
+fn bullet_edits_keep_hand_written_lines() {
+    let home = setup_home();
+    let output = notes_cmd(&home)
+        .args(["bullet", "open"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let path = String::from_utf8_lossy(&output).trim().to_string();
+    let heading = read_to_string(Path::new(&path));
+    let hand_written = format!(
+        "{}Standup ran long, follow up on hiring.\n\n## Tasks\n- [ ] Renew passport {{id:aaaaaaaa0001}}\n  - bring the old one\n- [ ] Pay rent {{id:aaaaaaaa0002 created:2026-10-01T09:00:00Z mood:calm}}\n\n## Ideas\nA free-form section\n",
+        heading
+    );
+    fs::write(Path::new(&path), &hand_written).expect("write daily file");
+
+    notes_cmd(&home)
+        .args(["bullet", "Book flights"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "complete", "aaaaaaaa0001"])
+        .assert()
+        .success();
+
+    let content = read_to_string(Path::new(&path));
+    let lines: Vec<&str> = content.lines().collect();
+    assert!(content.starts_with(&format!("{}Standup ran long", heading)));
+    assert_eq!(lines.iter().filter(|line| line.contains("Book flights")).count(), 1);
+    let tasks = lines.iter().position(|line| *line == "## Tasks").expect("tasks");
+    assert_eq!(lines[tasks + 1], "- [x] Renew passport {id:aaaaaaaa0001}");
+    assert_eq!(lines[tasks + 2], "  - bring the old one");
+    assert_eq!(
+        lines[tasks + 3],
+        "- [ ] Pay rent {id:aaaaaaaa0002 created:2026-10-01T09:00:00Z mood:calm}"
+    );
+    assert!(lines[tasks + 4].starts_with("- [ ] Book flights {id:"));
+    assert!(lines[tasks + 4].contains(" created:"));
+    assert!(content.ends_with("\n## Ideas\nA free-form section\n"));
+}
+
+#[test]
+fn nested_bullets_migrate_and_complete_with_their_subtasks() {
+    let home = setup_home();
+    let output = notes_cmd(&home)
+        .args(["bullet", "open", "-d", "2026-10-05"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let source = String::from_utf8_lossy(&output).trim().to_string();
+    fs::write(
+        Path::new(&source),
+        "# Daily Log - 2026-10-05\n\n## Tasks\n- [ ] Plan trip {id:bbbbbbbb0001}\n  - [x] Pick dates {id:bbbbbbbb0002}\n  - [ ] Book hotel {id:bbbbbbbb0003}\n    - [ ] Compare prices {id:bbbbbbbb0004}\n- [ ] Water plants {id:bbbbbbbb0005}\n",
+    )
+    .expect("write daily file");
+
+    notes_cmd(&home)
+        .args(["bullet", "migrate", "--from", "2026-10-05"])
+        .assert()
+        .success()
+        .stdout(contains("Migrated 4 task(s)"));
+    let source_content = read_to_string(Path::new(&source));
+    assert!(source_content.contains("  - [>] Book hotel {id:bbbbbbbb0003}"));
+    assert!(source_content.contains("- [>] Water plants {id:bbbbbbbb0005}"));
+
+    notes_cmd(&home)
+        .args(["bullet", "list"])
+        .assert()
+        .success()
+        .stdout(contains("[ ] Plan trip").and(contains("\n    [ ] Compare prices")));
+
+    let output = notes_cmd(&home)
+        .args(["bullet", "open"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let today = String::from_utf8_lossy(&output).trim().to_string();
+    let content = read_to_string(Path::new(&today));
+    let lines: Vec<&str> = content.lines().collect();
+    let parent = lines
+        .iter()
+        .position(|line| line.starts_with("- [ ] Plan trip {id:"))
+        .expect("migrated parent");
+    assert!(lines[parent + 1].starts_with("  - [ ] Book hotel {id:"));
+    assert!(lines[parent + 2].starts_with("    - [ ] Compare prices {id:"));
+    let id = &lines[parent]["- [ ] Plan trip {id:".len()..][..12];
+
+    notes_cmd(&home)
+        .args(["bullet", "complete", id, "--subtasks"])
+        .assert()
+        .success()
+        .stdout(contains("(and 2 sub-task(s))"));
+    let content = read_to_string(Path::new(&today));
+    assert!(content.contains("\n  - [x] Book hotel {id:"));
+    assert!(content.contains("\n    - [x] Compare prices {id:"));
+}
+
+#[test]
+fn scheduled_tasks_show_in_future_log_and_arrive_in_pending() {
+    let home = setup_home();
+    for task in ["Renew insurance", "File taxes", "Plan offsite"] {
+        notes_cmd(&home).args(["bullet", task]).assert().success();
+    }
+    let output = notes_cmd(&home)
+        .args(["bullet", "open"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let today = String::from_utf8_lossy(&output).trim().to_string();
+    let content = read_to_string(Path::new(&today));
+    let id_of = |task: &str| {
+        let line = content
+            .lines()
+            .find(|line| line.contains(task))
+            .expect("task line");
+        line[line.find("{id:").expect("id") + 4..][..12].to_string()
+    };
+    let (insurance, taxes, offsite) = (
+        id_of("Renew insurance"),
+        id_of("File taxes"),
+        id_of("Plan offsite"),
+    );
+
+    notes_cmd(&home)
+        .args(["bullet", "schedule", &insurance, "--to", "2099-01-05"])
+        .assert()
+        .success()
+        .stdout(contains("Scheduled"));
+    notes_cmd(&home)
+        .args(["bullet", "schedule", &taxes, "--month", "2099-03"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "schedule", &offsite, "--to", "2001-01-01"])
+        .assert()
+        .failure()
+        .stderr(contains("past"));
+
+    let content = read_to_string(Path::new(&today));
+    assert!(content.contains(&format!("- [<] Renew insurance {{id:{} ", insurance)));
+    assert!(content.contains(" to:2099-01-05}"));
+    let copy = read_to_string(&home.path().join("journal/daily/2099-01-05.md"));
+    assert!(copy.contains(&format!("from:{}", insurance)));
+
+    notes_cmd(&home)
+        .args(["bullet", "future"])
+        .assert()
+        .success()
+        .stdout(
+            contains("[ ] Renew insurance (2099-01-05)")
+                .and(contains("[ ] File taxes (2099-03-01)")),
+        );
+    notes_cmd(&home)
+        .args(["bullet", "list"])
+        .assert()
+        .success()
+        .stdout(contains("[<] Renew insurance -> 2099-01-05"));
+
+    let output = notes_cmd(&home)
+        .args(["bullet", "open", "--monthly"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let monthly = String::from_utf8_lossy(&output).trim().to_string();
+    let this_month = Path::new(&monthly)
+        .file_stem()
+        .expect("month key")
+        .to_string_lossy()
+        .to_string();
+    notes_cmd(&home)
+        .args(["bullet", "schedule", &offsite, "--month", &this_month])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "pending"])
+        .assert()
+        .success()
+        .stdout(contains("[ ] Plan offsite").and(contains("Renew insurance").not()));
+
+    notes_cmd(&home)
+        .args(["bullet", "-d", "2001-01-01", "File old taxes"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "pending"])
+        .assert()
+        .success()
+        .stdout(contains("File old taxes"));
+    notes_cmd(&home)
+        .args(["bullet", "pending", "--days", "7"])
+        .assert()
+        .success()
+        .stdout(contains("Plan offsite").and(contains("File old taxes").not()));
+}
+
+#[test]
+fn signifiers_survive_edits_and_put_priorities_first() {
+    let home = setup_home();
+    notes_cmd(&home).args(["bullet", "Water plants"]).assert().success();
+    notes_cmd(&home)
+        .args(["bullet", "-p", "Call the bank"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "-n", "-i", "-x", "Garden", "as", "a", "classroom"])
+        .assert()
+        .success();
+
+    let output = notes_cmd(&home)
+        .args(["bullet", "open"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let today = String::from_utf8_lossy(&output).trim().to_string();
+    let content = read_to_string(Path::new(&today));
+    assert!(content.contains("- [ ] * Call the bank {id:"));
+    assert!(content.contains("- [-] !? Garden as a classroom {id:"));
+    let line = content
+        .lines()
+        .find(|line| line.contains("Water plants"))
+        .expect("task line");
+    let id = &line[line.find("{id:").expect("id") + 4..][..12];
+    notes_cmd(&home)
+        .args(["bullet", "complete", id])
+        .assert()
+        .success();
+    assert!(read_to_string(Path::new(&today)).contains("- [ ] * Call the bank {id:"));
+
+    // A line written without the `sig:` field keeps its leading `?` as text
+    let mut content = read_to_string(Path::new(&today));
+    content.push_str("- [ ] ? ask Bob {id:abcdef012345}\n");
+    fs::write(Path::new(&today), content).expect("write");
+    notes_cmd(&home)
+        .args(["bullet", "complete", "abcdef012345"])
+        .assert()
+        .success();
+    let content = read_to_string(Path::new(&today));
+    assert!(content.contains("- [x] ? ask Bob {id:abcdef012345}"));
+    assert!(
+        content
+            .lines()
+            .any(|line| line.starts_with("- [ ] * Call the bank {id:") && line.ends_with(" sig:*}"))
+    );
+
+    notes_cmd(&home)
+        .args(["bullet", "Pay rent"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "? Compare phone plans"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "list", "--priority"])
+        .assert()
+        .success()
+        .stdout(contains("* [ ] Call the bank").and(contains("Garden").not()));
+    let output = notes_cmd(&home)
+        .args(["bullet", "pending"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let pending = String::from_utf8_lossy(&output);
+    assert!(pending.starts_with("* [ ] Call the bank"));
+    assert!(pending.contains("? [ ] Compare phone plans"));
+}
+
+#[test]
+fn cancelled_and_started_tasks_follow_pending_and_reopen() {
+    let home = setup_home();
+    for task in ["Learn Esperanto", "Fix the fence"] {
+        notes_cmd(&home).args(["bullet", task]).assert().success();
+    }
+    let output = notes_cmd(&home)
+        .args(["bullet", "open"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let today = String::from_utf8_lossy(&output).trim().to_string();
+    let content = read_to_string(Path::new(&today));
+    let id_of = |task: &str| {
+        let line = content
+            .lines()
+            .find(|line| line.contains(task))
+            .expect("task line");
+        line[line.find("{id:").expect("id") + 4..][..12].to_string()
+    };
+    let (esperanto, fence) = (id_of("Learn Esperanto"), id_of("Fix the fence"));
+
+    notes_cmd(&home)
+        .args(["bullet", "cancel", &esperanto])
+        .assert()
+        .success()
+        .stdout(contains("Cancelled"));
+    notes_cmd(&home)
+        .args(["bullet", "start", &fence])
+        .assert()
+        .success();
+    let content = read_to_string(Path::new(&today));
+    assert!(content.contains(&format!("- [~] ~~Learn Esperanto~~ {{id:{} ", esperanto)));
+    assert!(content.contains(&format!("- [/] Fix the fence {{id:{} ", fence)));
+
+    notes_cmd(&home)
+        .args(["bullet", "pending"])
+        .assert()
+        .success()
+        .stdout(contains("[/] Fix the fence").and(contains("Esperanto").not()));
+    notes_cmd(&home)
+        .args(["bullet", "cancel", &esperanto])
+        .assert()
+        .failure()
+        .stderr(contains("is cancelled"));
+    notes_cmd(&home)
+        .args(["bullet", "complete", &esperanto])
+        .assert()
+        .failure()
+        .stderr(contains("is cancelled").and(contains("bullet reopen")));
+
+    notes_cmd(&home)
+        .args(["bullet", "reopen", &esperanto])
+        .assert()
+        .success();
+    assert!(read_to_string(Path::new(&today)).contains("- [ ] Learn Esperanto {id:"));
+    notes_cmd(&home).args(["undo"]).assert().success();
+    notes_cmd(&home)
+        .args(["bullet", "list"])
+        .assert()
+        .success()
+        .stdout(contains("[~] L\u{336}"));
+}
+
+#[test]
+fn monthly_migration_offers_a_choice_per_task() {
+    let home = setup_home();
+    notes_cmd(&home)
+        .args(["bullet", "-m", "-d", "2026-01-10", "Quarterly report"])
+        .assert()
+        .success();
+    for task in ["Fix bike", "Sell couch", "Old idea"] {
+        notes_cmd(&home)
+            .args(["bullet", "-d", "2026-01-12", task])
+            .assert()
+            .success();
+    }
+
+    notes_cmd(&home)
+        .args(["bullet", "migrate", "--month", "--from", "2026-01-15", "--interactive"])
+        .write_stdin("m\ns\n2099-05\nc\nk\n")
+        .assert()
+        .success()
+        .stdout(
+            contains("Migrated 1 task(s) to this month's log.")
+                .and(contains("Scheduled 1 task(s)."))
+                .and(contains("Cancelled 1 task(s).")),
+        );
+
+    let january = read_to_string(&home.path().join("journal/daily/2026-01-12.md"));
+    assert!(january.contains("- [<] Fix bike {id:"));
+    assert!(january.contains("- [~] ~~Sell couch~~ {id:"));
+    assert!(january.contains("- [ ] Old idea {id:"));
+    assert!(read_to_string(&home.path().join("journal/monthly/2026-01.md"))
+        .contains("- [>] Quarterly report {id:"));
+    assert!(read_to_string(&home.path().join("journal/monthly/2099-05.md"))
+        .contains("- [ ] Fix bike {id:"));
+
+    notes_cmd(&home)
+        .args(["bullet", "list", "--month"])
+        .assert()
+        .success()
+        .stdout(contains("[ ] Quarterly report"));
+}
+
+#[test]
+fn entries_are_found_by_id_in_any_log() {
+    let home = setup_home();
+    notes_cmd(&home).args(["bullet", "Today's task"]).assert().success();
+    let journal = home.path().join("journal");
+    fs::write(
+        journal.join("daily/2025-01-01.md"),
+        "# Daily Log - 2025-01-01\n\n## Tasks\n- [ ] Cancel old gym membership {id:bbbbbbbb0001}\n- [ ] Return library books {id:bbbbbbbb0002}\n",
+    )
+    .expect("write old daily log");
+    fs::write(
+        journal.join("monthly/2026-01.md"),
+        "# Monthly Log - 2026-01\n\n## Tasks\n- [ ] Review budget {id:cccccccc0001}\n",
+    )
+    .expect("write monthly log");
+
+    notes_cmd(&home)
+        .args(["bullet", "complete", "bbbbbbbb"])
+        .assert()
+        .failure()
+        .stderr(contains("Ambiguous"));
+    notes_cmd(&home)
+        .args(["bullet", "complete", "bbbbbbbb0001"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "cancel", "cccccccc"])
+        .assert()
+        .success();
+    assert!(read_to_string(&journal.join("daily/2025-01-01.md"))
+        .contains("- [x] Cancel old gym membership {id:bbbbbbbb0001}"));
+    assert!(read_to_string(&journal.join("monthly/2026-01.md"))
+        .contains("- [~] ~~Review budget~~ {id:cccccccc0001}"));
+
+    notes_cmd(&home)
+        .args(["bullet", "search", "library"])
+        .assert()
+        .success()
+        .stdout(contains("Return library books"));
+    notes_cmd(&home)
+        .args(["bullet", "ids"])
+        .assert()
+        .success()
+        .stdout(contains("bbbbbbbb\t(Return library books)").and(contains("Today's task")));
+
+    fs::remove_file(journal.join("daily/2025-01-01.md")).expect("remove old daily log");
+    notes_cmd(&home)
+        .args(["bullet", "complete", "bbbbbbbb0002"])
+        .assert()
+        .failure()
+        .stderr(contains("Entry not found"));
+}
+
+#[test]
+fn entries_can_be_edited_moved_retyped_and_deleted() {
+    let home = setup_home();
+    notes_cmd(&home).args(["bullet", "Plan tirp"]).assert().success();
+    let output = notes_cmd(&home)
+        .args(["bullet", "open"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let today = String::from_utf8_lossy(&output).trim().to_string();
+    let content = read_to_string(Path::new(&today));
+    let line = content.lines().find(|line| line.contains("Plan tirp")).expect("task line");
+    let id = line[line.find("{id:").expect("id") + 4..][..12].to_string();
+    fs::write(
+        Path::new(&today),
+        format!(
+            "{}  - [ ] Book hotel {{id:eeeeeeee0001}}\n    near the station\n- [ ] Team dinner {{id:eeeeeeee0002}}\n",
+            content
+        ),
+    )
+    .expect("write daily log");
+
+    notes_cmd(&home)
+        .args(["bullet", "edit", &id, "*", "Plan", "trip"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "move", "eeee", "--monthly"])
+        .assert()
+        .failure()
+        .stderr(contains("Ambiguous").and(contains("eeeeeeee0001  ")).and(contains("Team dinner")));
+    notes_cmd(&home)
+        .args(["bullet", "retype", "eeeeeeee0002", "--event"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "move", &id, "--date", "2099-02-03"])
+        .assert()
+        .success()
+        .stdout(contains("Moved"));
+
+    let content = read_to_string(Path::new(&today));
+    assert!(!content.contains("Plan trip") && !content.contains("Book hotel"));
+    assert!(content.contains("## Events\n- [o] Team dinner {id:eeeeeeee0002}"));
+    let moved = read_to_string(&home.path().join("journal/daily/2099-02-03.md"));
+    assert!(moved.contains(&format!("- [ ] * Plan trip {{id:{} ", id)));
+    assert!(moved.contains("\n  - [ ] Book hotel {id:eeeeeeee0001}\n    near the station\n"));
+
+    notes_cmd(&home)
+        .args(["bullet", "complete", "eeeeeeee0001"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "rm", &id])
+        .assert()
+        .success()
+        .stdout(contains("and 1 sub-entries"));
+    assert!(!read_to_string(&home.path().join("journal/daily/2099-02-03.md")).contains("Book hotel"));
+    notes_cmd(&home)
+        .args(["bullet", "search", "hotel"])
+        .assert()
+        .success()
+        .stdout(contains("Book hotel").not());
+}
+
+#[test]
+fn recurring_entries_are_added_to_current_and_upcoming_logs() {
+    let home = setup_home();
+    notes_cmd(&home)
+        .args(["bullet", "add", "Standup", "-e", "--every", "day"])
+        .assert()
+        .success()
+        .stdout(contains("Added recurring event: Standup, every day"));
+    notes_cmd(&home)
+        .args(["bullet", "add", "Monthly report", "--every", "1st-of-month"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "add", "Standup", "--every", "fortnight"])
+        .assert()
+        .failure()
+        .stderr(contains("Unknown recurrence"));
+
+    let output = notes_cmd(&home)
+        .args(["bullet", "recur", "list"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let listing = String::from_utf8_lossy(&output).to_string();
+    let standup = listing
+        .lines()
+        .find(|line| line.contains("Standup  every day"))
+        .expect("standup recurrence")[..8]
+        .to_string();
+
+    let output = notes_cmd(&home)
+        .args(["bullet", "open"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let today = String::from_utf8_lossy(&output).trim().to_string();
+    notes_cmd(&home).args(["bullet", "list"]).assert().success();
+    let content = read_to_string(Path::new(&today));
+    assert_eq!(content.matches("] Standup {id:").count(), 1);
+    assert!(content.contains(&format!(" recur:{}", standup)));
+
+    // A deleted entry is not added again to the same log
+    let line = content.lines().find(|line| line.contains("Standup")).expect("standup");
+    let id = line[line.find("{id:").expect("id") + 4..][..12].to_string();
+    notes_cmd(&home).args(["bullet", "rm", &id]).assert().success();
+    notes_cmd(&home).args(["bullet", "list"]).assert().success();
+    assert!(!read_to_string(Path::new(&today)).contains("Standup"));
+
+    notes_cmd(&home)
+        .args(["bullet", "list", "--date", "2099-03-01"])
+        .assert()
+        .success()
+        .stdout(contains("[o] Standup").and(contains("[ ] Monthly report")));
+    notes_cmd(&home)
+        .args(["bullet", "list", "--date", "2099-03-02"])
+        .assert()
+        .success()
+        .stdout(contains("Standup").and(contains("Monthly report").not()));
+    notes_cmd(&home)
+        .args(["bullet", "list", "--date", "2001-03-01"])
+        .assert()
+        .success()
+        .stdout(contains("No entries."));
+
+    notes_cmd(&home)
+        .args(["bullet", "recur", "pause", &standup])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "list", "--date", "2099-03-03"])
+        .assert()
+        .success()
+        .stdout(contains("No entries."));
+    notes_cmd(&home)
+        .args(["bullet", "recur"])
+        .assert()
+        .success()
+        .stdout(contains("every day from").and(contains("(paused)")));
+    notes_cmd(&home)
+        .args(["bullet", "recur", "rm", &standup])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "recur"])
+        .assert()
+        .success()
+        .stdout(contains("Standup").not());
+    assert!(read_to_string(&home.path().join("journal/daily/2099-03-01.md")).contains("Standup"));
+}
+
+#[test]
+fn journal_dates_accept_relative_days_weeks_and_months() {
+    use chrono::Datelike;
+
+    let home = setup_home();
+    let today = chrono::Utc::now().date_naive();
+    let journal = home.path().join("journal");
+
+    notes_cmd(&home)
+        .args(["bullet", "--date", "+3d", "Call the plumber"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "add", "Old receipts", "-d", "-2w"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "add", "Dentist", "-e", "--date", "next monday"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "add", "Retro", "--date", "2026-W42"])
+        .assert()
+        .success();
+    notes_cmd(&home)
+        .args(["bullet", "add", "Budget", "--date", "2026-11"])
+        .assert()
+        .success();
+
+    let day_file = |date: chrono::NaiveDate| {
+        read_to_string(&journal.join(format!("daily/{}.md", date.format("%Y-%m-%d"))))
+    };
+    assert!(day_file(today + chrono::Duration::days(3)).contains("Call the plumber"));
+    assert!(day_file(today - chrono::Duration::days(14)).contains("Old receipts"));
+    let next_monday = today + chrono::Duration::days(7 - today.weekday().num_days_from_monday() as i64);
+    assert!(day_file(next_monday).contains("- [o] Dentist"));
+    assert!(read_to_string(&journal.join("weekly/2026-W42.md")).contains("Retro"));
+    assert!(read_to_string(&journal.join("monthly/2026-11.md")).contains("Budget"));
+
+    notes_cmd(&home)
+        .args(["bullet", "list", "--date", "2026-W42"])
+        .assert()
+        .success()
+        .stdout(contains("Retro"));
+    notes_cmd(&home)
+        .args(["bullet", "list", "--date", "in 3 days"])
+        .assert()
+        .success()
+        .stdout(contains("Call the plumber"));
+
+    let content = day_file(today + chrono::Duration::days(3));
+    let line = content.lines().find(|line| line.contains("plumber")).expect("task line");
+    let id = line[line.find("{id:").expect("id") + 4..][..12].to_string();
+    notes_cmd(&home)
+        .args(["bullet", "schedule", &id, "--to", "in 2 weeks"])
+        .assert()
+        .success();
+    assert!(day_file(today + chrono::Duration::days(14)).contains("Call the plumber"));
+
+    notes_cmd(&home)
+        .args(["bullet", "list", "--date", "next fortnight"])
+        .assert()
+        .failure()
+        .stderr(contains("Unrecognized date").and(contains("next monday")));
+}